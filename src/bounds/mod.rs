@@ -1,11 +1,26 @@
 use std::cmp::{min, max};
+use std::collections::BTreeSet;
+use std::fs::read_to_string;
+
+use super::witness::Witnesses;
+
+/// A line of a bounds file: `waste = permutations proven|claimed [witness]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub waste: usize,
+    pub permutations: usize,
+    pub proven: bool,
+    pub witness: Option<Vec<u8>>,
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bounds {
     pub lower_bounds: Vec<usize>,
     pub upper_bounds: Vec<usize>,
     pub thresholds: Vec<usize>,
     pub max: usize,
+    pub claimed: BTreeSet<usize>,
+    pub seeded: usize,
 }
 
 impl Bounds {
@@ -17,13 +32,98 @@ impl Bounds {
             upper_bounds: vec![factorial],
             thresholds: vec![0],
             max: factorial,
+            claimed: BTreeSet::new(),
+            seeded: 0,
+        }
+    }
+
+    /// Reads a bounds file with one line per number of wasted symbols,
+    /// starting from zero. A repeated line for the same waste replaces the one
+    /// before it.
+    pub fn read(path: &str, n: usize) -> Result<Self, Vec<String>> {
+        let text = read_to_string(path)
+            .map_err(|e| vec![format!("{}: {}", path, e)])?;
+
+        Self::parse(&text, n).map_err(|errors| {
+            errors.into_iter().map(|e| format!("{}: {}", path, e)).collect()
+        })
+    }
+
+    pub fn parse(text: &str, n: usize) -> Result<Self, Vec<String>> {
+        let mut bounds = Self::new(n);
+        let mut errors = vec![];
+
+        for (number, entry) in Self::entries(text) {
+            let result = entry.and_then(|e| bounds.seed(e.waste, e.permutations, e.proven));
+
+            if let Err(error) = result {
+                errors.push(format!("line {}: {}", number, error));
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(bounds),
+            false => Err(errors),
+        }
+    }
+
+    /// Parses each line of a bounds file that isn't blank or a comment, along
+    /// with its line number.
+    pub fn entries(text: &str) -> Vec<(usize, Result<Entry, String>)> {
+        text.lines().enumerate()
+            .map(|(number, line)| (number + 1, line.split('#').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(number, line)| (number, Entry::parse(line)))
+            .collect()
+    }
+
+    // A line for the same waste as the line before it replaces that line, so a
+    // witnesses file that raised a bound several times can be read back.
+    fn seed(&mut self, index: usize, bound: usize, proven: bool) -> Result<(), String> {
+        let repeated = self.seeded > 0 && index == self.seeded - 1;
+
+        if !repeated {
+            let expected = self.seeded;
+            self.seeded += 1;
+
+            if index != expected {
+                return Err(format!("expected the bound for {} wasted symbols (got {})", expected, index));
+            }
+        }
+
+        if bound > self.max {
+            return Err(format!("{} permutations is more than {}", bound, self.max));
+        }
+
+        if repeated && self.lower_bounds.get(index).is_some_and(|&b| bound < b) {
+            return Err(format!("{} permutations is less than the line before it", bound));
+        }
+
+        if index > 0 && self.lower_bounds.get(index - 1).is_some_and(|&b| bound < b) {
+            return Err(format!("{} permutations is less than the bound for {} wasted symbols", bound, index - 1));
+        }
+
+        self.update(index, bound);
+
+        if bound > self.upper_bounds[index] {
+            return Err(format!("{} permutations is more than the upper bound of {}", bound, self.upper_bounds[index]));
+        }
+
+        match proven {
+            true => self.fix_upper_bound(index),
+            false => { self.claimed.insert(index); },
         }
+
+        Ok(())
     }
 
     pub fn update(&mut self, index: usize, bound: usize) -> bool {
+        if self.lower_bounds.get(index).is_some_and(|&b| bound >= b) {
+            self.claimed.remove(&index);
+        }
+
         if self.lower_bounds.len() <= index {
             self.add_new_index(index, bound);
-            println!("{} wasted symbols: at most {} permutations", index - 1, bound);
             return true;
         }
 
@@ -43,6 +143,20 @@ impl Bounds {
         *self.lower_bounds.last().unwrap() == self.max
     }
 
+    pub fn proven(&self) -> &[usize] {
+        let last = self.lower_bounds.len() - 1;
+
+        let finished = match self.found_for_superpermutation() || last < self.seeded {
+            true => last + 1,
+            false => last,
+        };
+
+        match self.claimed.iter().next() {
+            Some(&claim) => &self.lower_bounds[..min(finished, claim)],
+            None => &self.lower_bounds[..finished],
+        }
+    }
+
     fn add_new_index(&mut self, index: usize, bound: usize) {
         let previous_len = self.lower_bounds.len();
         let last_bound = *self.lower_bounds.last().unwrap();
@@ -93,5 +207,35 @@ impl Bounds {
     }
 }
 
+impl Entry {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let expected = || "expected 'waste = permutations proven' or 'waste = permutations claimed'".to_string();
+
+        let (waste, rest) = line.split_once('=').ok_or_else(expected)?;
+        let mut words = rest.split_whitespace();
+
+        let (permutations, status, witness) = match (words.next(), words.next(), words.next(), words.next()) {
+            (Some(p), Some(s), w, None) => (p, s, w),
+            _ => return Err(expected()),
+        };
+
+        let waste = waste.trim().parse().map_err(|_| format!("invalid waste '{}'", waste.trim()))?;
+        let permutations = permutations.parse().map_err(|_| format!("invalid permutations '{}'", permutations))?;
+
+        let proven = match status {
+            "proven" => true,
+            "claimed" => false,
+            _ => return Err(format!("invalid status '{}' (expected proven or claimed)", status)),
+        };
+
+        let witness = match witness {
+            Some(w) => Some(Witnesses::parse(w).ok_or_else(|| format!("invalid witness '{}'", w))?),
+            None => None,
+        };
+
+        Ok(Self { waste, permutations, proven, witness })
+    }
+}
+
 #[cfg(test)]
 mod test;
@@ -40,11 +40,11 @@ mod update {
     fn it_returns_true_if_the_bounds_updated() {
         let mut subject = Subject::new(N);
 
-        assert_eq!(subject.update(0, 0), false);
-        assert_eq!(subject.update(0, 1), true);
-        assert_eq!(subject.update(0, 1), false);
-        assert_eq!(subject.update(0, 2), true);
-        assert_eq!(subject.update(0, 3), true);
+        assert!(!subject.update(0, 0));
+        assert!(subject.update(0, 1));
+        assert!(!subject.update(0, 1));
+        assert!(subject.update(0, 2));
+        assert!(subject.update(0, 3));
     }
 
     mod when_the_index_is_larger_than_the_array {
@@ -95,8 +95,8 @@ mod update {
 
             subject.update(0, 5);
 
-            assert_eq!(subject.update(1, 3), true);
-            assert_eq!(subject.update(2, 3), true);
+            assert!(subject.update(1, 3));
+            assert!(subject.update(2, 3));
         }
 
         #[test]
@@ -201,12 +201,110 @@ mod found_for_superpermutation {
     #[test]
     fn it_returns_true_if_the_last_lower_bound_is_equal_to_factorial_n() {
         let mut subject = Subject::new(N);
-        assert_eq!(subject.found_for_superpermutation(), false);
+        assert!(!subject.found_for_superpermutation());
 
         subject.update(0, 119);
-        assert_eq!(subject.found_for_superpermutation(), false);
+        assert!(!subject.found_for_superpermutation());
 
         subject.update(0, 120);
-        assert_eq!(subject.found_for_superpermutation(), true);
+        assert!(subject.found_for_superpermutation());
+    }
+}
+
+mod proven {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_lower_bounds_for_every_phase_that_has_finished() {
+        let mut subject = Subject::new(N);
+        assert_eq!(subject.proven(), &[] as &[usize]);
+
+        subject.update(0, 5);
+        assert_eq!(subject.proven(), &[] as &[usize]);
+
+        subject.update(1, 5);
+        subject.update(1, 10);
+        assert_eq!(subject.proven(), &[5]);
+
+        subject.update(3, 15);
+        assert_eq!(subject.proven(), &[5, 10, 15]);
+    }
+
+    #[test]
+    fn it_includes_the_last_lower_bound_once_all_permutations_are_found() {
+        let mut subject = Subject::new(N);
+
+        subject.update(0, 100);
+        subject.update(1, 120);
+
+        assert_eq!(subject.proven(), &[100, 120]);
+    }
+}
+
+mod parse {
+    use super::*;
+
+    #[test]
+    fn it_seeds_the_bounds_with_the_proven_results() {
+        let subject = Subject::parse("0 = 5 proven\n1 = 10 proven # comment\n\n2 = 15 proven", N).unwrap();
+
+        assert_eq!(subject.lower_bounds, &[5, 10, 15]);
+        assert_eq!(subject.upper_bounds, &[5, 10, 15]);
+        assert_eq!(subject.thresholds, &[0, 5, 10]);
+        assert_eq!(subject.seeded, 3);
+        assert_eq!(subject.proven(), &[5, 10, 15]);
+    }
+
+    #[test]
+    fn it_does_not_treat_claimed_results_as_proven_until_they_are_found() {
+        let mut subject = Subject::parse("0 = 5 proven\n1 = 10 claimed\n2 = 15 proven", N).unwrap();
+
+        assert_eq!(subject.lower_bounds, &[5, 10, 15]);
+        assert_eq!(subject.proven(), &[5]);
+
+        assert!(!subject.update(1, 9));
+        assert_eq!(subject.proven(), &[5]);
+
+        assert!(!subject.update(1, 10));
+        assert_eq!(subject.proven(), &[5, 10, 15]);
+    }
+
+    #[test]
+    fn it_leaves_the_upper_bound_open_for_a_claimed_result() {
+        let subject = Subject::parse("0 = 5 proven\n1 = 9 claimed", N).unwrap();
+
+        assert_eq!(subject.lower_bounds, &[5, 9]);
+        assert_eq!(subject.upper_bounds, &[5, 10]);
+    }
+
+    #[test]
+    fn it_keeps_the_last_line_for_a_repeated_waste() {
+        let subject = Subject::parse("0 = 3 claimed\n0 = 5 claimed\n1 = 7 claimed\n1 = 9 claimed", N).unwrap();
+
+        assert_eq!(subject.lower_bounds, &[5, 9]);
+        assert_eq!(subject.seeded, 2);
+        assert!(subject.proven().is_empty());
+    }
+
+    #[test]
+    fn it_rejects_a_repeated_line_with_a_lower_bound() {
+        let errors = Subject::parse("0 = 5 claimed\n0 = 4 claimed", N).unwrap_err();
+
+        assert_eq!(errors, vec!["line 2: 4 permutations is less than the line before it"]);
+    }
+
+    #[test]
+    fn it_reports_every_problem_with_its_line_number() {
+        let text = "0 = 5 proven\n1 = 4 proven\n3 = 10 proven\n3 = 500 claimed\n4 = 20 maybe\n5\n6 = x proven";
+        let errors = Subject::parse(text, N).unwrap_err();
+
+        assert_eq!(errors, vec![
+            "line 2: 4 permutations is less than the bound for 0 wasted symbols",
+            "line 3: expected the bound for 2 wasted symbols (got 3)",
+            "line 4: 500 permutations is more than 120",
+            "line 5: invalid status 'maybe' (expected proven or claimed)",
+            "line 6: expected 'waste = permutations proven' or 'waste = permutations claimed'",
+            "line 7: invalid permutations 'x'",
+        ]);
     }
 }
@@ -1,5 +1,8 @@
+mod path;
 mod serialize;
 
+pub use self::path::Path;
+
 use bit_set::BitSet;
 use lehmer::Lehmer;
 use std::iter::once;
@@ -10,6 +13,7 @@ pub struct Candidate {
     pub permutations_seen: BitSet,
     pub tail_of_string: Vec<u8>,
     pub wasted_symbols: u16,
+    pub path: Path,
 }
 
 impl Candidate {
@@ -23,16 +27,20 @@ impl Candidate {
             permutations_seen: seen,
             tail_of_string: (1..n as u8).collect(),
             wasted_symbols: 0,
+            path: Path::default(),
         }
     }
 
-    pub fn expand(self, upper_bound: usize, n: usize) -> impl Iterator<Item=Self> {
+    /// The children of the candidate. They only remember the choices that
+    /// built them if `paths` is set, so without it they can't rebuild their
+    /// strings.
+    pub fn expand(self, upper_bound: usize, n: usize, paths: bool) -> impl Iterator<Item=Self> {
         let last_symbol = *self.tail_of_string.last().unwrap();
         let at_upper_bound = self.number_of_permutations() == upper_bound;
 
         (0..n as u8)
             .filter(move |&s| s != last_symbol)
-            .map(move |s| self.expand_one(s, at_upper_bound, n))
+            .map(move |s| self.expand_one(s, at_upper_bound, n, paths))
     }
 
     pub fn number_of_permutations(&self) -> usize {
@@ -47,23 +55,44 @@ impl Candidate {
         self.wasted_symbols as usize + self.future_waste(n)
     }
 
-    fn expand_one(&self, symbol: u8, at_upper_bound: bool, n: usize) -> Self {
+    /// Rebuilds the full string by replaying the choices made since the seed.
+    pub fn string(&self, n: usize) -> Vec<u8> {
+        let mut string: Vec<u8> = (0..n as u8).collect();
+
+        for choice in self.path.choices(n) {
+            let last_symbol = *string.last().unwrap();
+
+            string.push(match choice < last_symbol {
+                true => choice,
+                false => choice + 1,
+            });
+        }
+
+        string
+    }
+
+    fn expand_one(&self, symbol: u8, at_upper_bound: bool, n: usize, paths: bool) -> Self {
         let tail_of_string = self.build_tail(symbol, n);
 
+        let path = match paths {
+            true => self.build_path(symbol, n),
+            false => Path::default(),
+        };
+
         if Self::less_than_full(&self.tail_of_string, n) {
-            return self.candidate_with_wasted_symbol(tail_of_string, 1);
+            return self.candidate_with_wasted_symbol(tail_of_string, path, 1);
         }
 
         if Self::less_than_full(&tail_of_string, n) {
-            return self.candidate_with_wasted_symbol(tail_of_string, 1);
+            return self.candidate_with_wasted_symbol(tail_of_string, path, 1);
         }
 
         if self.tail_starts_with(symbol) {
-            return self.candidate_with_wasted_symbol(tail_of_string, 1);
+            return self.candidate_with_wasted_symbol(tail_of_string, path, 1);
         }
 
         if at_upper_bound {
-            return self.candidate_with_wasted_symbol(tail_of_string, 1);
+            return self.candidate_with_wasted_symbol(tail_of_string, path, 1);
         }
 
         let id = Self::permutation_id(&self.tail_of_string, symbol);
@@ -74,26 +103,27 @@ impl Candidate {
                 true => 2,
             };
 
-            return self.candidate_with_wasted_symbol(tail_of_string, penalty);
+            return self.candidate_with_wasted_symbol(tail_of_string, path, penalty);
         }
 
-        self.candidate_with_new_permutation(tail_of_string, id)
+        self.candidate_with_new_permutation(tail_of_string, path, id)
     }
 
-    fn candidate_with_wasted_symbol(&self, tail_of_string: Vec<u8>, penalty: usize) -> Self {
+    fn candidate_with_wasted_symbol(&self, tail_of_string: Vec<u8>, path: Path, penalty: usize) -> Self {
         Candidate {
             permutations_seen: self.permutations_seen.clone(),
-            tail_of_string: tail_of_string,
+            tail_of_string,
             wasted_symbols: self.wasted_symbols + penalty as u16,
+            path,
         }
     }
 
-    fn candidate_with_new_permutation(&self, tail_of_string: Vec<u8>, id: usize) -> Self {
+    fn candidate_with_new_permutation(&self, tail_of_string: Vec<u8>, path: Path, id: usize) -> Self {
         let mut permutations_seen = self.permutations_seen.clone();
         permutations_seen.insert(id);
 
         let wasted_symbols = self.wasted_symbols;
-        Candidate { permutations_seen, tail_of_string, wasted_symbols }
+        Candidate { permutations_seen, tail_of_string, wasted_symbols, path }
     }
 
     fn less_than_full(tail_of_string: &[u8], n: usize) -> bool {
@@ -108,7 +138,7 @@ impl Candidate {
     fn permutation_id(tail_of_string: &[u8], symbol: u8) -> usize {
         let permutation = tail_of_string
             .iter()
-            .map(|&e| e)
+            .copied()
             .chain(once(symbol))
             .collect();
 
@@ -129,6 +159,18 @@ impl Candidate {
         Self::append(&head[index..], symbol)
     }
 
+    // The symbol can't repeat the last one, so the symbols above it shift down.
+    fn build_path(&self, symbol: u8, n: usize) -> Path {
+        let last_symbol = *self.tail_of_string.last().unwrap();
+
+        let choice = match symbol < last_symbol {
+            true => symbol,
+            false => symbol - 1,
+        };
+
+        self.path.push(choice, n)
+    }
+
     fn seen_next_tail_as_well(&self, tail_of_string: &[u8], n: usize) -> bool {
         let mut symbols_in_tail = vec![false; n];
 
@@ -147,7 +189,7 @@ impl Candidate {
     }
 
     fn append(slice: &[u8], symbol: u8) -> Vec<u8> {
-        slice.iter().map(|&e| e).chain(once(symbol)).collect()
+        slice.iter().copied().chain(once(symbol)).collect()
     }
 }
 
@@ -0,0 +1,64 @@
+// The symbols appended to the seed, packed into as few bits as possible. Each
+// step can't repeat the previous symbol so there are n - 1 choices per step.
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Path {
+    #[serde(with = "serde_bytes")]
+    bytes: Vec<u8>,
+    len: u32,
+}
+
+impl Path {
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The packed choices, for codecs that store them as they are.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, len: usize) -> Self {
+        Self { bytes, len: len as u32 }
+    }
+
+    pub fn push(&self, choice: u8, n: usize) -> Self {
+        let bits = Self::bits_per_choice(n);
+        let offset = self.len() * bits;
+
+        let mut bytes = Vec::with_capacity((offset + bits).div_ceil(8));
+        bytes.extend_from_slice(&self.bytes);
+        bytes.resize((offset + bits).div_ceil(8), 0);
+
+        for bit in 0..bits {
+            if choice & (1 << bit) != 0 {
+                let position = offset + bit;
+                bytes[position / 8] |= 1 << (position % 8);
+            }
+        }
+
+        Self { bytes, len: self.len + 1 }
+    }
+
+    pub fn choices(&self, n: usize) -> impl Iterator<Item=u8> + '_ {
+        let bits = Self::bits_per_choice(n);
+
+        (0..self.len()).map(move |i| {
+            (0..bits).fold(0, |choice, bit| {
+                let position = i * bits + bit;
+                let set = self.bytes[position / 8] & (1 << (position % 8)) != 0;
+
+                choice | ((set as u8) << bit)
+            })
+        })
+    }
+
+    pub fn bits_per_choice(n: usize) -> usize {
+        let choices = n.saturating_sub(1).max(2);
+        (usize::BITS - (choices - 1).leading_zeros()) as usize
+    }
+}
@@ -1,5 +1,5 @@
 use super::*;
-use std::usize::MAX;
+const MAX: usize = usize::MAX;
 
 type Subject = Candidate;
 
@@ -13,8 +13,8 @@ mod seed {
     fn it_has_seen_the_first_permutation() {
         let subject = Subject::seed(N);
 
-        assert_eq!(subject.permutations_seen.contains(0), true);
-        assert_eq!(subject.permutations_seen.contains(1), false);
+        assert!(subject.permutations_seen.contains(0));
+        assert!(!subject.permutations_seen.contains(1));
     }
 
     #[test]
@@ -38,7 +38,7 @@ mod expand {
     #[test]
     fn it_expands_all_candidates_except_for_the_last_symbol_of_the_tail() {
         let subject = Subject::seed(N);
-        let candidates: Vec<Subject> = subject.expand(MAX, N).collect();
+        let candidates: Vec<Subject> = subject.expand(MAX, N, true).collect();
 
         assert_eq!(candidates.len(), 4);
 
@@ -69,19 +69,19 @@ mod expand_one {
         #[test]
         fn it_has_seen_the_new_permutation() {
             let subject = Subject::seed(N);
-            let candidate = subject.expand_one(0, F, N);
+            let candidate = subject.expand_one(0, F, N, true);
 
             let lehmer = Lehmer::from_permutation(vec![1, 2, 3, 4, 0]);
             let decimal = lehmer.to_decimal() as usize;
 
-            assert_eq!(candidate.permutations_seen.contains(decimal), true);
+            assert!(candidate.permutations_seen.contains(decimal));
             assert_eq!(candidate.permutations_seen.len(), 2);
         }
 
         #[test]
         fn it_builds_a_tail_from_the_end_of_the_permutation() {
             let subject = Subject::seed(N);
-            let candidate = subject.expand_one(0, F, N);
+            let candidate = subject.expand_one(0, F, N, true);
 
             assert_eq!(candidate.tail_of_string, &[2, 3, 4, 0]);
         }
@@ -89,7 +89,7 @@ mod expand_one {
         #[test]
         fn it_has_no_additional_wasted_symbols() {
             let subject = Subject::seed(N);
-            let candidate = subject.expand_one(0, F, N);
+            let candidate = subject.expand_one(0, F, N, true);
 
             assert_eq!(candidate.wasted_symbols, 0);
         }
@@ -101,7 +101,7 @@ mod expand_one {
         #[test]
         fn it_has_not_seen_any_new_permutations() {
             let subject = Subject::seed(N);
-            let candidate = subject.expand_one(3, F, N);
+            let candidate = subject.expand_one(3, F, N, true);
 
             assert_eq!(candidate.permutations_seen.len(), 1);
         }
@@ -110,16 +110,16 @@ mod expand_one {
         fn it_builds_a_tail_after_the_repeated_symbol_in_the_previous_tail() {
             let subject = Subject::seed(N);
 
-            let candidate = subject.expand_one(1, F, N);
+            let candidate = subject.expand_one(1, F, N, true);
             assert_eq!(candidate.tail_of_string, &[2, 3, 4, 1]);
 
-            let candidate = subject.expand_one(2, F, N);
+            let candidate = subject.expand_one(2, F, N, true);
             assert_eq!(candidate.tail_of_string, &[3, 4, 2]);
 
-            let candidate = subject.expand_one(3, F, N);
+            let candidate = subject.expand_one(3, F, N, true);
             assert_eq!(candidate.tail_of_string, &[4, 3]);
 
-            let candidate = subject.expand_one(4, F, N);
+            let candidate = subject.expand_one(4, F, N, true);
             assert_eq!(candidate.tail_of_string, &[4]);
         }
 
@@ -127,10 +127,10 @@ mod expand_one {
         fn it_has_one_additional_wasted_symbol() {
             let subject = Subject::seed(N);
 
-            let candidate = subject.expand_one(3, F, N);
+            let candidate = subject.expand_one(3, F, N, true);
             assert_eq!(candidate.wasted_symbols, 1);
 
-            let candidate = candidate.expand_one(3, F, N);
+            let candidate = candidate.expand_one(3, F, N, true);
             assert_eq!(candidate.wasted_symbols, 2);
         }
 
@@ -142,16 +142,16 @@ mod expand_one {
                 let subject = Subject::seed(N);
 
                 // Waste a symbol after the first permutation.
-                let candidate = subject.expand_one(3, F, N);
+                let candidate = subject.expand_one(3, F, N, true);
                 assert_eq!(candidate.wasted_symbols, 1);
 
-                let candidate = candidate.expand_one(0, F, N);
-                let candidate = candidate.expand_one(1, F, N);
-                let candidate = candidate.expand_one(2, F, N);
-                let candidate = candidate.expand_one(3, F, N);
+                let candidate = candidate.expand_one(0, F, N, true);
+                let candidate = candidate.expand_one(1, F, N, true);
+                let candidate = candidate.expand_one(2, F, N, true);
+                let candidate = candidate.expand_one(3, F, N, true);
                 assert_eq!(candidate.wasted_symbols, 4);
 
-                let candidate = candidate.expand_one(4, F, N);
+                let candidate = candidate.expand_one(4, F, N, true);
                 assert_eq!(candidate.wasted_symbols, 5);
             }
 
@@ -164,14 +164,14 @@ mod expand_one {
 
                     // No symbol is wasted here...
 
-                    let candidate = subject.expand_one(0, F, N);
-                    let candidate = candidate.expand_one(1, F, N);
-                    let candidate = candidate.expand_one(2, F, N);
-                    let candidate = candidate.expand_one(3, F, N);
+                    let candidate = subject.expand_one(0, F, N, true);
+                    let candidate = candidate.expand_one(1, F, N, true);
+                    let candidate = candidate.expand_one(2, F, N, true);
+                    let candidate = candidate.expand_one(3, F, N, true);
                     assert_eq!(candidate.wasted_symbols, 0);
 
                     // ... and the only choice after 4 (0) is already taken.
-                    let candidate = candidate.expand_one(4, F, N);
+                    let candidate = candidate.expand_one(4, F, N, true);
 
                     // So we penalise by an extra symbol of waste:
                     assert_eq!(candidate.wasted_symbols, 2);
@@ -188,7 +188,7 @@ mod expand_one {
             let subject = Subject::seed(N);
             let at_upper_bound = true;
 
-            let candidate = subject.expand_one(0, at_upper_bound, N);
+            let candidate = subject.expand_one(0, at_upper_bound, N, true);
 
             assert_eq!(candidate.permutations_seen.len(), 1);
             assert_eq!(candidate.wasted_symbols, 1);
@@ -205,27 +205,27 @@ mod expand_one {
             assert_eq!(subject.tail_of_string, &[1, 2, 3, 4]);
             assert_eq!(subject.wasted_symbols, 0);
 
-            let depth_1 = subject.expand_one(1, F, N);
+            let depth_1 = subject.expand_one(1, F, N, true);
             assert_eq!(depth_1.permutations_seen.len(), 1);
             assert_eq!(depth_1.tail_of_string, &[2, 3, 4, 1]);
             assert_eq!(depth_1.wasted_symbols, 1);
 
-            let depth_2 = depth_1.expand_one(0, F, N);
+            let depth_2 = depth_1.expand_one(0, F, N, true);
             assert_eq!(depth_2.permutations_seen.len(), 2);
             assert_eq!(depth_2.tail_of_string, &[3, 4, 1, 0]);
             assert_eq!(depth_2.wasted_symbols, 1);
 
-            let depth_3 = depth_2.expand_one(4, F, N);
+            let depth_3 = depth_2.expand_one(4, F, N, true);
             assert_eq!(depth_3.permutations_seen.len(), 2);
             assert_eq!(depth_3.tail_of_string, &[1, 0, 4]);
             assert_eq!(depth_3.wasted_symbols, 2);
 
-            let depth_4 = depth_3.expand_one(3, F, N);
+            let depth_4 = depth_3.expand_one(3, F, N, true);
             assert_eq!(depth_4.permutations_seen.len(), 2);
             assert_eq!(depth_4.tail_of_string, &[1, 0, 4, 3]);
             assert_eq!(depth_4.wasted_symbols, 3);
 
-            let depth_5 = depth_4.expand_one(2, F, N);
+            let depth_5 = depth_4.expand_one(2, F, N, true);
             assert_eq!(depth_5.permutations_seen.len(), 3);
             assert_eq!(depth_5.tail_of_string, &[0, 4, 3, 2]);
             assert_eq!(depth_5.wasted_symbols, 3);
@@ -241,16 +241,16 @@ mod future_waste {
         let subject = Subject::seed(N);             //     01234
         assert_eq!(subject.future_waste(N), 0);     //       |
                                                     //       v
-        let depth_1 = subject.expand_one(3, F, N);  //    012343ww   (2 wasted)
+        let depth_1 = subject.expand_one(3, F, N, true);  //    012343ww   (2 wasted)
         assert_eq!(depth_1.future_waste(N), 2);     //       |
                                                     //       v
-        let depth_2 = depth_1.expand_one(0, F, N);  //    0123430w
+        let depth_2 = depth_1.expand_one(0, F, N, true);  //    0123430w
         assert_eq!(depth_2.future_waste(N), 1);     //       |
                                                     //       v
-        let depth_3 = depth_2.expand_one(1, F, N);  //    01234301
+        let depth_3 = depth_2.expand_one(1, F, N, true);  //    01234301
         assert_eq!(depth_3.future_waste(N), 0);     //       |
                                                     //       v
-        let depth_4 = depth_3.expand_one(1, F, N);  //  012343011www
+        let depth_4 = depth_3.expand_one(1, F, N, true);  //  012343011www
         assert_eq!(depth_4.future_waste(N), 3);
     }
 }
@@ -263,27 +263,83 @@ mod total_waste {
         let subject = Subject::seed(N);             //     01234
         assert_eq!(subject.total_waste(N), 0);      //       |
                                                     //       v
-        let depth_1 = subject.expand_one(3, F, N);  //    01234[3ww]   (3 wasted in total)
+        let depth_1 = subject.expand_one(3, F, N, true);  //    01234[3ww]   (3 wasted in total)
         assert_eq!(depth_1.total_waste(N), 3);      //       |
                                                     //       v
-        let depth_2 = depth_1.expand_one(0, F, N);  //    01234[30w]
+        let depth_2 = depth_1.expand_one(0, F, N, true);  //    01234[30w]
         assert_eq!(depth_2.total_waste(N), 3);      //       |
                                                     //       v
-        let depth_3 = depth_2.expand_one(1, F, N);  //    01234[301]
+        let depth_3 = depth_2.expand_one(1, F, N, true);  //    01234[301]
         assert_eq!(depth_3.total_waste(N), 3);      //       |
                                                     //       v
-        let depth_4 = depth_3.expand_one(1, F, N);  //  01234[3011www]
+        let depth_4 = depth_3.expand_one(1, F, N, true);  //  01234[3011www]
         assert_eq!(depth_4.total_waste(N), 7);
     }
 }
 
+mod string {
+    use super::*;
+
+    #[test]
+    fn it_rebuilds_the_full_string_from_the_path() {
+        let subject = Subject::seed(N);
+        assert_eq!(subject.string(N), &[0, 1, 2, 3, 4]);
+
+        let depth_1 = subject.expand_one(0, F, N, true);
+        let depth_2 = depth_1.expand_one(3, F, N, true);
+        let depth_3 = depth_2.expand_one(4, F, N, true);
+
+        assert_eq!(depth_3.string(N), &[0, 1, 2, 3, 4, 0, 3, 4]);
+    }
+
+    #[test]
+    fn it_rebuilds_the_string_of_every_expanded_candidate() {
+        let children: Vec<Subject> = Subject::seed(N).expand(MAX, N, true).collect();
+
+        for (child, symbol) in children.iter().zip(&[0, 1, 2, 3]) {
+            assert_eq!(child.string(N), &[0, 1, 2, 3, 4, *symbol]);
+        }
+    }
+}
+
+mod path {
+    use super::*;
+
+    #[test]
+    fn it_packs_each_choice_into_as_few_bits_as_possible() {
+        assert_eq!(Path::bits_per_choice(2), 1);
+        assert_eq!(Path::bits_per_choice(3), 1);
+        assert_eq!(Path::bits_per_choice(5), 2);
+        assert_eq!(Path::bits_per_choice(6), 3);
+        assert_eq!(Path::bits_per_choice(9), 3);
+        assert_eq!(Path::bits_per_choice(10), 4);
+    }
+
+    #[test]
+    fn it_returns_the_choices_in_the_order_they_were_pushed() {
+        let choices = [4, 0, 3, 2, 1, 4, 4, 0, 2];
+        let subject = choices.iter().fold(Path::default(), |path, &c| path.push(c, 6));
+
+        assert_eq!(subject.len(), 9);
+        assert_eq!(subject.choices(6).collect::<Vec<_>>(), &choices);
+    }
+
+    #[test]
+    fn it_does_not_change_the_path_it_was_pushed_onto() {
+        let subject = Path::default().push(1, 4);
+        let _ = subject.push(2, 4);
+
+        assert_eq!(subject.choices(4).collect::<Vec<_>>(), &[1]);
+    }
+}
+
 mod serialization {
     use super::*;
     use bincode::{serialize, deserialize};
 
     #[test]
     fn it_can_be_serialized_and_deserialized() {
-        let subject = Subject::seed(N);
+        let subject = Subject::seed(N).expand_one(0, F, N, true).expand_one(2, F, N, true);
 
         let data = serialize(&subject).unwrap();
         let candidate: Subject = deserialize(&data).unwrap();
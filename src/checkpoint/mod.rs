@@ -0,0 +1,139 @@
+use super::bounds::Bounds;
+use super::codec::Codec;
+use super::config::Config;
+use super::known::Mark;
+
+use std::fs::{File, OpenOptions, metadata, rename};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::time::Duration;
+
+use bincode::{serialize_into, deserialize_from};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+
+pub const CHECKPOINT: &str = "checkpoint";
+/// Creating a file with this name in the scratch directory asks a search
+/// that saves checkpoints to save one as soon as possible.
+pub const REQUEST: &str = "checkpoint-now";
+
+const VERSION: u32 = 5;
+
+/// The state of a search apart from its frontier.
+///
+/// The checkpoint file holds this followed by the frontier, which is
+/// compressed if the search uses zlib for its scratch files. Chunks on disk stay
+/// where they are and are found through the disk index in the frontier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub config: Config,
+    pub bounds: Bounds,
+    pub expansions: usize,
+    pub pruned_seeds: usize,
+    pub phases_finished: usize,
+    pub witnesses: Vec<Option<Vec<u8>>>,
+    pub marks: Vec<Mark>,
+    pub elapsed: Duration,
+    /// How long the witnesses file, the proof log and the events file were, so
+    /// that anything written to them after the checkpoint can be dropped when
+    /// resuming.
+    pub witnesses_len: Option<u64>,
+    pub proof_len: Option<u64>,
+    pub events_len: Option<u64>,
+}
+
+impl Checkpoint {
+    pub fn path(scratch: &str) -> String {
+        format!("{}/{}", scratch, CHECKPOINT)
+    }
+
+    /// Writes the checkpoint and then the frontier to a temporary file and
+    /// renames it into place, then syncs the scratch directory, so a crash
+    /// leaves the previous checkpoint intact.
+    /// Returns the size of the file.
+    pub fn save<F>(&self, frontier: F) -> Result<u64, String>
+        where F: FnOnce(&mut dyn Write) -> Result<(), String>
+    {
+        let path = Self::path(&self.config.scratch);
+        let temporary = format!("{}.tmp", path);
+        let error = |e: &dyn ::std::fmt::Display| format!("Failed to write {}: {}", temporary, e);
+
+        let file = File::create(&temporary).map_err(|e| error(&e))?;
+        let mut writer = BufWriter::new(file);
+
+        serialize_into(&mut writer, &VERSION).map_err(|e| error(&e))?;
+        serialize_into(&mut writer, self).map_err(|e| error(&e))?;
+
+        if let Codec::Zlib(level) = self.config.codec {
+            let mut encoder = ZlibEncoder::new(&mut writer, Compression::new(level));
+            frontier(&mut encoder)?;
+            encoder.finish().map_err(|e| error(&e))?;
+        } else {
+            frontier(&mut writer)?;
+        }
+
+        let file = writer.into_inner().map_err(|e| error(&e))?;
+        file.sync_all().map_err(|e| error(&e))?;
+        drop(file);
+
+        rename(&temporary, &path).map_err(|e| format!("Failed to rename {} to {}: {}", temporary, path, e))?;
+
+        // The rename only survives a crash once the directory is synced too.
+        File::open(&self.config.scratch).and_then(|dir| dir.sync_all())
+            .map_err(|e| format!("Failed to sync {}: {}", self.config.scratch, e))?;
+
+        Ok(metadata(&path).map(|m| m.len()).unwrap_or(0))
+    }
+
+    /// Reads the checkpoint in a scratch directory and returns it with a
+    /// reader for the frontier that follows it.
+    pub fn load(scratch: &str) -> Result<(Self, Box<dyn Read>), String> {
+        let path = Self::path(scratch);
+        let file = File::open(&path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+
+        let mut reader = BufReader::new(file);
+
+        let version: u32 = deserialize_from(&mut reader)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+        if version != VERSION {
+            return Err(format!("{} has version {} but this build reads version {}", path, version, VERSION));
+        }
+
+        let checkpoint: Self = deserialize_from(&mut reader)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+        let frontier: Box<dyn Read> = match checkpoint.config.codec {
+            Codec::Zlib(_) => Box::new(ZlibDecoder::new(reader)),
+            _ => Box::new(reader),
+        };
+
+        Ok((checkpoint, frontier))
+    }
+
+    /// The length of a file that the search appends to, if it writes one.
+    /// Events written to stdout (`-`) can't be taken back.
+    pub fn len_of(path: &Option<String>) -> Option<u64> {
+        path.as_ref().filter(|p| *p != "-").map(|p| metadata(p).map(|m| m.len()).unwrap_or(0))
+    }
+
+    /// Drops anything that was written to a file after the checkpoint.
+    pub fn truncate(path: &Option<String>, len: Option<u64>) -> Result<(), String> {
+        let (path, len) = match (path, len) {
+            (Some(path), Some(len)) => (path, len),
+            _ => return Ok(()),
+        };
+
+        let file = OpenOptions::new().write(true).create(true).truncate(false).open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+
+        file.set_len(len).map_err(|e| format!("Failed to truncate {}: {}", path, e))
+    }
+}
+
+/// Opens a file to add to the end of it, for the outputs of a resumed search.
+pub fn append(path: &str) -> File {
+    OpenOptions::new().append(true).create(true).open(path)
+        .unwrap_or_else(|_| panic!("Failed to open {}", path))
+}
+
+#[cfg(test)]
+mod test;
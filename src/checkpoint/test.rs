@@ -0,0 +1,105 @@
+use super::*;
+
+use std::fs::{create_dir_all, read_to_string, write};
+
+type Subject = Checkpoint;
+
+const PATH: &str = "/tmp/superpermutation-test";
+
+fn subject(scratch: &str, codec: Codec) -> Subject {
+    let scratch = format!("{}/{}", PATH, scratch);
+    create_dir_all(&scratch).unwrap();
+
+    Subject {
+        config: Config { n: 4, codec, scratch, ..Config::default() },
+        bounds: Bounds::new(4),
+        expansions: 123,
+        pruned_seeds: 0,
+        phases_finished: 2,
+        witnesses: vec![Some(vec![0, 1, 2, 3])],
+        marks: vec![],
+        elapsed: Duration::from_secs(5),
+        witnesses_len: None,
+        proof_len: Some(42),
+        events_len: None,
+    }
+}
+
+fn frontier(writer: &mut dyn Write) -> Result<(), String> {
+    writer.write_all(b"the frontier").map_err(|e| e.to_string())
+}
+
+mod save {
+    use super::*;
+
+    #[test]
+    fn it_saves_the_state_followed_by_the_frontier() {
+        for (scratch, codec) in [("checkpoint-1", Codec::Raw), ("checkpoint-2", Codec::Zlib(6))] {
+            let subject = subject(scratch, codec);
+            subject.save(frontier).unwrap();
+
+            let (checkpoint, mut reader) = Subject::load(&subject.config.scratch).unwrap();
+            let mut rest = String::new();
+            reader.read_to_string(&mut rest).unwrap();
+
+            assert_eq!(checkpoint.config, subject.config);
+            assert_eq!(checkpoint.expansions, 123);
+            assert_eq!(checkpoint.witnesses, vec![Some(vec![0, 1, 2, 3])]);
+            assert_eq!(checkpoint.proof_len, Some(42));
+            assert_eq!(rest, "the frontier");
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_previous_checkpoint_if_the_frontier_fails_to_save() {
+        let subject = subject("checkpoint-3", Codec::Raw);
+        subject.save(frontier).unwrap();
+
+        let failed = Subject { expansions: 456, ..subject.clone() };
+        let result = failed.save(|_| Err("disk full".to_string()));
+
+        assert_eq!(result, Err("disk full".to_string()));
+        assert_eq!(Subject::load(&subject.config.scratch).unwrap().0.expansions, 123);
+    }
+}
+
+mod load {
+    use super::*;
+
+    #[test]
+    fn it_reports_a_missing_checkpoint() {
+        let scratch = format!("{}/checkpoint-4", PATH);
+        create_dir_all(&scratch).unwrap();
+        let _ = ::std::fs::remove_file(Subject::path(&scratch));
+
+        let error = Subject::load(&scratch).err().unwrap();
+        assert!(error.starts_with(&format!("Failed to open {}/checkpoint", scratch)));
+    }
+
+    #[test]
+    fn it_refuses_a_checkpoint_from_another_version() {
+        let scratch = format!("{}/checkpoint-5", PATH);
+        create_dir_all(&scratch).unwrap();
+        write(Subject::path(&scratch), [9, 0, 0, 0]).unwrap();
+
+        let error = Subject::load(&scratch).err().unwrap();
+        assert_eq!(error, format!("{}/checkpoint has version 9 but this build reads version 5", scratch));
+    }
+}
+
+mod truncate {
+    use super::*;
+
+    #[test]
+    fn it_drops_what_was_written_after_the_checkpoint() {
+        let path = format!("{}/checkpoint-6.proof", PATH);
+        create_dir_all(PATH).unwrap();
+        write(&path, "before\nafter\n").unwrap();
+
+        let len = Subject::len_of(&Some(path.clone()));
+        assert_eq!(len, Some(13));
+
+        Subject::truncate(&Some(path.clone()), Some(7)).unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), "before\n");
+    }
+}
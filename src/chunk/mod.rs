@@ -0,0 +1,186 @@
+use super::candidate::Candidate;
+use super::codec::Codec;
+use super::disk::TEMPORARY;
+
+use std::collections::VecDeque;
+use std::fs::{File, read};
+use std::io::{BufWriter, Write};
+
+use flate2::{Crc, CrcWriter};
+
+pub const MAGIC: &[u8; 8] = b"LEAPSBND";
+/// Version 2 added codecs other than raw and zlib. Version 1 headers are laid
+/// out the same way so they are still read.
+pub const VERSION: u16 = 2;
+pub const HEADER_SIZE: usize = 22;
+
+const CHECKSUM_SIZE: usize = 4;
+
+/// The start of every chunk file. It says what the chunk holds so that a
+/// chunk written by another build or for another value of n isn't misread.
+///
+/// The header is followed by the candidates, encoded with the codec, and then
+/// a CRC32 of everything before it. Numbers are little-endian.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Header {
+    pub version: u16,
+    pub n: usize,
+    pub codec: u16,
+    pub count: usize,
+}
+
+impl Header {
+    pub fn new(n: usize, codec: Codec, count: usize) -> Self {
+        Self { version: VERSION, n, codec: codec.id(), count }
+    }
+
+    pub fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE);
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&(self.n as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.codec.to_le_bytes());
+        bytes.extend_from_slice(&(self.count as u64).to_le_bytes());
+
+        bytes
+    }
+
+    /// Reads the header at the start of a chunk, or returns none if the chunk
+    /// doesn't start with the magic number.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+            return None;
+        }
+
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let mut count = [0; 8];
+        count.copy_from_slice(&bytes[14..22]);
+
+        Some(Self {
+            version: u16_at(8),
+            n: u16_at(10) as usize,
+            codec: u16_at(12),
+            count: u64::from_le_bytes(count) as usize,
+        })
+    }
+
+    // Returns the codec to decode the chunk with, or what is wrong with a
+    // chunk that doesn't match the search.
+    fn check(&self, n: usize) -> Result<Codec, String> {
+        if self.version > VERSION {
+            return Err(format!("has format version {} but this build reads up to version {}", self.version, VERSION));
+        }
+
+        if self.n != n {
+            return Err(format!("was written for n = {} but the search has n = {}", self.n, n));
+        }
+
+        Codec::from_id(self.codec).ok_or_else(|| {
+            format!("was written with codec {}, which this build doesn't know", self.codec)
+        })
+    }
+}
+
+/// Writes the header, the candidates and the checksum, then syncs the file.
+pub fn save(filename: &str, bucket: &VecDeque<Candidate>, n: usize, codec: Codec) -> Result<(), String> {
+    let file = File::create(filename).map_err(|e| e.to_string())?;
+    let mut writer = CrcWriter::new(BufWriter::new(file));
+
+    let header = Header::new(n, codec, bucket.len());
+    writer.write_all(&header.to_bytes()).map_err(|e| e.to_string())?;
+
+    codec.encode(&mut writer, bucket, n)?;
+
+    let checksum = writer.crc().sum();
+    let mut writer = writer.into_inner();
+
+    writer.write_all(&checksum.to_le_bytes()).map_err(|e| e.to_string())?;
+
+    let file = writer.into_inner().map_err(|e| e.error().to_string())?;
+    file.sync_all().map_err(|e| e.to_string())
+}
+
+/// Reads a chunk with the codec in its header and checks it was written for
+/// this search. Chunks without a header are only recognised if they were
+/// written with the given codec. The error says what is wrong with the chunk,
+/// to follow its name.
+pub fn load(filename: &str, n: usize, codec: Codec) -> Result<VecDeque<Candidate>, String> {
+    let bytes = read(filename).map_err(|e| format!("can't be opened ({})", e))?;
+
+    if !bytes.starts_with(MAGIC) && decode_legacy(&bytes, n, codec).is_ok() {
+        return Err("has no header because an older version wrote it (run `migrate` to upgrade it)".to_string());
+    }
+
+    let payload = verify(&bytes)?;
+
+    let header = match Header::parse(payload) {
+        Some(header) => header,
+        None => return Err(format!("is corrupt: it is {} bytes long, which is too short to hold a header", bytes.len())),
+    };
+
+    let codec = header.check(n)?;
+    let bucket = decode(&payload[HEADER_SIZE..], n, codec)?;
+
+    if bucket.len() != header.count {
+        return Err(format!("is corrupt: its header says {} candidates but it holds {}", header.count, bucket.len()));
+    }
+
+    Ok(bucket)
+}
+
+/// Rewrites a chunk that an older version wrote without a header, and returns
+/// whether it needed to be. The chunk must be decoded with the codec it was
+/// written with, since older versions didn't record it.
+pub fn migrate(filename: &str, n: usize, codec: Codec) -> Result<bool, String> {
+    let bytes = read(filename).map_err(|e| format!("can't be opened ({})", e))?;
+
+    if bytes.starts_with(MAGIC) {
+        return load(filename, n, codec).map(|_| false);
+    }
+
+    let bucket = decode_legacy(&bytes, n, codec)?;
+
+    if let Some(&symbol) = bucket.iter().flat_map(|c| c.tail_of_string.iter()).find(|&&s| s as usize >= n) {
+        return Err(format!("holds the symbol {}, which is too big for n = {}", symbol, n));
+    }
+
+    let temporary = format!("{}{}", filename, TEMPORARY);
+
+    save(&temporary, &bucket, n, codec)
+        .and_then(|_| ::std::fs::rename(&temporary, filename).map_err(|e| e.to_string()))
+        .map_err(|e| { let _ = ::std::fs::remove_file(&temporary); format!("can't be rewritten ({})", e) })?;
+
+    Ok(true)
+}
+
+// Returns everything before the checksum once it has been checked.
+fn verify(bytes: &[u8]) -> Result<&[u8], String> {
+    if bytes.len() < CHECKSUM_SIZE {
+        return Err(format!("is corrupt: it is {} bytes long, which is too short to hold a checksum", bytes.len()));
+    }
+
+    let (payload, trailer) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+    let expected = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+
+    let mut crc = Crc::new();
+    crc.update(payload);
+
+    match crc.sum() == expected {
+        true => Ok(payload),
+        false => Err(format!("is corrupt: its checksum is {:08x} but it should be {:08x}", crc.sum(), expected)),
+    }
+}
+
+// Chunks from before the header end with a checksum, unless they are from
+// before checksums too.
+fn decode_legacy(bytes: &[u8], n: usize, codec: Codec) -> Result<VecDeque<Candidate>, String> {
+    decode(verify(bytes).unwrap_or(bytes), n, codec)
+}
+
+fn decode(bytes: &[u8], n: usize, codec: Codec) -> Result<VecDeque<Candidate>, String> {
+    codec.decode(bytes, n).map_err(|e| format!("is corrupt: it could not be decoded ({})", e))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,142 @@
+use super::*;
+
+use std::fs::{create_dir_all, write};
+
+const PATH: &str = "/tmp/superpermutation-test/chunks";
+
+fn filename(test_id: &str) -> String {
+    create_dir_all(PATH).unwrap();
+    format!("{}/{}", PATH, test_id)
+}
+
+fn bucket() -> VecDeque<Candidate> {
+    (0..100).map(|_| Candidate::seed(5)).collect()
+}
+
+// Writes a chunk the way versions before the header did, with or without the
+// checksum that came before it.
+fn legacy(filename: &str, codec: Codec, checksum: bool) {
+    let mut bytes = vec![];
+    codec.encode(&mut bytes, &bucket(), 5).unwrap();
+
+    if checksum {
+        let mut crc = Crc::new();
+        crc.update(&bytes);
+        bytes.extend_from_slice(&crc.sum().to_le_bytes());
+    }
+
+    write(filename, &bytes).unwrap();
+}
+
+mod header {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_through_bytes() {
+        let header = Header::new(7, Codec::Packed, 123_456);
+        let bytes = header.to_bytes();
+
+        assert_eq!(bytes.len(), HEADER_SIZE);
+        assert_eq!(&bytes[..8], MAGIC);
+        assert_eq!(Header::parse(&bytes), Some(header));
+    }
+
+    #[test]
+    fn it_does_not_parse_bytes_without_the_magic_number() {
+        assert_eq!(Header::parse(b"not a chunk at all, honest"), None);
+        assert_eq!(Header::parse(b"LEAPS"), None);
+    }
+}
+
+mod load {
+    use super::*;
+
+    #[test]
+    fn it_reads_back_what_was_saved_with_the_codec_in_the_header() {
+        for (test_id, codec) in [("chunk-1", Codec::Raw), ("chunk-2", Codec::Zlib(9)), ("chunk-13", Codec::Packed)] {
+            let filename = filename(test_id);
+            save(&filename, &bucket(), 5, codec).unwrap();
+
+            assert_eq!(load(&filename, 5, Codec::Raw), Ok(bucket()));
+        }
+    }
+
+    #[test]
+    fn it_rejects_a_chunk_written_for_another_n() {
+        let filename = filename("chunk-3");
+        save(&filename, &bucket(), 5, Codec::Raw).unwrap();
+
+        assert_eq!(load(&filename, 6, Codec::Raw), Err("was written for n = 5 but the search has n = 6".to_string()));
+    }
+
+    #[test]
+    fn it_rejects_a_chunk_written_with_an_unknown_codec() {
+        let filename = filename("chunk-4");
+        let header = Header { codec: 99, ..Header::new(5, Codec::Raw, 0) };
+
+        let mut bytes = header.to_bytes();
+        let mut crc = Crc::new();
+        crc.update(&bytes);
+        bytes.extend_from_slice(&crc.sum().to_le_bytes());
+        write(&filename, &bytes).unwrap();
+
+        assert_eq!(load(&filename, 5, Codec::Raw), Err("was written with codec 99, which this build doesn't know".to_string()));
+    }
+
+    #[test]
+    fn it_rejects_a_chunk_from_a_newer_version() {
+        let filename = filename("chunk-5");
+        let header = Header { version: VERSION + 1, ..Header::new(5, Codec::Raw, 0) };
+
+        let mut bytes = header.to_bytes();
+        let mut crc = Crc::new();
+        crc.update(&bytes);
+        bytes.extend_from_slice(&crc.sum().to_le_bytes());
+        write(&filename, &bytes).unwrap();
+
+        assert_eq!(load(&filename, 5, Codec::Raw), Err(format!("has format version {} but this build reads up to version {}", VERSION + 1, VERSION)));
+    }
+
+    #[test]
+    fn it_points_a_chunk_without_a_header_at_migrate() {
+        let filename = filename("chunk-6");
+        legacy(&filename, Codec::Raw, true);
+
+        let error = load(&filename, 5, Codec::Raw).unwrap_err();
+        assert!(error.contains("run `migrate`"));
+    }
+}
+
+mod migrate {
+    use super::*;
+
+    #[test]
+    fn it_adds_a_header_to_chunks_from_older_versions() {
+        let zlib = Codec::Zlib(6);
+
+        for (test_id, codec, checksum) in [("chunk-7", Codec::Raw, true), ("chunk-8", zlib, true), ("chunk-9", Codec::Raw, false), ("chunk-10", zlib, false)] {
+            let filename = filename(test_id);
+            legacy(&filename, codec, checksum);
+
+            assert_eq!(migrate(&filename, 5, codec), Ok(true));
+            assert_eq!(load(&filename, 5, codec), Ok(bucket()));
+        }
+    }
+
+    #[test]
+    fn it_leaves_chunks_that_have_a_header_alone() {
+        let filename = filename("chunk-11");
+        save(&filename, &bucket(), 5, Codec::Raw).unwrap();
+
+        assert_eq!(migrate(&filename, 5, Codec::Raw), Ok(false));
+    }
+
+    #[test]
+    fn it_refuses_candidates_with_symbols_too_big_for_n() {
+        let filename = filename("chunk-12");
+        legacy(&filename, Codec::Raw, true);
+
+        let error = migrate(&filename, 2, Codec::Raw).unwrap_err();
+        assert!(error.ends_with("which is too big for n = 2"));
+    }
+}
@@ -0,0 +1,197 @@
+use leaps_and_bounds::config::Settings;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Search,
+    Verify,
+    Enumerate,
+    Selftest,
+    Resume,
+    Inspect,
+    Migrate,
+    BenchCodecs,
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub command: Command,
+    pub config: Option<String>,
+    pub string: Option<String>,
+    pub settings: Settings,
+}
+
+impl Args {
+    pub fn parse<I: IntoIterator<Item=String>>(args: I) -> Result<Self, String> {
+        let mut args = args.into_iter().peekable();
+
+        let mut command = match args.peek().map(|s| s.as_str()) {
+            Some("search") => { args.next(); Command::Search },
+            Some("verify") => { args.next(); Command::Verify },
+            Some("enumerate") => { args.next(); Command::Enumerate },
+            Some("selftest") => { args.next(); Command::Selftest },
+            Some("resume") => { args.next(); Command::Resume },
+            Some("inspect") => { args.next(); Command::Inspect },
+            Some("migrate") => { args.next(); Command::Migrate },
+            Some("bench-codecs") => { args.next(); Command::BenchCodecs },
+            Some("help") => { args.next(); Command::Help },
+            Some(arg) if !arg.starts_with('-') => {
+                return Err(format!("Unknown command '{}'.", arg));
+            },
+            _ => Command::Search,
+        };
+
+        let mut config = None;
+        let mut string = None;
+        let mut settings = Settings::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => config = Some(Self::value(&arg, args.next())?),
+                "--n" => settings.n = Some(Self::value(&arg, args.next())?),
+                "--memory" => settings.memory = Some(Self::value(&arg, args.next())?),
+                "--gzip" => settings.gzip = Some(true),
+                "--no-gzip" => settings.gzip = Some(false),
+                "--codec" => {
+                    let value: String = Self::value(&arg, args.next())?;
+                    settings.codec = Some(Settings::parse_codec(&arg, &value).map_err(|_| {
+                        format!("Invalid value '{}' for '{}'.", value, arg)
+                    })?);
+                },
+                "--verbose" => settings.verbose = Some(true),
+                "--quiet" => settings.verbose = Some(false),
+                "--no-banner" => settings.banner = Some(false),
+                "--no-status" => settings.status = Some(false),
+                "--paths" => settings.paths = Some(true),
+                "--no-paths" => settings.paths = Some(false),
+                "--scratch" => settings.scratch = Some(Self::value(&arg, args.next())?),
+                "--split-size" => settings.split_size = Some(Self::value(&arg, args.next())?),
+                "--events" => settings.events = Some(Self::value(&arg, args.next())?),
+                "--witnesses" => settings.witnesses = Some(Self::value(&arg, args.next())?),
+                "--bounds" => settings.bounds = Some(Self::value(&arg, args.next())?),
+                "--proof" => settings.proof = Some(Self::value(&arg, args.next())?),
+                "--max-waste" => settings.max_waste = Some(Self::value(&arg, args.next())?),
+                "--max-expansions" => settings.max_expansions = Some(Self::value(&arg, args.next())?),
+                "--time-limit" => {
+                    let value: String = Self::value(&arg, args.next())?;
+                    settings.time_limit = Some(Settings::parse_duration(&arg, &value).map_err(|_| {
+                        format!("Invalid value '{}' for '{}'.", value, arg)
+                    })?);
+                },
+                "--checkpoint-every" => {
+                    let value: String = Self::value(&arg, args.next())?;
+                    settings.checkpoint_every = Some(Settings::parse_duration(&arg, &value).map_err(|_| {
+                        format!("Invalid value '{}' for '{}'.", value, arg)
+                    })?);
+                },
+                "--offload" => {
+                    let value: String = Self::value(&arg, args.next())?;
+                    settings.offload = Some(Settings::parse_offload(&arg, &value).map_err(|_| {
+                        format!("Invalid value '{}' for '{}'.", value, arg)
+                    })?);
+                },
+                "--high-watermark" => settings.high_watermark = Some(Self::value(&arg, args.next())?),
+                "--low-watermark" => settings.low_watermark = Some(Self::value(&arg, args.next())?),
+                "--prune" => {
+                    let value: String = Self::value(&arg, args.next())?;
+                    settings.prune = Some(Settings::parse_prune(&arg, &value).map_err(|_| {
+                        format!("Invalid value '{}' for '{}'.", value, arg)
+                    })?);
+                },
+                "--help" | "-h" => command = Command::Help,
+                _ if command == Command::Verify && string.is_none() && !arg.starts_with('-') => string = Some(arg),
+                _ => return Err(format!("Unknown option '{}'.", arg)),
+            }
+        }
+
+        Ok(Self { command, config, string, settings })
+    }
+
+    pub fn usage() -> &'static str {
+        "Usage: leaps-and-bounds [search] [options]
+       leaps-and-bounds verify [--n <symbols>] <string>
+       leaps-and-bounds verify --n <symbols> --bounds <path>
+       leaps-and-bounds verify --proof <path>
+       leaps-and-bounds enumerate [options]
+       leaps-and-bounds selftest [--n <symbols>]
+       leaps-and-bounds resume [--scratch <dir>] [options]
+       leaps-and-bounds inspect [--scratch <dir>] [--n <symbols>] [--codec <codec>]
+       leaps-and-bounds migrate [--scratch <dir>] [--n <symbols>] [--codec <codec>]
+       leaps-and-bounds bench-codecs [--scratch <dir>] [--n <symbols>] [--codec <codec>]
+
+Commands:
+  search               Search for superpermutation bounds (default)
+  verify               Check the permutations and waste of a string, the
+                       witness on each line of a bounds file or a proof log
+  enumerate            Search and list every shortest superpermutation (or
+                       every string that achieves the bound for --max-waste)
+  selftest             Check the search against a brute-force solver and the
+                       known results for n from 2 to 4 (or just --n, up to 5)
+  resume               Carry on from the checkpoint in the scratch directory
+  inspect              Read every chunk in the scratch directory and report
+                       the ones that are orphaned, missing or unreadable
+  migrate              Add headers to the chunks that an older version wrote
+                       in the scratch directory
+  bench-codecs         Measure the size and speed of each codec on the
+                       biggest bucket in the scratch directory
+  help                 Print this message
+
+Options:
+  --config <path>      Read settings from a 'key = value' file
+  --n <symbols>        How many symbols the string should contain
+  --memory <GiB>       How many gigabytes of memory the search may use
+  --gzip               Compress scratch files to save space (zlib:6)
+  --no-gzip            Do not compress scratch files (raw)
+  --codec <codec>      Encode scratch files with 'raw', 'zlib', 'zlib:<0-9>',
+                       'packed' or 'compact' (default: raw)
+  --verbose            Print verbose output
+  --quiet              Do not print verbose output
+  --no-banner          Do not print the introduction
+  --no-status          Do not show the live status line
+  --scratch <dir>      Where to offload candidates (default: scratch-files)
+  --split-size <n>     How many candidates to write per scratch file
+  --offload <policy>   Offload the buckets that are 'unlikely' to be unpruned,
+                       the 'largest' or those with the most 'waste' first
+                       (default: unlikely)
+  --high-watermark <f> Offload once this share of memory is used (default: 1)
+  --low-watermark <f>  Offload until this share of memory is used (default: 0.75)
+  --prune <mode>       Prune 'eager' or 'lazy' (default: eager)
+  --events <path>      Write search events as JSON lines ('-' for stdout, which
+                       moves the rest of the output to stderr)
+  --witnesses <path>   Save a string that achieves each new lower bound
+  --paths              Keep what rebuilds each candidate's string so that each
+                       bound is printed with one, which costs memory (on with
+                       --witnesses)
+  --no-paths           Do not keep them (the default without --witnesses)
+  --bounds <path>      Start from the bounds in a 'waste = permutations' file
+  --proof <path>       Log the decisions behind each upper bound
+  --checkpoint-every <time>
+                       Save a checkpoint to resume from at this interval, when
+                       the search stops early and when a 'checkpoint-now' file
+                       is created in the scratch directory
+  --max-waste <w>      Stop once the bound for w wasted symbols is proven
+  --time-limit <time>  Stop after a duration, e.g. 90s, 30m, 12h or 2d
+  --max-expansions <n> Stop after expanding n candidates
+
+Inspect, migrate and bench-codecs take n and the codec from the checkpoint
+in the scratch directory if there is one. Otherwise, they need --n.
+
+Flags take precedence over the config file. Any of n, memory, gzip or
+verbose that is still missing is asked for interactively when stdin is a
+terminal. Otherwise, its default value is used.
+
+When resuming, the settings that change what the search finds come from the
+checkpoint and the stop conditions count from the resume."
+    }
+
+    fn value<T: ::std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+        let value = value.ok_or_else(|| format!("Missing value for '{}'.", flag))?;
+
+        value.trim().parse().map_err(|_| {
+            format!("Invalid value '{}' for '{}'.", value, flag)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test;
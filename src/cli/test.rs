@@ -0,0 +1,176 @@
+use super::*;
+use leaps_and_bounds::codec::Codec;
+use leaps_and_bounds::config::Prune;
+use leaps_and_bounds::offload::Offload;
+use std::time::Duration;
+
+type Subject = Args;
+
+fn parse(args: &[&str]) -> Result<Subject, String> {
+    Subject::parse(args.iter().map(|s| s.to_string()))
+}
+
+mod parse {
+    use super::*;
+
+    #[test]
+    fn it_defaults_to_the_search_command_with_no_options_set() {
+        let subject = parse(&[]).unwrap();
+
+        assert_eq!(subject.command, Command::Search);
+        assert_eq!(subject.config, None);
+        assert_eq!(subject.settings, Settings::default());
+    }
+
+    #[test]
+    fn it_parses_the_search_command_and_its_options() {
+        let subject = parse(&["search", "--n", "5", "--memory", "12", "--gzip", "--verbose"]).unwrap();
+
+        assert_eq!(subject.command, Command::Search);
+        assert_eq!(subject.settings.n, Some(5));
+        assert_eq!(subject.settings.memory, Some(12.0));
+        assert_eq!(subject.settings.gzip, Some(true));
+        assert_eq!(subject.settings.verbose, Some(true));
+    }
+
+    #[test]
+    fn it_allows_options_without_a_command() {
+        let subject = parse(&["--n", "4", "--no-gzip", "--quiet", "--no-banner", "--no-status", "--no-paths"]).unwrap();
+
+        assert_eq!(subject.command, Command::Search);
+        assert_eq!(subject.settings.n, Some(4));
+        assert_eq!(subject.settings.gzip, Some(false));
+        assert_eq!(subject.settings.verbose, Some(false));
+        assert_eq!(subject.settings.banner, Some(false));
+        assert_eq!(subject.settings.status, Some(false));
+        assert_eq!(subject.settings.paths, Some(false));
+    }
+
+    #[test]
+    fn it_parses_the_config_file_and_scratch_options() {
+        let args = ["--config", "run.conf", "--scratch", "/tmp/s", "--split-size", "100", "--prune", "lazy", "--events", "-", "--bounds", "n6.bounds", "--witnesses", "w.txt", "--proof", "n4.proof"];
+        let subject = parse(&args).unwrap();
+
+        assert_eq!(subject.config, Some("run.conf".to_string()));
+        assert_eq!(subject.settings.scratch, Some("/tmp/s".to_string()));
+        assert_eq!(subject.settings.split_size, Some(100));
+        assert_eq!(subject.settings.prune, Some(Prune::Lazy));
+        assert_eq!(subject.settings.events, Some("-".to_string()));
+        assert_eq!(subject.settings.bounds, Some("n6.bounds".to_string()));
+        assert_eq!(subject.settings.witnesses, Some("w.txt".to_string()));
+        assert_eq!(subject.settings.proof, Some("n4.proof".to_string()));
+    }
+
+    #[test]
+    fn it_parses_the_offload_options() {
+        let subject = parse(&["--offload", "waste", "--high-watermark", "0.9", "--low-watermark", "0.25"]).unwrap();
+
+        assert_eq!(subject.settings.offload, Some(Offload::Waste));
+        assert_eq!(subject.settings.high_watermark, Some(0.9));
+        assert_eq!(subject.settings.low_watermark, Some(0.25));
+    }
+
+    #[test]
+    fn it_parses_the_stop_conditions() {
+        let subject = parse(&["--max-waste", "8", "--time-limit", "2h", "--max-expansions", "5000"]).unwrap();
+
+        assert_eq!(subject.settings.max_waste, Some(8));
+        assert_eq!(subject.settings.time_limit, Some(Duration::from_secs(7200)));
+        assert_eq!(subject.settings.max_expansions, Some(5000));
+
+        assert_eq!(parse(&["--time-limit", "soon"]), Err("Invalid value 'soon' for '--time-limit'.".to_string()));
+    }
+
+    #[test]
+    fn it_parses_the_verify_command_and_its_string() {
+        let subject = parse(&["verify", "--n", "3", "123121321"]).unwrap();
+
+        assert_eq!(subject.command, Command::Verify);
+        assert_eq!(subject.settings.n, Some(3));
+        assert_eq!(subject.string, Some("123121321".to_string()));
+
+        let subject = parse(&["verify", "--bounds", "n5.bounds"]).unwrap();
+
+        assert_eq!(subject.string, None);
+        assert_eq!(subject.settings.bounds, Some("n5.bounds".to_string()));
+    }
+
+    #[test]
+    fn it_only_takes_a_string_for_the_verify_command() {
+        assert_eq!(parse(&["verify", "123", "456"]), Err("Unknown option '456'.".to_string()));
+        assert_eq!(parse(&["search", "--n", "3", "123"]), Err("Unknown option '123'.".to_string()));
+    }
+
+    #[test]
+    fn it_parses_the_enumerate_command() {
+        let subject = parse(&["enumerate", "--n", "4", "--max-waste", "3"]).unwrap();
+
+        assert_eq!(subject.command, Command::Enumerate);
+        assert_eq!(subject.settings.n, Some(4));
+        assert_eq!(subject.settings.max_waste, Some(3));
+    }
+
+    #[test]
+    fn it_parses_the_selftest_command() {
+        let subject = parse(&["selftest", "--n", "3"]).unwrap();
+
+        assert_eq!(subject.command, Command::Selftest);
+        assert_eq!(subject.settings.n, Some(3));
+    }
+
+    #[test]
+    fn it_parses_the_resume_command() {
+        let subject = parse(&["resume", "--scratch", "runs/n6", "--checkpoint-every", "30m"]).unwrap();
+
+        assert_eq!(subject.command, Command::Resume);
+        assert_eq!(subject.settings.scratch, Some("runs/n6".to_string()));
+        assert_eq!(subject.settings.checkpoint_every, Some(Duration::from_secs(30 * 60)));
+    }
+
+    #[test]
+    fn it_parses_the_inspect_command() {
+        let subject = parse(&["inspect", "--scratch", "runs/n6", "--gzip"]).unwrap();
+
+        assert_eq!(subject.command, Command::Inspect);
+        assert_eq!(subject.settings.gzip, Some(true));
+    }
+
+    #[test]
+    fn it_parses_the_bench_codecs_command() {
+        let subject = parse(&["bench-codecs", "--scratch", "runs/n6", "--codec", "zlib:1"]).unwrap();
+
+        assert_eq!(subject.command, Command::BenchCodecs);
+        assert_eq!(subject.settings.codec, Some(Codec::Zlib(1)));
+    }
+
+    #[test]
+    fn it_parses_the_migrate_command() {
+        let subject = parse(&["migrate", "--scratch", "runs/n6", "--n", "6"]).unwrap();
+
+        assert_eq!(subject.command, Command::Migrate);
+        assert_eq!(subject.settings.n, Some(6));
+    }
+
+    #[test]
+    fn it_parses_the_help_command_and_flag() {
+        assert_eq!(parse(&["help"]).unwrap().command, Command::Help);
+        assert_eq!(parse(&["--help"]).unwrap().command, Command::Help);
+        assert_eq!(parse(&["search", "-h"]).unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn it_returns_an_error_for_unknown_commands_and_options() {
+        assert_eq!(parse(&["explode"]), Err("Unknown command 'explode'.".to_string()));
+        assert_eq!(parse(&["--colour"]), Err("Unknown option '--colour'.".to_string()));
+    }
+
+    #[test]
+    fn it_returns_an_error_for_missing_or_invalid_values() {
+        assert_eq!(parse(&["--n"]), Err("Missing value for '--n'.".to_string()));
+        assert_eq!(parse(&["--n", "five"]), Err("Invalid value 'five' for '--n'.".to_string()));
+        assert_eq!(parse(&["--memory", "lots"]), Err("Invalid value 'lots' for '--memory'.".to_string()));
+        assert_eq!(parse(&["--prune", "often"]), Err("Invalid value 'often' for '--prune'.".to_string()));
+        assert_eq!(parse(&["--codec", "lz4"]), Err("Invalid value 'lz4' for '--codec'.".to_string()));
+        assert_eq!(parse(&["--offload", "random"]), Err("Invalid value 'random' for '--offload'.".to_string()));
+    }
+}
@@ -0,0 +1,292 @@
+// Every candidate in a bucket has the same waste and permutation count and
+// candidates next to each other were usually expanded from the same parent, so
+// this encodes a bucket as a whole rather than one candidate at a time:
+//
+// - the waste, tail length and path length are written once if every candidate
+//   shares them and once per candidate otherwise
+// - the tails are packed into as few bits per symbol as n needs
+// - each path is written as how many choices it shares with the path before
+//   it, followed by the rest of its choices
+// - each permutation set is XORed against the one before it and the bits that
+//   differ are written as sorted deltas, or as a bitmap if that is smaller
+//
+// The codec then compresses this with zlib, which finds what is left to share
+// between the differences.
+
+use super::super::bounds::Bounds;
+use super::super::candidate::{Candidate, Path};
+use super::packed::{Reader, write_varint};
+
+use bit_set::BitSet;
+use std::collections::VecDeque;
+
+const SAME_WASTE: u8 = 1;
+const SAME_TAIL_LEN: u8 = 2;
+const SAME_PATH_LEN: u8 = 4;
+
+const DENSE: u8 = 0;
+const SPARSE: u8 = 1;
+
+pub fn encode(bucket: &VecDeque<Candidate>, n: usize) -> Vec<u8> {
+    let mut bytes = vec![];
+    write_varint(&mut bytes, bucket.len());
+
+    let first = match bucket.front() {
+        Some(first) => first,
+        None => return bytes,
+    };
+
+    let mut flags = 0;
+    if bucket.iter().all(|c| c.wasted_symbols == first.wasted_symbols) { flags |= SAME_WASTE; }
+    if bucket.iter().all(|c| c.tail_of_string.len() == first.tail_of_string.len()) { flags |= SAME_TAIL_LEN; }
+    if bucket.iter().all(|c| c.path.len() == first.path.len()) { flags |= SAME_PATH_LEN; }
+    bytes.push(flags);
+
+    for (i, candidate) in bucket.iter().enumerate() {
+        if flags & SAME_WASTE == 0 || i == 0 { write_varint(&mut bytes, candidate.wasted_symbols as usize); }
+        if flags & SAME_TAIL_LEN == 0 || i == 0 { write_varint(&mut bytes, candidate.tail_of_string.len()); }
+        if flags & SAME_PATH_LEN == 0 || i == 0 { write_varint(&mut bytes, candidate.path.len()); }
+    }
+
+    let symbol_bits = bits_per_symbol(bucket);
+    bytes.push(symbol_bits as u8);
+
+    let mut tails = Bits::default();
+    for symbol in bucket.iter().flat_map(|c| c.tail_of_string.iter()) {
+        tails.push(*symbol as usize, symbol_bits);
+    }
+    bytes.extend_from_slice(&tails.bytes);
+
+    let choice_bits = Path::bits_per_choice(n);
+    let mut suffixes = Bits::default();
+    let mut previous: Vec<u8> = vec![];
+
+    for candidate in bucket {
+        let choices: Vec<u8> = candidate.path.choices(n).collect();
+        let shared = previous.iter().zip(&choices).take_while(|(a, b)| a == b).count();
+
+        write_varint(&mut bytes, shared);
+        for &choice in &choices[shared..] {
+            suffixes.push(choice as usize, choice_bits);
+        }
+
+        previous = choices;
+    }
+    bytes.extend_from_slice(&suffixes.bytes);
+
+    let dense_len = Bounds::factorial(n).div_ceil(8);
+    let mut previous = BitSet::new();
+
+    for candidate in bucket {
+        write_difference(&mut bytes, &previous, &candidate.permutations_seen, dense_len);
+        previous = candidate.permutations_seen.clone();
+    }
+
+    bytes
+}
+
+pub fn decode(bytes: &[u8], n: usize) -> Result<VecDeque<Candidate>, String> {
+    let mut reader = Reader::new(bytes);
+    let count = reader.varint()?;
+
+    if count == 0 {
+        return reader.finish(VecDeque::new());
+    }
+
+    let flags = reader.take(1)?[0];
+    let mut scalars = Vec::with_capacity(count.min(bytes.len()));
+
+    for i in 0..count {
+        let (waste, tail_len, path_len) = scalars.last().cloned().unwrap_or((0, 0, 0));
+
+        let waste = if flags & SAME_WASTE == 0 || i == 0 { reader.varint()? } else { waste };
+        let tail_len = if flags & SAME_TAIL_LEN == 0 || i == 0 { reader.varint()? } else { tail_len };
+        let path_len = if flags & SAME_PATH_LEN == 0 || i == 0 { reader.varint()? } else { path_len };
+
+        scalars.push((waste, tail_len, path_len));
+    }
+
+    let symbol_bits = reader.take(1)?[0] as usize;
+    let total_symbols: usize = scalars.iter().map(|s| s.1).sum();
+
+    let tails = reader.take((total_symbols * symbol_bits).div_ceil(8))?;
+    let mut position = 0;
+
+    let choice_bits = Path::bits_per_choice(n);
+
+    let mut bucket = VecDeque::with_capacity(count.min(bytes.len()));
+
+    for &(waste, tail_len, _) in &scalars {
+        let tail_of_string = (0..tail_len).map(|_| {
+            let symbol = read_bits(tails, position, symbol_bits) as u8;
+            position += symbol_bits;
+            symbol
+        }).collect();
+
+        bucket.push_back(Candidate {
+            permutations_seen: BitSet::new(),
+            tail_of_string,
+            wasted_symbols: waste as u16,
+            path: Path::default(),
+        });
+    }
+
+    let mut shared = Vec::with_capacity(count.min(bytes.len()));
+    for &(_, _, path_len) in &scalars {
+        shared.push(reader.varint()?.min(path_len));
+    }
+
+    let suffix_choices: usize = scalars.iter().zip(&shared).map(|(s, shared)| s.2 - shared).sum();
+    let suffixes = reader.take((suffix_choices * choice_bits).div_ceil(8))?;
+    let mut position = 0;
+
+    // The choices are packed the same way as a path, so the shared ones are
+    // kept as they are.
+    let mut previous = Bits::default();
+
+    for (candidate, (&(_, _, path_len), &shared)) in bucket.iter_mut().zip(scalars.iter().zip(&shared)) {
+        if shared * choice_bits > previous.len {
+            return Err("a path shares more choices than the path before it has".to_string());
+        }
+
+        previous.truncate(shared * choice_bits);
+
+        // The rest of the choices are copied a few bytes at a time.
+        let mut remaining = (path_len - shared) * choice_bits;
+
+        while remaining > 0 {
+            let bits = remaining.min(56);
+
+            previous.push(read_bits(suffixes, position, bits), bits);
+            position += bits;
+            remaining -= bits;
+        }
+
+        candidate.path = Path::from_bytes(previous.bytes.clone(), path_len);
+    }
+
+    let mut previous = BitSet::with_capacity(Bounds::factorial(n));
+
+    for candidate in bucket.iter_mut() {
+        previous = read_difference(&mut reader, previous, Bounds::factorial(n))?;
+        candidate.permutations_seen = previous.clone();
+    }
+
+    reader.finish(bucket)
+}
+
+// The fewest bits that hold the biggest symbol in the bucket.
+fn bits_per_symbol(bucket: &VecDeque<Candidate>) -> usize {
+    let max = bucket.iter().flat_map(|c| c.tail_of_string.iter()).max().cloned().unwrap_or(0);
+    (u8::BITS - max.leading_zeros()).max(1) as usize
+}
+
+fn write_difference(bytes: &mut Vec<u8>, previous: &BitSet, current: &BitSet, dense_len: usize) {
+    let mut sparse = vec![];
+    let mut last = 0;
+
+    let changed: Vec<usize> = previous.symmetric_difference(current).collect();
+    write_varint(&mut sparse, changed.len());
+
+    for &id in &changed {
+        write_varint(&mut sparse, id - last);
+        last = id;
+    }
+
+    if sparse.len() <= dense_len || changed.last().is_some_and(|&id| id >= dense_len * 8) {
+        bytes.push(SPARSE);
+        bytes.extend_from_slice(&sparse);
+    } else {
+        let mut dense = vec![0; dense_len];
+        for id in changed {
+            dense[id / 8] |= 0x80 >> (id % 8);
+        }
+
+        bytes.push(DENSE);
+        bytes.extend_from_slice(&dense);
+    }
+}
+
+fn read_difference(reader: &mut Reader, mut previous: BitSet, permutations: usize) -> Result<BitSet, String> {
+    let mut toggle = |id: usize| {
+        if !previous.remove(id) {
+            previous.insert(id);
+        }
+    };
+
+    match reader.take(1)?[0] {
+        SPARSE => {
+            let count = reader.varint()?;
+            let mut id: usize = 0;
+
+            for _ in 0..count {
+                id = id.checked_add(reader.varint()?).filter(|&id| id < permutations)
+                    .ok_or_else(|| "a permutation is out of range".to_string())?;
+
+                toggle(id);
+            }
+        },
+        DENSE => {
+            let dense = reader.take(permutations.div_ceil(8))?;
+            previous.symmetric_difference_with(&BitSet::from_bytes(dense));
+        },
+        tag => return Err(format!("{} isn't a kind of permutation set", tag)),
+    }
+
+    Ok(previous)
+}
+
+// Reads a value that `Bits::push` wrote, a byte at a time.
+fn read_bits(bytes: &[u8], mut position: usize, bits: usize) -> usize {
+    let mut value = 0;
+    let mut read = 0;
+
+    while read < bits {
+        let offset = position % 8;
+        let take = (8 - offset).min(bits - read);
+        let part = (bytes[position / 8] >> offset) as usize & ((1 << take) - 1);
+
+        value |= part << read;
+        read += take;
+        position += take;
+    }
+
+    value
+}
+
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Bits {
+    // Appends the low bits of the value, least significant first, in the same
+    // order as `Path` packs its choices.
+    fn push(&mut self, mut value: usize, mut bits: usize) {
+        while bits > 0 {
+            let offset = self.len % 8;
+            if offset == 0 {
+                self.bytes.push(0);
+            }
+
+            let take = (8 - offset).min(bits);
+            *self.bytes.last_mut().unwrap() |= ((value & ((1 << take) - 1)) << offset) as u8;
+
+            value >>= take;
+            bits -= take;
+            self.len += take;
+        }
+    }
+
+    // Drops the bits after the first len and clears the rest of the last byte
+    // so that pushing continues where it left off.
+    fn truncate(&mut self, len: usize) {
+        self.bytes.truncate(len.div_ceil(8));
+        self.len = len;
+
+        if !len.is_multiple_of(8) {
+            *self.bytes.last_mut().unwrap() &= (1 << (len % 8)) - 1;
+        }
+    }
+}
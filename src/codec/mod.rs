@@ -0,0 +1,196 @@
+mod compact;
+mod packed;
+
+use super::candidate::Candidate;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use bincode::{serialize_into, deserialize_from};
+
+pub const DEFAULT_LEVEL: u32 = 6;
+
+/// How the candidates in a chunk are encoded. Each chunk records its codec in
+/// its header so that it can be read back whichever codec the run uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Codec {
+    /// Plain bincode.
+    #[default]
+    Raw,
+    /// Bincode compressed with zlib at a level from 0 (fastest) to 9 (smallest).
+    Zlib(u32),
+    /// An encoding built for candidates. It stores sparse permutation sets as
+    /// the gaps between permutations and packs the tail into nibbles.
+    Packed,
+    /// Encodes a bucket as a whole. It writes the fields that candidates in a
+    /// bucket share once and each permutation set as its difference from the
+    /// one before it, then compresses the result with zlib.
+    Compact,
+}
+
+impl Codec {
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+
+        match input.split_once(':') {
+            None if input == "raw" => Some(Codec::Raw),
+            None if input == "zlib" || input == "gzip" => Some(Codec::Zlib(DEFAULT_LEVEL)),
+            None if input == "packed" => Some(Codec::Packed),
+            None if input == "compact" => Some(Codec::Compact),
+            Some(("zlib", level)) | Some(("gzip", level)) => match level.parse() {
+                Ok(level) if level <= 9 => Some(Codec::Zlib(level)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The number that identifies the codec in a chunk header. The level
+    /// isn't needed to decode a chunk so it isn't recorded.
+    pub fn id(&self) -> u16 {
+        match self {
+            Codec::Raw => 0,
+            Codec::Zlib(_) => 1,
+            Codec::Packed => 2,
+            Codec::Compact => 3,
+        }
+    }
+
+    pub fn from_id(id: u16) -> Option<Self> {
+        match id {
+            0 => Some(Codec::Raw),
+            1 => Some(Codec::Zlib(DEFAULT_LEVEL)),
+            2 => Some(Codec::Packed),
+            3 => Some(Codec::Compact),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Codec::Raw => "raw",
+            Codec::Zlib(_) => "zlib",
+            Codec::Packed => "packed",
+            Codec::Compact => "compact",
+        }
+    }
+
+    /// Goes after `.dat` in the names of chunk files.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Codec::Raw => "",
+            Codec::Zlib(_) => ".gz",
+            Codec::Packed => ".packed",
+            Codec::Compact => ".compact",
+        }
+    }
+
+    pub fn encode(&self, writer: &mut dyn Write, bucket: &VecDeque<Candidate>, n: usize) -> Result<(), String> {
+        match self {
+            Codec::Raw => serialize_into(writer, bucket).map_err(|e| e.to_string()),
+            Codec::Zlib(level) => {
+                let mut encoder = ZlibEncoder::new(writer, Compression::new(*level));
+                serialize_into(&mut encoder, bucket).map_err(|e| e.to_string())?;
+                encoder.finish().map(|_| ()).map_err(|e| e.to_string())
+            },
+            Codec::Packed => writer.write_all(&packed::encode(bucket)).map_err(|e| e.to_string()),
+            Codec::Compact => {
+                let mut encoder = ZlibEncoder::new(writer, Compression::new(DEFAULT_LEVEL));
+                encoder.write_all(&compact::encode(bucket, n)).map_err(|e| e.to_string())?;
+                encoder.finish().map(|_| ()).map_err(|e| e.to_string())
+            },
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8], n: usize) -> Result<VecDeque<Candidate>, String> {
+        match self {
+            Codec::Raw => deserialize_from(bytes).map_err(|e| e.to_string()),
+            Codec::Zlib(_) => deserialize_from(ZlibDecoder::new(bytes)).map_err(|e| e.to_string()),
+            Codec::Packed => packed::decode(bytes),
+            Codec::Compact => {
+                let mut compacted = vec![];
+                ZlibDecoder::new(bytes).read_to_end(&mut compacted).map_err(|e| e.to_string())?;
+                compact::decode(&compacted, n)
+            },
+        }
+    }
+
+    /// The codecs that `bench-codecs` compares.
+    pub fn all() -> Vec<Self> {
+        vec![Codec::Raw, Codec::Zlib(1), Codec::Zlib(DEFAULT_LEVEL), Codec::Zlib(9), Codec::Packed, Codec::Compact]
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Codec::Zlib(level) => write!(f, "zlib:{}", level),
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+/// How one codec did on a bucket. The throughputs are in terms of the raw
+/// size so that codecs can be compared with each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Benchmark {
+    pub codec: Codec,
+    pub raw_bytes: usize,
+    pub bytes: usize,
+    pub encode: Duration,
+    pub decode: Duration,
+}
+
+impl Benchmark {
+    /// Encodes and decodes the bucket with the codec, taking the fastest of
+    /// a few rounds, and checks the bucket survives the round trip.
+    pub fn run(codec: Codec, bucket: &VecDeque<Candidate>, n: usize, rounds: usize) -> Result<Self, String> {
+        let mut raw = vec![];
+        Codec::Raw.encode(&mut raw, bucket, n)?;
+
+        let mut bytes = vec![];
+        let mut encode = Duration::MAX;
+        let mut decode = Duration::MAX;
+
+        for _ in 0..rounds.max(1) {
+            bytes.clear();
+
+            let start = Instant::now();
+            codec.encode(&mut bytes, bucket, n)?;
+            encode = encode.min(start.elapsed());
+
+            let start = Instant::now();
+            let decoded = codec.decode(&bytes, n)?;
+            decode = decode.min(start.elapsed());
+
+            if &decoded != bucket {
+                return Err(format!("The {} codec didn't decode the bucket it encoded", codec));
+            }
+        }
+
+        Ok(Self { codec, raw_bytes: raw.len(), bytes: bytes.len(), encode, decode })
+    }
+
+    pub fn ratio(&self) -> f64 {
+        self.raw_bytes as f64 / self.bytes.max(1) as f64
+    }
+
+    pub fn encode_throughput(&self) -> f64 {
+        Self::throughput(self.raw_bytes, self.encode)
+    }
+
+    pub fn decode_throughput(&self) -> f64 {
+        Self::throughput(self.raw_bytes, self.decode)
+    }
+
+    // Mebibytes a second.
+    fn throughput(bytes: usize, duration: Duration) -> f64 {
+        bytes as f64 / duration.as_secs_f64().max(1e-9) / (1024. * 1024.)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,169 @@
+// Candidates in a bucket have seen few of the n! permutations early in the
+// search and most of them late on, so each permutation set is stored either
+// as a bitmap or as the gaps between its permutations, whichever is smaller.
+// Numbers are LEB128 varints and the tail is packed two symbols a byte unless
+// n is too big for a symbol to fit in four bits.
+
+use super::super::candidate::{Candidate, Path};
+
+use bit_set::BitSet;
+use std::collections::VecDeque;
+
+const DENSE: u8 = 0;
+const SPARSE: u8 = 1;
+
+pub fn encode(bucket: &VecDeque<Candidate>) -> Vec<u8> {
+    let wide = bucket.iter().flat_map(|c| c.tail_of_string.iter()).any(|&s| s > 0x0f);
+
+    let mut bytes = vec![];
+    write_varint(&mut bytes, bucket.len());
+    bytes.push(wide as u8);
+
+    for candidate in bucket {
+        write_varint(&mut bytes, candidate.wasted_symbols as usize);
+
+        write_varint(&mut bytes, candidate.tail_of_string.len());
+        match wide {
+            true => bytes.extend_from_slice(&candidate.tail_of_string),
+            false => for pair in candidate.tail_of_string.chunks(2) {
+                bytes.push(pair[0] | pair.get(1).map_or(0, |s| s << 4));
+            },
+        }
+
+        write_permutations(&mut bytes, &candidate.permutations_seen);
+
+        write_varint(&mut bytes, candidate.path.len());
+        write_varint(&mut bytes, candidate.path.bytes().len());
+        bytes.extend_from_slice(candidate.path.bytes());
+    }
+
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<VecDeque<Candidate>, String> {
+    let mut reader = Reader::new(bytes);
+    let count = reader.varint()?;
+    let wide = reader.take(1)?[0] != 0;
+
+    let mut bucket = VecDeque::with_capacity(count.min(bytes.len()));
+
+    for _ in 0..count {
+        let wasted_symbols = reader.varint()? as u16;
+
+        let tail_len = reader.varint()?;
+        let tail_of_string = match wide {
+            true => reader.take(tail_len)?.to_vec(),
+            false => {
+                let packed = reader.take(tail_len.div_ceil(2))?;
+                (0..tail_len).map(|i| (packed[i / 2] >> (4 * (i % 2))) & 0x0f).collect()
+            },
+        };
+
+        let permutations_seen = reader.permutations()?;
+
+        let path_len = reader.varint()?;
+        let path_bytes = reader.varint()?;
+        let path = Path::from_bytes(reader.take(path_bytes)?.to_vec(), path_len);
+
+        bucket.push_back(Candidate { permutations_seen, tail_of_string, wasted_symbols, path });
+    }
+
+    reader.finish(bucket)
+}
+
+fn write_permutations(bytes: &mut Vec<u8>, permutations: &BitSet) {
+    let dense = permutations.get_ref().to_bytes();
+
+    let mut sparse = vec![];
+    let mut previous = 0;
+
+    write_varint(&mut sparse, permutations.len());
+    for id in permutations.iter() {
+        write_varint(&mut sparse, id - previous);
+        previous = id;
+    }
+
+    if sparse.len() < dense.len() {
+        bytes.push(SPARSE);
+        bytes.extend_from_slice(&sparse);
+    } else {
+        bytes.push(DENSE);
+        write_varint(bytes, dense.len());
+        bytes.extend_from_slice(&dense);
+    }
+}
+
+pub fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(len).filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| "the packed candidates end too soon".to_string())?;
+
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+
+        Ok(slice)
+    }
+
+    pub fn varint(&mut self) -> Result<usize, String> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as usize) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err("a varint in the packed candidates is too long".to_string())
+    }
+
+    /// Returns the bucket if every byte was read.
+    pub fn finish(self, bucket: VecDeque<Candidate>) -> Result<VecDeque<Candidate>, String> {
+        match self.bytes.len() - self.position {
+            0 => Ok(bucket),
+            left => Err(format!("{} bytes were left over after the last candidate", left)),
+        }
+    }
+
+    fn permutations(&mut self) -> Result<BitSet, String> {
+        match self.take(1)?[0] {
+            DENSE => {
+                let len = self.varint()?;
+                Ok(BitSet::from_bytes(self.take(len)?))
+            },
+            SPARSE => {
+                let count = self.varint()?;
+                let mut permutations = BitSet::new();
+                let mut id = 0;
+
+                for _ in 0..count {
+                    id += self.varint()?;
+                    permutations.insert(id);
+                }
+
+                Ok(permutations)
+            },
+            tag => Err(format!("{} isn't a kind of permutation set", tag)),
+        }
+    }
+}
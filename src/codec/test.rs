@@ -0,0 +1,184 @@
+use super::*;
+use super::super::candidate::Path;
+
+// Expands the seed for a few steps to get candidates with a mix of tails,
+// paths, waste and permutations.
+fn bucket(n: usize, steps: usize) -> VecDeque<Candidate> {
+    let mut bucket = VecDeque::from(vec![Candidate::seed(n)]);
+
+    for _ in 0..steps {
+        bucket = bucket.into_iter().flat_map(|c| c.expand(usize::MAX, n, true)).collect();
+    }
+
+    bucket
+}
+
+fn round_trip(codec: Codec, bucket: &VecDeque<Candidate>, n: usize) -> (VecDeque<Candidate>, usize) {
+    let mut bytes = vec![];
+    codec.encode(&mut bytes, bucket, n).unwrap();
+
+    (codec.decode(&bytes, n).unwrap(), bytes.len())
+}
+
+mod parse {
+    use super::*;
+
+    #[test]
+    fn it_parses_each_codec() {
+        assert_eq!(Codec::parse("raw"), Some(Codec::Raw));
+        assert_eq!(Codec::parse("zlib"), Some(Codec::Zlib(DEFAULT_LEVEL)));
+        assert_eq!(Codec::parse(" ZLIB:9 "), Some(Codec::Zlib(9)));
+        assert_eq!(Codec::parse("gzip:0"), Some(Codec::Zlib(0)));
+        assert_eq!(Codec::parse("packed"), Some(Codec::Packed));
+        assert_eq!(Codec::parse("compact"), Some(Codec::Compact));
+    }
+
+    #[test]
+    fn it_returns_none_for_unknown_codecs_and_levels() {
+        assert_eq!(Codec::parse("lz4"), None);
+        assert_eq!(Codec::parse("zlib:10"), None);
+        assert_eq!(Codec::parse("zlib:"), None);
+        assert_eq!(Codec::parse("raw:1"), None);
+    }
+
+    #[test]
+    fn it_parses_what_it_displays() {
+        for codec in Codec::all() {
+            assert_eq!(Codec::parse(&codec.to_string()), Some(codec));
+        }
+    }
+}
+
+mod id {
+    use super::*;
+
+    #[test]
+    fn it_finds_each_codec_from_its_id() {
+        assert_eq!(Codec::from_id(Codec::Raw.id()), Some(Codec::Raw));
+        assert_eq!(Codec::from_id(Codec::Zlib(1).id()), Some(Codec::Zlib(DEFAULT_LEVEL)));
+        assert_eq!(Codec::from_id(Codec::Packed.id()), Some(Codec::Packed));
+        assert_eq!(Codec::from_id(Codec::Compact.id()), Some(Codec::Compact));
+        assert_eq!(Codec::from_id(4), None);
+    }
+}
+
+mod encode {
+    use super::*;
+
+    #[test]
+    fn it_decodes_what_each_codec_encodes() {
+        let bucket = bucket(5, 6);
+
+        for codec in Codec::all() {
+            assert_eq!(round_trip(codec, &bucket, 5).0, bucket);
+        }
+    }
+
+    #[test]
+    fn it_packs_candidates_into_fewer_bytes_than_raw() {
+        let bucket = bucket(5, 6);
+
+        let (_, raw) = round_trip(Codec::Raw, &bucket, 5);
+        let (_, packed) = round_trip(Codec::Packed, &bucket, 5);
+
+        assert!(packed * 2 < raw);
+    }
+
+    #[test]
+    fn it_packs_symbols_that_do_not_fit_in_a_nibble() {
+        let candidate = Candidate {
+            permutations_seen: [0, 5, 1_000_000].iter().cloned().collect(),
+            tail_of_string: (1..18).collect(),
+            wasted_symbols: 3,
+            path: Path::default().push(16, 18),
+        };
+
+        let bucket = VecDeque::from(vec![candidate]);
+        assert_eq!(round_trip(Codec::Packed, &bucket, 18).0, bucket);
+    }
+
+    #[test]
+    fn it_returns_an_error_for_truncated_packed_candidates() {
+        let mut bytes = vec![];
+        Codec::Packed.encode(&mut bytes, &bucket(4, 2), 4).unwrap();
+        bytes.pop();
+
+        assert_eq!(Codec::Packed.decode(&bytes, 4), Err("the packed candidates end too soon".to_string()));
+    }
+}
+
+mod compact {
+    use super::*;
+
+    // The candidates in a bucket all have the same waste and permutations.
+    fn bucket_of(n: usize, steps: usize) -> VecDeque<Candidate> {
+        let all = bucket(n, steps);
+        let first = all.iter().find(|c| c.wasted_symbols > 0).unwrap();
+        let key = (first.wasted_symbols, first.number_of_permutations());
+
+        all.into_iter().filter(|c| (c.wasted_symbols, c.number_of_permutations()) == key).collect()
+    }
+
+    #[test]
+    fn it_decodes_buckets_and_mixed_candidates() {
+        for (bucket, n) in [(bucket_of(5, 7), 5), (bucket(5, 6), 5), (bucket(3, 4), 3), (VecDeque::new(), 5)] {
+            assert_eq!(round_trip(Codec::Compact, &bucket, n).0, bucket);
+        }
+    }
+
+    #[test]
+    fn it_encodes_a_bucket_in_fewer_bytes_than_packed_and_zlib() {
+        let bucket = bucket_of(5, 7);
+
+        let (_, packed) = round_trip(Codec::Packed, &bucket, 5);
+        let (_, zlib) = round_trip(Codec::Zlib(DEFAULT_LEVEL), &bucket, 5);
+        let (_, compact) = round_trip(Codec::Compact, &bucket, 5);
+
+        assert!(compact < packed);
+        assert!(compact < zlib);
+    }
+
+    #[test]
+    fn it_stores_a_large_difference_as_a_bitmap() {
+        let candidate = |permutations: &[usize]| Candidate {
+            permutations_seen: permutations.iter().cloned().collect(),
+            ..Candidate::seed(4)
+        };
+
+        let bucket = VecDeque::from(vec![candidate(&[0, 23]), candidate(&(1..23).collect::<Vec<_>>())]);
+        assert_eq!(round_trip(Codec::Compact, &bucket, 4).0, bucket);
+    }
+
+    #[test]
+    fn it_returns_an_error_for_truncated_candidates() {
+        let mut compacted = super::super::compact::encode(&bucket(4, 2), 4);
+        compacted.pop();
+
+        let mut encoder = ZlibEncoder::new(vec![], Compression::new(DEFAULT_LEVEL));
+        encoder.write_all(&compacted).unwrap();
+        let mut bytes = encoder.finish().unwrap();
+
+        assert_eq!(Codec::Compact.decode(&bytes, 4), Err("the packed candidates end too soon".to_string()));
+
+        bytes.truncate(bytes.len() / 2);
+        assert!(Codec::Compact.decode(&bytes, 4).is_err());
+    }
+}
+
+mod benchmark {
+    use super::*;
+
+    #[test]
+    fn it_measures_the_size_of_each_codec_against_raw() {
+        let bucket = bucket(5, 5);
+
+        let raw = Benchmark::run(Codec::Raw, &bucket, 5, 1).unwrap();
+        let zlib = Benchmark::run(Codec::Zlib(9), &bucket, 5, 2).unwrap();
+
+        assert_eq!(raw.bytes, raw.raw_bytes);
+        assert_eq!(raw.ratio(), 1.);
+        assert_eq!(zlib.raw_bytes, raw.raw_bytes);
+        assert!(zlib.ratio() > 1.);
+        assert!(zlib.decode_throughput() > 0.);
+    }
+}
@@ -0,0 +1,437 @@
+use super::bounds::Bounds;
+use super::codec::{Codec, DEFAULT_LEVEL};
+use super::disk::{Disk, SPLIT_SIZE};
+use super::frontier::Frontier;
+use super::offload::{Offload, HIGH_WATERMARK, LOW_WATERMARK};
+use super::stop::Stop;
+use super::ui::UI;
+
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::time::Duration;
+
+const MIN_N: usize = 2;
+const MAX_N: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Prune {
+    Eager,
+    Lazy,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub n: Option<usize>,
+    pub memory: Option<f64>,
+    pub gzip: Option<bool>,
+    pub codec: Option<Codec>,
+    pub verbose: Option<bool>,
+    pub banner: Option<bool>,
+    pub scratch: Option<String>,
+    pub split_size: Option<usize>,
+    pub offload: Option<Offload>,
+    pub high_watermark: Option<f64>,
+    pub low_watermark: Option<f64>,
+    pub prune: Option<Prune>,
+    pub events: Option<String>,
+    pub witnesses: Option<String>,
+    pub paths: Option<bool>,
+    pub bounds: Option<String>,
+    pub proof: Option<String>,
+    pub checkpoint_every: Option<Duration>,
+    pub status: Option<bool>,
+    pub max_waste: Option<usize>,
+    pub time_limit: Option<Duration>,
+    pub max_expansions: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    pub n: usize,
+    pub memory: f64,
+    pub codec: Codec,
+    pub verbose: bool,
+    pub banner: bool,
+    pub scratch: String,
+    pub split_size: usize,
+    pub offload: Offload,
+    pub high_watermark: f64,
+    pub low_watermark: f64,
+    pub prune: Prune,
+    pub events: Option<String>,
+    pub witnesses: Option<String>,
+    /// Whether candidates keep the choices that rebuild their strings, which
+    /// witnesses need. It's off unless asked for, or a witnesses file is given,
+    /// because it makes each candidate larger.
+    pub paths: bool,
+    pub bounds: Option<String>,
+    pub proof: Option<String>,
+    pub checkpoint_every: Option<Duration>,
+    pub status: bool,
+    pub stop: Stop,
+}
+
+impl Settings {
+    pub fn read(path: &str) -> Result<Self, Vec<String>> {
+        let text = read_to_string(path)
+            .map_err(|e| vec![format!("{}: {}", path, e)])?;
+
+        Self::parse(&text).map_err(|errors| {
+            errors.into_iter().map(|e| format!("{}: {}", path, e)).collect()
+        })
+    }
+
+    /// Parses `key = value` lines. If any of them don't parse, the values that
+    /// did are validated too so every problem with the file is reported at once.
+    pub fn parse(text: &str) -> Result<Self, Vec<String>> {
+        let mut settings = Self::default();
+        let mut lines = HashMap::new();
+        let mut errors = vec![];
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => {
+                    errors.push((number + 1, "expected 'key = value'".to_string()));
+                    continue;
+                },
+            };
+
+            match settings.set(key, value) {
+                Ok(()) => { lines.insert(key.to_string(), number + 1); },
+                Err(error) => errors.push((number + 1, error)),
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(settings);
+        }
+
+        errors.extend(settings.problems(&lines));
+        errors.sort_by_key(|&(number, _)| number);
+
+        Err(errors.into_iter().map(|(number, error)| format!("line {}: {}", number, error)).collect())
+    }
+
+    // The problems a run would find with the values that parsed, on the line
+    // that set them. Problems that also depend on a key the file doesn't set
+    // are left for when the config is validated.
+    fn problems(&self, lines: &HashMap<String, usize>) -> Vec<(usize, String)> {
+        let default = Config::default();
+
+        let settings = Self {
+            n: self.n.or(Some(default.n)),
+            memory: self.memory.or(Some(default.memory)),
+            gzip: self.gzip.or(Some(false)),
+            verbose: self.verbose.or(Some(default.verbose)),
+            ..self.clone()
+        };
+
+        Config::resolve(settings).problems().into_iter()
+            .filter(|(keys, _)| keys.iter().all(|key| lines.contains_key(*key)))
+            .map(|(keys, error)| (lines[keys[0]], error))
+            .collect()
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "n" => self.n = Some(Self::parse_value(key, value)?),
+            "memory" => self.memory = Some(Self::parse_value(key, value)?),
+            "gzip" => self.gzip = Some(Self::parse_boolean(key, value)?),
+            "codec" => self.codec = Some(Self::parse_codec(key, value)?),
+            "verbose" => self.verbose = Some(Self::parse_boolean(key, value)?),
+            "banner" => self.banner = Some(Self::parse_boolean(key, value)?),
+            "scratch" => self.scratch = Some(value.to_string()),
+            "split_size" => self.split_size = Some(Self::parse_value(key, value)?),
+            "offload" => self.offload = Some(Self::parse_offload(key, value)?),
+            "high_watermark" => self.high_watermark = Some(Self::parse_value(key, value)?),
+            "low_watermark" => self.low_watermark = Some(Self::parse_value(key, value)?),
+            "prune" => self.prune = Some(Self::parse_prune(key, value)?),
+            "events" => self.events = Some(value.to_string()),
+            "witnesses" => self.witnesses = Some(value.to_string()),
+            "paths" => self.paths = Some(Self::parse_boolean(key, value)?),
+            "bounds" => self.bounds = Some(value.to_string()),
+            "proof" => self.proof = Some(value.to_string()),
+            "checkpoint_every" => self.checkpoint_every = Some(Self::parse_duration(key, value)?),
+            "status" => self.status = Some(Self::parse_boolean(key, value)?),
+            "max_waste" => self.max_waste = Some(Self::parse_value(key, value)?),
+            "time_limit" => self.time_limit = Some(Self::parse_duration(key, value)?),
+            "max_expansions" => self.max_expansions = Some(Self::parse_value(key, value)?),
+            _ => return Err(format!("unknown key '{}'", key)),
+        }
+
+        Ok(())
+    }
+
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            n: self.n.or(other.n),
+            memory: self.memory.or(other.memory),
+            gzip: self.gzip.or(other.gzip),
+            codec: self.codec.or(other.codec),
+            verbose: self.verbose.or(other.verbose),
+            banner: self.banner.or(other.banner),
+            scratch: self.scratch.or(other.scratch),
+            split_size: self.split_size.or(other.split_size),
+            offload: self.offload.or(other.offload),
+            high_watermark: self.high_watermark.or(other.high_watermark),
+            low_watermark: self.low_watermark.or(other.low_watermark),
+            prune: self.prune.or(other.prune),
+            events: self.events.or(other.events),
+            witnesses: self.witnesses.or(other.witnesses),
+            paths: self.paths.or(other.paths),
+            bounds: self.bounds.or(other.bounds),
+            proof: self.proof.or(other.proof),
+            checkpoint_every: self.checkpoint_every.or(other.checkpoint_every),
+            status: self.status.or(other.status),
+            max_waste: self.max_waste.or(other.max_waste),
+            time_limit: self.time_limit.or(other.time_limit),
+            max_expansions: self.max_expansions.or(other.max_expansions),
+        }
+    }
+
+    pub fn parse_value<T: ::std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+        value.trim().parse().map_err(|_| format!("invalid value '{}' for '{}'", value, key))
+    }
+
+    pub fn parse_boolean(key: &str, value: &str) -> Result<bool, String> {
+        match value.to_lowercase().trim() {
+            "y" | "yes" | "true" => Ok(true),
+            "n" | "no" | "false" => Ok(false),
+            _ => Err(format!("invalid boolean '{}' for '{}'", value, key)),
+        }
+    }
+
+    pub fn parse_duration(key: &str, value: &str) -> Result<Duration, String> {
+        Stop::parse_duration(value).ok_or_else(|| {
+            format!("invalid duration '{}' for '{}' (e.g. 90s, 30m, 12h or 2d)", value, key)
+        })
+    }
+
+    pub fn parse_codec(key: &str, value: &str) -> Result<Codec, String> {
+        Codec::parse(value).ok_or_else(|| {
+            format!("invalid codec '{}' for '{}' (expected raw, zlib, zlib:0 to zlib:9, packed or compact)", value, key)
+        })
+    }
+
+    pub fn parse_prune(key: &str, value: &str) -> Result<Prune, String> {
+        match value.to_lowercase().trim() {
+            "eager" => Ok(Prune::Eager),
+            "lazy" => Ok(Prune::Lazy),
+            _ => Err(format!("invalid prune mode '{}' for '{}' (expected eager or lazy)", value, key)),
+        }
+    }
+
+    pub fn parse_offload(key: &str, value: &str) -> Result<Offload, String> {
+        Offload::parse(value).ok_or_else(|| {
+            format!("invalid offload policy '{}' for '{}' (expected unlikely, largest or waste)", value, key)
+        })
+    }
+}
+
+impl Config {
+    pub fn resolve(settings: Settings) -> Self {
+        let default = Self::default();
+        let paths = settings.paths.unwrap_or(settings.witnesses.is_some() || default.paths);
+
+        Self {
+            n: settings.n.unwrap_or_else(UI::ask_for_n),
+            memory: settings.memory.unwrap_or_else(UI::ask_for_memory),
+            codec: settings.codec.unwrap_or_else(|| match settings.gzip.unwrap_or_else(UI::ask_for_gzip) {
+                true => Codec::Zlib(DEFAULT_LEVEL),
+                false => Codec::Raw,
+            }),
+            verbose: settings.verbose.unwrap_or_else(UI::ask_for_verbose),
+            banner: settings.banner.unwrap_or(default.banner),
+            scratch: settings.scratch.unwrap_or(default.scratch),
+            split_size: settings.split_size.unwrap_or(default.split_size),
+            offload: settings.offload.unwrap_or(default.offload),
+            high_watermark: settings.high_watermark.unwrap_or(default.high_watermark),
+            low_watermark: settings.low_watermark.unwrap_or(default.low_watermark),
+            prune: settings.prune.unwrap_or(default.prune),
+            events: settings.events.or(default.events),
+            witnesses: settings.witnesses.or(default.witnesses),
+            paths,
+            bounds: settings.bounds.or(default.bounds),
+            proof: settings.proof.or(default.proof),
+            checkpoint_every: settings.checkpoint_every.or(default.checkpoint_every),
+            status: settings.status.unwrap_or(default.status),
+            stop: Stop {
+                max_waste: settings.max_waste,
+                time_limit: settings.time_limit,
+                max_expansions: settings.max_expansions,
+            },
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let errors: Vec<_> = self.problems().into_iter().map(|(_, error)| error).collect();
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    // Every problem with the config, along with the keys whose values it
+    // depends on.
+    fn problems(&self) -> Vec<(&'static [&'static str], String)> {
+        let mut errors: Vec<(&'static [&'static str], String)> = vec![];
+
+        if self.n < MIN_N || self.n > MAX_N {
+            errors.push((&["n"], format!("n must be between {} and {} (got {})", MIN_N, MAX_N, self.n)));
+        }
+
+        if !self.memory.is_finite() || self.memory <= 0. {
+            errors.push((&["memory"], format!("memory must be a positive number of gigabytes (got {})", self.memory)));
+        } else if errors.is_empty() && Frontier::queue_capacity(self.memory, self.n, self.paths) == 0 {
+            let bytes = Frontier::memory_per_candidate(self.n, self.paths);
+            errors.push((&["memory", "n"], format!("memory must fit at least one candidate of {} bytes (got {}GiB)", bytes, self.memory)));
+        }
+
+        if self.scratch.trim().is_empty() {
+            errors.push((&["scratch"], "scratch must be the path to a directory".to_string()));
+        } else if Path::new(&self.scratch).exists() && !Path::new(&self.scratch).is_dir() {
+            errors.push((&["scratch"], format!("scratch must be a directory ('{}' is a file)", self.scratch)));
+        } else if let Err(error) = Disk::check(&self.scratch) {
+            errors.push((&["scratch"], error));
+        }
+
+        if self.split_size == 0 {
+            errors.push((&["split_size"], "split_size must be at least 1".to_string()));
+        }
+
+        if !(self.high_watermark > 0. && self.high_watermark <= 1.) {
+            errors.push((&["high_watermark"], format!("high_watermark must be more than 0 and at most 1 (got {})", self.high_watermark)));
+        } else if !(self.low_watermark >= 0. && self.low_watermark <= self.high_watermark) {
+            errors.push((&["low_watermark", "high_watermark"], format!("low_watermark must be between 0 and high_watermark (got {})", self.low_watermark)));
+        }
+
+        if self.stop.time_limit == Some(Duration::from_secs(0)) {
+            errors.push((&["time_limit"], "time_limit must be at least one second".to_string()));
+        }
+
+        if self.checkpoint_every == Some(Duration::from_secs(0)) {
+            errors.push((&["checkpoint_every"], "checkpoint_every must be at least one second".to_string()));
+        }
+
+        if self.stop.max_expansions == Some(0) {
+            errors.push((&["max_expansions"], "max_expansions must be at least 1".to_string()));
+        }
+
+        if let Some(path) = self.events.as_ref().filter(|p| p.as_str() != "-") {
+            if !Self::in_existing_directory(path) {
+                errors.push((&["events"], format!("events must be '-' or a file in an existing directory (got '{}')", path)));
+            }
+        }
+
+        if let Some(path) = self.witnesses.as_ref().filter(|p| !Self::in_existing_directory(p)) {
+            errors.push((&["witnesses"], format!("witnesses must be a file in an existing directory (got '{}')", path)));
+        } else if self.witnesses.is_some() && !self.paths {
+            errors.push((&["witnesses", "paths"], "witnesses needs paths to be kept".to_string()));
+        }
+
+        if let Some(path) = self.proof.as_ref().filter(|p| !Self::in_existing_directory(p)) {
+            errors.push((&["proof"], format!("proof must be a file in an existing directory (got '{}')", path)));
+        }
+
+        if let Some(path) = self.bounds.as_ref().filter(|_| errors.is_empty()) {
+            if let Err(bounds_errors) = Bounds::read(path, self.n) {
+                errors.extend(bounds_errors.into_iter().map(|e| (&["bounds", "n"][..], e)));
+            }
+        }
+
+        errors
+    }
+}
+
+impl Config {
+    /// Applies the settings given to `resume` to the config saved in a
+    /// checkpoint. Only the settings that don't change what the search finds
+    /// can be given, and the stop conditions start afresh.
+    pub fn resumed(self, settings: Settings) -> Result<Self, Vec<String>> {
+        let fixed = [
+            ("n", settings.n.is_some()),
+            ("gzip", settings.gzip.is_some()),
+            ("codec", settings.codec.is_some()),
+            ("split_size", settings.split_size.is_some()),
+            ("prune", settings.prune.is_some()),
+            ("witnesses", settings.witnesses.is_some()),
+            ("paths", settings.paths.is_some()),
+            ("bounds", settings.bounds.is_some()),
+            ("proof", settings.proof.is_some()),
+        ];
+
+        let errors: Vec<_> = fixed.iter()
+            .filter(|(_, given)| *given)
+            .map(|(key, _)| format!("{} can't be changed when resuming", key))
+            .collect();
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let config = Self {
+            memory: settings.memory.unwrap_or(self.memory),
+            offload: settings.offload.unwrap_or(self.offload),
+            high_watermark: settings.high_watermark.unwrap_or(self.high_watermark),
+            low_watermark: settings.low_watermark.unwrap_or(self.low_watermark),
+            verbose: settings.verbose.unwrap_or(self.verbose),
+            banner: settings.banner.unwrap_or(self.banner),
+            events: settings.events.or(self.events),
+            checkpoint_every: settings.checkpoint_every.or(self.checkpoint_every),
+            status: settings.status.unwrap_or(self.status),
+            stop: Stop {
+                max_waste: settings.max_waste,
+                time_limit: settings.time_limit,
+                max_expansions: settings.max_expansions,
+            },
+            ..self
+        };
+
+        // The bounds were read into the checkpoint so the file isn't needed.
+        Self { bounds: None, ..config.clone() }.validate()?;
+        Ok(config)
+    }
+
+    fn in_existing_directory(path: &str) -> bool {
+        let parent = Path::new(path).parent().filter(|p| !p.as_os_str().is_empty());
+        parent.is_none_or(|p| p.is_dir())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            n: 5,
+            memory: 12.,
+            codec: Codec::Raw,
+            verbose: false,
+            banner: true,
+            scratch: "scratch-files".to_string(),
+            split_size: SPLIT_SIZE,
+            offload: Offload::default(),
+            high_watermark: HIGH_WATERMARK,
+            low_watermark: LOW_WATERMARK,
+            prune: Prune::Eager,
+            events: None,
+            witnesses: None,
+            paths: false,
+            bounds: None,
+            proof: None,
+            checkpoint_every: None,
+            status: true,
+            stop: Stop::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
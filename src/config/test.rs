@@ -0,0 +1,327 @@
+use super::*;
+
+type Subject = Config;
+
+fn valid() -> Subject {
+    Subject { n: 4, memory: 1., scratch: "/tmp/superpermutation-config".to_string(), ..Subject::default() }
+}
+
+mod parse {
+    use super::*;
+
+    #[test]
+    fn it_reads_every_setting_from_key_value_lines() {
+        let text = "
+            # A comment on its own line
+            n = 4
+            memory = 2.5
+            gzip = yes
+            codec = zlib:9
+            verbose = no       # trailing comment
+            banner = false
+            scratch = /tmp/scratch
+            split_size = 1000
+            offload = largest
+            high_watermark = 0.9
+            low_watermark = 0.5
+            prune = lazy
+            events = events.jsonl
+            witnesses = witnesses.txt
+            bounds = n6.bounds
+            proof = n6.proof
+            checkpoint_every = 1h
+            status = no
+            paths = no
+            max_waste = 12
+            time_limit = 90m
+            max_expansions = 1000000
+        ";
+
+        let settings = Settings::parse(text).unwrap();
+
+        assert_eq!(settings.n, Some(4));
+        assert_eq!(settings.memory, Some(2.5));
+        assert_eq!(settings.gzip, Some(true));
+        assert_eq!(settings.codec, Some(Codec::Zlib(9)));
+        assert_eq!(settings.verbose, Some(false));
+        assert_eq!(settings.banner, Some(false));
+        assert_eq!(settings.scratch, Some("/tmp/scratch".to_string()));
+        assert_eq!(settings.split_size, Some(1000));
+        assert_eq!(settings.offload, Some(Offload::Largest));
+        assert_eq!(settings.high_watermark, Some(0.9));
+        assert_eq!(settings.low_watermark, Some(0.5));
+        assert_eq!(settings.prune, Some(Prune::Lazy));
+        assert_eq!(settings.events, Some("events.jsonl".to_string()));
+        assert_eq!(settings.witnesses, Some("witnesses.txt".to_string()));
+        assert_eq!(settings.bounds, Some("n6.bounds".to_string()));
+        assert_eq!(settings.proof, Some("n6.proof".to_string()));
+        assert_eq!(settings.checkpoint_every, Some(Duration::from_secs(3600)));
+        assert_eq!(settings.status, Some(false));
+        assert_eq!(settings.paths, Some(false));
+        assert_eq!(settings.max_waste, Some(12));
+        assert_eq!(settings.time_limit, Some(Duration::from_secs(5400)));
+        assert_eq!(settings.max_expansions, Some(1000000));
+    }
+
+    #[test]
+    fn it_leaves_missing_settings_unset() {
+        let settings = Settings::parse("n = 5").unwrap();
+
+        assert_eq!(settings.n, Some(5));
+        assert_eq!(settings.memory, None);
+        assert_eq!(settings.scratch, None);
+    }
+
+    #[test]
+    fn it_reports_every_problem_with_its_line_number() {
+        let text = "n = five\ngzip = maybe\ncolour = blue\nmemory\nprune = often\ntime_limit = soon\ncodec = lz4\noffload = random";
+        let errors = Settings::parse(text).unwrap_err();
+
+        assert_eq!(errors, vec![
+            "line 1: invalid value 'five' for 'n'",
+            "line 2: invalid boolean 'maybe' for 'gzip'",
+            "line 3: unknown key 'colour'",
+            "line 4: expected 'key = value'",
+            "line 5: invalid prune mode 'often' for 'prune' (expected eager or lazy)",
+            "line 6: invalid duration 'soon' for 'time_limit' (e.g. 90s, 30m, 12h or 2d)",
+            "line 7: invalid codec 'lz4' for 'codec' (expected raw, zlib, zlib:0 to zlib:9, packed or compact)",
+            "line 8: invalid offload policy 'random' for 'offload' (expected unlikely, largest or waste)",
+        ]);
+    }
+
+    #[test]
+    fn it_validates_the_values_that_parsed_when_others_did_not() {
+        let text = "n = 40\nmemory = lots\nsplit_size = 0\nlow_watermark = 2\nwitnesses = /no/such/dir/w.txt";
+        let errors = Settings::parse(text).unwrap_err();
+
+        // The low watermark is only checked against a high watermark the file sets.
+        assert_eq!(errors, vec![
+            "line 1: n must be between 2 and 20 (got 40)",
+            "line 2: invalid value 'lots' for 'memory'",
+            "line 3: split_size must be at least 1",
+            "line 5: witnesses must be a file in an existing directory (got '/no/such/dir/w.txt')",
+        ]);
+    }
+}
+
+mod or {
+    use super::*;
+
+    #[test]
+    fn it_prefers_its_own_settings_over_the_others() {
+        let flags = Settings { n: Some(5), ..Settings::default() };
+        let file = Settings { n: Some(4), memory: Some(2.), ..Settings::default() };
+
+        let settings = flags.or(file);
+
+        assert_eq!(settings.n, Some(5));
+        assert_eq!(settings.memory, Some(2.));
+        assert_eq!(settings.gzip, None);
+    }
+}
+
+mod resolve {
+    use super::*;
+
+    #[test]
+    fn it_uses_defaults_for_settings_that_are_not_prompted_for() {
+        let settings = Settings {
+            n: Some(4),
+            memory: Some(1.),
+            gzip: Some(true),
+            verbose: Some(false),
+            ..Settings::default()
+        };
+
+        let subject = Subject::resolve(settings);
+
+        assert_eq!(subject.n, 4);
+        assert_eq!(subject.codec, Codec::Zlib(6));
+        assert!(subject.banner);
+        assert_eq!(subject.scratch, "scratch-files");
+        assert_eq!(subject.split_size, SPLIT_SIZE);
+        assert_eq!(subject.prune, Prune::Eager);
+        assert_eq!(subject.offload, Offload::Unlikely);
+        assert_eq!((subject.high_watermark, subject.low_watermark), (HIGH_WATERMARK, LOW_WATERMARK));
+        assert!(!subject.paths);
+    }
+
+    #[test]
+    fn it_keeps_paths_for_a_witnesses_file_unless_told_not_to() {
+        let settings = |paths| Settings {
+            n: Some(4),
+            memory: Some(1.),
+            gzip: Some(true),
+            verbose: Some(false),
+            witnesses: Some("witnesses.txt".to_string()),
+            paths,
+            ..Settings::default()
+        };
+
+        assert!(Subject::resolve(settings(None)).paths);
+        assert!(!Subject::resolve(settings(Some(false))).paths);
+    }
+
+    #[test]
+    fn it_prefers_the_codec_to_gzip() {
+        let settings = Settings {
+            n: Some(4),
+            memory: Some(1.),
+            gzip: Some(true),
+            codec: Some(Codec::Packed),
+            verbose: Some(false),
+            ..Settings::default()
+        };
+
+        assert_eq!(Subject::resolve(settings).codec, Codec::Packed);
+    }
+}
+
+mod resumed {
+    use super::*;
+
+    #[test]
+    fn it_takes_the_reporting_and_stop_settings_from_the_flags() {
+        let saved = Subject {
+            stop: Stop { max_expansions: Some(1000), ..Stop::default() },
+            checkpoint_every: Some(Duration::from_secs(60)),
+            ..valid()
+        };
+
+        let settings = Settings { verbose: Some(true), offload: Some(Offload::Waste), max_waste: Some(3), ..Settings::default() };
+        let subject = saved.resumed(settings).unwrap();
+
+        assert_eq!(subject.n, 4);
+        assert!(subject.verbose);
+        assert_eq!(subject.offload, Offload::Waste);
+        assert_eq!(subject.checkpoint_every, Some(Duration::from_secs(60)));
+        assert_eq!(subject.stop, Stop { max_waste: Some(3), ..Stop::default() });
+    }
+
+    #[test]
+    fn it_refuses_settings_that_change_what_the_search_finds() {
+        let settings = Settings { n: Some(5), codec: Some(Codec::Packed), prune: Some(Prune::Lazy), paths: Some(false), ..Settings::default() };
+        let errors = valid().resumed(settings).unwrap_err();
+
+        assert_eq!(errors, vec![
+            "n can't be changed when resuming",
+            "codec can't be changed when resuming",
+            "prune can't be changed when resuming",
+            "paths can't be changed when resuming",
+        ]);
+    }
+}
+
+mod validate {
+    use super::*;
+
+    #[test]
+    fn it_accepts_a_valid_config() {
+        assert_eq!(valid().validate(), Ok(()));
+    }
+
+    #[test]
+    fn it_reports_every_problem_at_once() {
+        let stop = Stop { max_waste: None, time_limit: Some(Duration::from_secs(0)), max_expansions: Some(0) };
+        let subject = Subject { n: 1, memory: -1., scratch: "".to_string(), split_size: 0, stop, ..valid() };
+        let errors = subject.validate().unwrap_err();
+
+        assert_eq!(errors, vec![
+            "n must be between 2 and 20 (got 1)",
+            "memory must be a positive number of gigabytes (got -1)",
+            "scratch must be the path to a directory",
+            "split_size must be at least 1",
+            "time_limit must be at least one second",
+            "max_expansions must be at least 1",
+        ]);
+    }
+
+    #[test]
+    fn it_checks_there_is_enough_memory_for_one_candidate() {
+        let subject = Subject { n: 12, memory: 0.01, ..valid() };
+        let errors = subject.validate().unwrap_err();
+
+        assert_eq!(errors, vec![
+            "memory must fit at least one candidate of 59875296 bytes (got 0.01GiB)",
+        ]);
+    }
+
+    #[test]
+    fn it_fits_more_candidates_in_memory_without_paths() {
+        let subject = Subject { n: 11, memory: 0.01, paths: true, ..valid() };
+        assert!(subject.validate().is_err());
+
+        let subject = Subject { n: 11, memory: 0.01, ..valid() };
+        assert_eq!(subject.validate(), Ok(()));
+
+        let subject = Subject { witnesses: Some("witnesses.txt".to_string()), paths: false, ..valid() };
+        assert_eq!(subject.validate().unwrap_err(), vec!["witnesses needs paths to be kept"]);
+    }
+
+    #[test]
+    fn it_checks_the_watermarks_are_shares_of_memory_in_order() {
+        let subject = Subject { high_watermark: 1.5, ..valid() };
+        assert_eq!(subject.validate().unwrap_err(), vec!["high_watermark must be more than 0 and at most 1 (got 1.5)"]);
+
+        let subject = Subject { high_watermark: 0.5, low_watermark: 0.75, ..valid() };
+        assert_eq!(subject.validate().unwrap_err(), vec!["low_watermark must be between 0 and high_watermark (got 0.75)"]);
+    }
+
+    #[test]
+    fn it_checks_the_scratch_path_is_not_a_file() {
+        let subject = Subject { scratch: "Cargo.toml".to_string(), ..valid() };
+        let errors = subject.validate().unwrap_err();
+
+        assert_eq!(errors, vec!["scratch must be a directory ('Cargo.toml' is a file)"]);
+    }
+
+    #[test]
+    fn it_checks_the_events_file_is_in_an_existing_directory() {
+        let subject = Subject { events: Some("-".to_string()), ..valid() };
+        assert_eq!(subject.validate(), Ok(()));
+
+        let subject = Subject { events: Some("events.jsonl".to_string()), ..valid() };
+        assert_eq!(subject.validate(), Ok(()));
+
+        let subject = Subject { events: Some("/no/such/dir/events.jsonl".to_string()), ..valid() };
+        let errors = subject.validate().unwrap_err();
+
+        assert_eq!(errors, vec!["events must be '-' or a file in an existing directory (got '/no/such/dir/events.jsonl')"]);
+    }
+
+    #[test]
+    fn it_checks_the_witnesses_file_is_in_an_existing_directory() {
+        let subject = Subject { witnesses: Some("witnesses.txt".to_string()), paths: true, ..valid() };
+        assert_eq!(subject.validate(), Ok(()));
+
+        let subject = Subject { witnesses: Some("/no/such/dir/witnesses.txt".to_string()), ..valid() };
+        let errors = subject.validate().unwrap_err();
+
+        assert_eq!(errors, vec!["witnesses must be a file in an existing directory (got '/no/such/dir/witnesses.txt')"]);
+    }
+
+    #[test]
+    fn it_checks_the_proof_log_is_in_an_existing_directory() {
+        let subject = Subject { proof: Some("n5.proof".to_string()), ..valid() };
+        assert_eq!(subject.validate(), Ok(()));
+
+        let subject = Subject { proof: Some("/no/such/dir/n5.proof".to_string()), ..valid() };
+        let errors = subject.validate().unwrap_err();
+
+        assert_eq!(errors, vec!["proof must be a file in an existing directory (got '/no/such/dir/n5.proof')"]);
+    }
+
+    #[test]
+    fn it_checks_the_bounds_file_can_be_read() {
+        let path = "/tmp/superpermutation-config.bounds";
+        ::std::fs::write(path, "0 = 4 proven\n1 = 9 proven\n").unwrap();
+
+        let subject = Subject { bounds: Some(path.to_string()), ..valid() };
+        let errors = subject.validate().unwrap_err();
+
+        assert_eq!(errors, vec![format!("{}: line 2: 9 permutations is more than the upper bound of 8", path)]);
+
+        let subject = Subject { bounds: Some("/no/such/file".to_string()), ..valid() };
+        assert!(subject.validate().is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use super::known::Comparison;
+use super::observer::{Observer, Snapshot};
+use super::offload::Watermarks;
+use super::status::Status;
+use super::ui::UI;
+use super::{out, outln};
+use super::witness::Witnesses;
+
+/// Prints the progress of the search to stdout.
+///
+/// With verbose set, this also prints each bucket that is unpruned and the
+/// sizes of the buckets that are offloaded to disk, one row per waste level.
+pub struct Console {
+    verbose: bool,
+    row: Option<Row>,
+}
+
+struct Row {
+    waste: usize,
+    min_permutations: usize,
+    max_permutations: usize,
+    sizes: Vec<usize>,
+}
+
+impl Console {
+    pub fn new(verbose: bool) -> Self {
+        Self { verbose, row: None }
+    }
+
+    fn finish_row(&mut self) {
+        if let Some(row) = self.row.take() {
+            let sizes: Vec<_> = row.sizes.iter().map(|s| s.to_string()).collect();
+            outln!("  {:02}, {:03}..{:03} | {}", row.waste, row.min_permutations, row.max_permutations, sizes.join(" "));
+        }
+    }
+}
+
+impl Observer for Console {
+    fn phase_started(&mut self, waste: usize, permutations: usize, _: &Snapshot) {
+        UI::clear_line();
+        outln!("{} wasted symbols: at most {} permutations", waste - 1, permutations);
+    }
+
+    fn witness_found(&mut self, waste: usize, permutations: usize, witness: &[u8], _: &Snapshot) {
+        UI::clear_line();
+        outln!("  e.g. {} ({} wasted symbols, {} permutations)", Witnesses::format(witness), waste, permutations);
+    }
+
+    fn bound_compared(&mut self, waste: usize, permutations: usize, comparison: Comparison, _: &Snapshot) {
+        UI::clear_line();
+
+        match comparison.is_contradiction() {
+            false => outln!("  {} permutations for {} wasted symbols {}", permutations, waste, comparison.describe()),
+            true => eprintln!("!!! {} permutations for {} wasted symbols {} !!!", permutations, waste, comparison.describe()),
+        }
+    }
+
+    fn bucket_unpruned(&mut self, waste: usize, permutations: usize, from_disk: bool, snapshot: &Snapshot) {
+        if !self.verbose {
+            return;
+        }
+
+        let queue = snapshot.enabled + snapshot.disabled;
+
+        match from_disk {
+            true => outln!("  unpruning ({}, {}) from disk ..... queue: {}", waste, permutations, queue),
+            false => outln!("  unpruning ({}, {}) from memory ... queue: {}", waste, permutations, queue),
+        }
+    }
+
+    fn offload_planned(&mut self, watermarks: &Watermarks, policy: &str, _: &Snapshot) {
+        outln!("\nEach candidate string consumes approximately {} bytes of memory.", watermarks.bytes_per_candidate);
+        outln!("The queue limit has been set to {} candidates.", watermarks.queue_limit);
+        outln!("Offloading starts above {} candidates and frees memory down to {} with the '{}' policy.\n",
+                 watermarks.high, watermarks.low, policy);
+    }
+
+    fn offload_started(&mut self, _: &Snapshot) {
+        UI::clear_line();
+        out!("running low on memory, offloading to disk... ");
+        UI::flush();
+        if self.verbose { outln!(); }
+    }
+
+    fn offloaded(&mut self, waste: usize, permutations: usize, candidates: usize, _: &Snapshot) {
+        if !self.verbose {
+            return;
+        }
+
+        if self.row.as_ref().map(|r| r.waste) != Some(waste) {
+            self.finish_row();
+            self.row = Some(Row { waste, min_permutations: permutations, max_permutations: permutations, sizes: vec![] });
+        }
+
+        let row = self.row.as_mut().unwrap();
+        row.max_permutations = permutations;
+        row.sizes.push(candidates);
+    }
+
+    fn offload_finished(&mut self, _: &Snapshot) {
+        self.finish_row();
+        outln!("done");
+    }
+
+    fn checkpoint_saved(&mut self, bytes: u64, _: &Snapshot) {
+        if self.verbose {
+            UI::clear_line();
+            outln!("  saved a checkpoint of {}", Status::format_bytes(bytes));
+        }
+    }
+
+    fn checkpoint_failed(&mut self, error: &str, _: &Snapshot) {
+        UI::clear_line();
+        eprintln!("Failed to save a checkpoint: {}", error);
+    }
+
+    fn orphans_removed(&mut self, filenames: &[String], _: &Snapshot) {
+        outln!("Removed {} orphaned chunks that the checkpoint doesn't reach.", filenames.len());
+
+        if self.verbose {
+            filenames.iter().for_each(|f| outln!("  {}", f));
+        }
+    }
+
+    fn search_completed(&mut self, waste: usize, permutations: usize, _: usize, _: &Snapshot) {
+        UI::clear_line();
+        outln!("{} wasted symbols: at most {} permutations", waste, permutations);
+        outln!();
+    }
+}
@@ -1,71 +1,395 @@
 use super::candidate::Candidate;
+use super::chunk;
+use super::codec::Codec;
 
-use std::collections::VecDeque;
-use std::fs::{File, create_dir_all, remove_dir_all, remove_file};
-use std::io::{BufWriter, BufReader};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{File, OpenOptions, create_dir_all, metadata, read_dir, remove_dir_all, remove_file, rename, write};
+use std::fs::TryLockError;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
-use bincode::{serialize_into, deserialize_from};
+pub const SPLIT_SIZE: usize = 222_222;
+pub const MARKER: &str = ".leaps-and-bounds-scratch";
+pub const LOCK: &str = ".lock";
+/// Chunks are written under this suffix and renamed once they are complete.
+pub const TEMPORARY: &str = ".tmp";
 
-const SPLIT_SIZE: usize = 222_222;
+pub type Index = Vec<Vec<Option<(usize, usize)>>>;
+
+/// What scanning a scratch directory found.
+#[derive(Debug, Default, PartialEq)]
+pub struct Scan {
+    pub buckets: Vec<Bucket>,
+    /// Chunk files that the index can't reach, because they follow a gap,
+    /// were written with the other codec or were never finished.
+    pub orphaned: Vec<String>,
+    /// The first chunk missing from each bucket with a gap in it.
+    pub missing: Vec<String>,
+    /// Chunks that couldn't be read, with the reason.
+    pub unreadable: Vec<(String, String)>,
+}
+
+/// The chunks of a bucket that the index reaches, numbered from `first`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub waste: usize,
+    pub permutations: usize,
+    pub first: usize,
+    pub chunks: usize,
+    pub bytes: u64,
+    /// How many candidates the chunks hold, if they were read.
+    pub candidates: Option<usize>,
+}
 
 pub struct Disk {
     path: String,
-    gzip: bool,
-    index: Arc<Mutex<Vec<Vec<Option<(usize, usize)>>>>>,
+    n: usize,
+    codec: Codec,
+    split_size: usize,
+    index: Arc<Mutex<Index>>,
+    bytes: AtomicU64,
+    retired: Option<Mutex<Vec<String>>>,
+    _lock: File,
 }
 
 impl Disk {
-    pub fn new(path: String, gzip: bool) -> Self {
-        let _ = remove_dir_all(&path);
-        create_dir_all(&path).expect(&format!("Failed to create {}", path));
+    pub fn new(path: String, n: usize, codec: Codec, split_size: usize) -> Result<Self, String> {
+        Self::check(&path)?;
+
+        create_dir_all(&path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+        let lock = Self::lock(&path)?;
+
+        Self::wipe(&path)?;
+        write(format!("{}/{}", path, MARKER), "Scratch files for leaps-and-bounds. This directory is wiped when a new search starts in it, but resume carries on from it.\n")
+            .map_err(|e| format!("Failed to create {}/{}: {}", path, MARKER, e))?;
 
         let index = Arc::new(Mutex::new(vec![]));
-        Self { path, gzip, index }
+        Ok(Self { path, n, codec, split_size, index, bytes: AtomicU64::new(0), retired: None, _lock: lock })
     }
 
-    pub fn read(&self, wasted_symbols: usize, permutations: usize) -> Option<VecDeque<Candidate>> {
-        let filename = self.filename_for_reading(wasted_symbols, permutations)?;
-        let file = File::open(&filename).expect(&format!("Failed to open {}", filename));
+    /// Opens the scratch directory of an earlier run without wiping it and
+    /// rebuilds the index from the chunks in it.
+    pub fn open(path: String, n: usize, codec: Codec, split_size: usize) -> Result<Self, String> {
+        if !Path::new(&path).join(MARKER).exists() {
+            return Err(format!("{} is not a scratch directory (it has no {} file in it)", path, MARKER));
+        }
 
-        let mut reader = BufReader::new(file);
+        let lock = Self::lock(&path)?;
+        let scan = Self::scan(&path, n, codec, false)?;
 
-        let candidates = if self.gzip {
-            let mut decoder = ZlibDecoder::new(reader);
-            deserialize_from(&mut decoder).unwrap()
-        } else {
-            deserialize_from(&mut reader).unwrap()
+        let index = Arc::new(Mutex::new(scan.index()));
+        let bytes = AtomicU64::new(scan.bytes());
+
+        Ok(Self { path, n, codec, split_size, index, bytes, retired: None, _lock: lock })
+    }
+
+    /// Finds the chunks in a scratch directory without locking it. The index
+    /// reaches the chunks of each bucket from the lowest number up to the
+    /// first gap. With `read` set, every chunk it reaches is read to count the
+    /// candidates and find the ones that are unreadable.
+    pub fn scan(path: &str, n: usize, codec: Codec, read: bool) -> Result<Scan, String> {
+        let entries = read_dir(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+        let mut scan = Scan::default();
+        let mut found: BTreeMap<(usize, usize), Vec<(usize, u64)>> = BTreeMap::new();
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            let temporary = name.ends_with(TEMPORARY);
+
+            match Self::parse_filename(name.trim_end_matches(TEMPORARY)) {
+                None => continue,
+                Some((_, _, extension, _)) if extension != codec.extension() || temporary => scan.orphaned.push(format!("{}/{}", path, name)),
+                Some((waste, permutations, _, number)) => {
+                    let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    found.entry((waste, permutations)).or_default().push((number, bytes));
+                },
+            }
+        }
+
+        for ((waste, permutations), mut chunks) in found {
+            chunks.sort();
+
+            let basename = Self::basename_in(path, codec, waste, permutations);
+            let first = chunks[0].0;
+            let reached = chunks.iter().enumerate().take_while(|(i, c)| c.0 == first + i).count();
+
+            if reached < chunks.len() {
+                scan.missing.push(format!("{}.{}", basename, first + reached));
+            }
+
+            for (number, _) in &chunks[reached..] {
+                scan.orphaned.push(format!("{}.{}", basename, number));
+            }
+
+            let bytes = chunks[..reached].iter().map(|c| c.1).sum();
+            let mut candidates = None;
+
+            if read {
+                let mut count = 0;
+
+                for number in first..first + reached {
+                    let filename = format!("{}.{}", basename, number);
+
+                    match chunk::load(&filename, n, codec) {
+                        Ok(bucket) => count += bucket.len(),
+                        Err(error) => scan.unreadable.push((filename, error)),
+                    }
+                }
+
+                candidates = Some(count);
+            }
+
+            scan.buckets.push(Bucket { waste, permutations, first, chunks: reached, bytes, candidates });
+        }
+
+        scan.orphaned.sort();
+        Ok(scan)
+    }
+
+    // Splits a name like candidates-with-W-wasted-symbols-and-P-permutations.dat.gz.N
+    // into the bucket, the extension of the codec and the chunk number.
+    fn parse_filename(name: &str) -> Option<(usize, usize, &str, usize)> {
+        let rest = name.strip_prefix("candidates-with-")?;
+        let (waste, rest) = rest.split_once("-wasted-symbols-and-")?;
+        let (permutations, rest) = rest.split_once("-permutations.dat")?;
+        let (extension, number) = rest.rsplit_once('.')?;
+
+        Some((waste.parse().ok()?, permutations.parse().ok()?, extension, number.parse().ok()?))
+    }
+
+    /// Keeps the files that have been read until `release` is called, so that
+    /// the last checkpoint can still find every chunk in its index.
+    pub fn retain_chunks(&mut self) {
+        self.retired = Some(Mutex::new(vec![]));
+    }
+
+    /// Removes the files that were read since the last call.
+    pub fn release(&self) -> Result<(), String> {
+        let retired = match &self.retired {
+            None => return Ok(()),
+            Some(retired) => retired,
         };
 
-        remove_file(&filename).expect(&format!("Failed to remove {}", filename));
+        for filename in retired.lock().unwrap().drain(..) {
+            remove_file(&filename).map_err(|e| format!("Failed to remove {}: {}", filename, e))?;
+        }
+
+        Ok(())
+    }
 
-        Some(candidates)
+    pub fn index(&self) -> Index {
+        self.index.lock().unwrap().clone()
     }
 
-    pub fn write(&self, bucket: VecDeque<Candidate>, wasted_symbols: usize, permutations: usize) {
+    /// Rewrites the chunks that an older version wrote without a header and
+    /// returns their names. Chunks that already have one are left alone.
+    pub fn migrate(&self) -> Result<Vec<String>, String> {
+        let index = self.index();
+        let mut migrated = vec![];
+
+        for (waste, nested) in index.iter().enumerate() {
+            for (permutations, range) in nested.iter().enumerate() {
+                let (min, max) = match range {
+                    Some(range) => *range,
+                    None => continue,
+                };
+
+                for number in min..=max {
+                    let filename = format!("{}.{}", self.basename(waste, permutations), number);
+                    let before = metadata(&filename).map(|m| m.len()).unwrap_or(0);
+
+                    let rewritten = chunk::migrate(&filename, self.n, self.codec).map_err(|error| {
+                        format!("The chunk {} of bucket ({}, {}) {}", filename, waste, permutations, error)
+                    })?;
+
+                    if rewritten {
+                        let after = metadata(&filename).map(|m| m.len()).unwrap_or(0);
+
+                        self.bytes.fetch_add(after, Ordering::Relaxed);
+                        self.bytes.fetch_sub(before, Ordering::Relaxed);
+
+                        migrated.push(filename);
+                    }
+                }
+            }
+        }
+
+        Ok(migrated)
+    }
+
+    /// Replaces the index with one saved in a checkpoint. Every chunk it
+    /// reaches must still be on disk. Chunks it doesn't reach were written
+    /// after the checkpoint or read before it, so they are removed and their
+    /// names are returned.
+    pub fn restore(&self, index: Index, bytes: u64) -> Result<Vec<String>, String> {
+        let reaches = |waste: usize, permutations: usize, number: usize| {
+            match index.get(waste).and_then(|nested| nested.get(permutations)) {
+                Some(&Some((min, max))) => min <= number && number <= max,
+                _ => false,
+            }
+        };
+
+        let mut missing = vec![];
+
+        for (waste, nested) in index.iter().enumerate() {
+            for (permutations, range) in nested.iter().enumerate() {
+                let (min, max) = match range {
+                    Some(range) => *range,
+                    None => continue,
+                };
+
+                for number in min..=max {
+                    let filename = format!("{}.{}", self.basename(waste, permutations), number);
+
+                    if !Path::new(&filename).exists() {
+                        missing.push(filename);
+                    }
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(format!("The checkpoint needs chunks that are missing: {}", missing.join(", ")));
+        }
+
+        let entries = read_dir(&self.path).map_err(|e| format!("Failed to read {}: {}", self.path, e))?;
+        let mut orphaned = vec![];
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            let temporary = name.ends_with(TEMPORARY);
+
+            let orphan = match Self::parse_filename(name.trim_end_matches(TEMPORARY)) {
+                None => continue,
+                Some((waste, permutations, extension, number)) => {
+                    temporary || extension != self.codec.extension() || !reaches(waste, permutations, number)
+                },
+            };
+
+            if orphan {
+                let filename = format!("{}/{}", self.path, name);
+                remove_file(&filename).map_err(|e| format!("Failed to remove {}: {}", filename, e))?;
+                orphaned.push(filename);
+            }
+        }
+
+        *self.index.lock().unwrap() = index;
+        self.bytes.store(bytes, Ordering::Relaxed);
+
+        orphaned.sort();
+        Ok(orphaned)
+    }
+
+    pub fn check(path: &str) -> Result<(), String> {
+        let entries = match read_dir(path) {
+            Err(_) => return Ok(()),
+            Ok(entries) => entries,
+        };
+
+        if entries.count() == 0 || Path::new(path).join(MARKER).exists() {
+            return Ok(());
+        }
+
+        Err(format!("Refusing to wipe {} because it is not empty and has no {} file in it", path, MARKER))
+    }
+
+    fn lock(path: &str) -> Result<File, String> {
+        let filename = format!("{}/{}", path, LOCK);
+
+        let file = OpenOptions::new().create(true).truncate(false).write(true).open(&filename)
+            .map_err(|e| format!("Failed to create {}: {}", filename, e))?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(file),
+            Err(TryLockError::WouldBlock) => Err(format!("{} is already in use by another run", path)),
+            Err(TryLockError::Error(e)) => Err(format!("Failed to lock {}: {}", filename, e)),
+        }
+    }
+
+    fn wipe(path: &str) -> Result<(), String> {
+        let entries = read_dir(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            if entry.file_name() == LOCK {
+                continue;
+            }
+
+            let entry_path = entry.path();
+
+            let result = match entry_path.is_dir() {
+                true => remove_dir_all(&entry_path),
+                false => remove_file(&entry_path),
+            };
+
+            result.map_err(|e| format!("Failed to remove {}: {}", entry_path.display(), e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the next chunk of a bucket, or returns none if it has no chunks
+    /// left. A chunk that can't be read is an error that names the bucket.
+    pub fn read(&self, wasted_symbols: usize, permutations: usize) -> Result<Option<VecDeque<Candidate>>, String> {
+        let filename = match self.filename_for_reading(wasted_symbols, permutations) {
+            None => return Ok(None),
+            Some(filename) => filename,
+        };
+
+        let candidates = chunk::load(&filename, self.n, self.codec).map_err(|error| {
+            format!("The chunk {} of bucket ({}, {}) {}", filename, wasted_symbols, permutations, error)
+        })?;
+
+        let bytes = metadata(&filename).map(|m| m.len()).unwrap_or(0);
+
+        match &self.retired {
+            Some(retired) => retired.lock().unwrap().push(filename),
+            None => remove_file(&filename).map_err(|e| format!("Failed to remove {}: {}", filename, e))?,
+        }
+
+        self.bytes.fetch_sub(bytes, Ordering::Relaxed);
+
+        Ok(Some(candidates))
+    }
+
+    /// Writes a chunk to a temporary file, syncs it and then renames it, so a
+    /// chunk is either complete or not there at all. The directory is synced
+    /// after the rename so that the new name survives a crash too.
+    pub fn write(&self, bucket: VecDeque<Candidate>, wasted_symbols: usize, permutations: usize) -> Result<(), String> {
         let filename = self.filename_for_writing(wasted_symbols, permutations);
-        let file = File::create(&filename).expect(&format!("Failed to create {}", filename));
+        let temporary = format!("{}{}", filename, TEMPORARY);
 
-        let mut writer = BufWriter::new(file);
+        let result = chunk::save(&temporary, &bucket, self.n, self.codec)
+            .and_then(|_| rename(&temporary, &filename).map_err(|e| e.to_string()))
+            .and_then(|_| File::open(&self.path).and_then(|dir| dir.sync_all()).map_err(|e| e.to_string()));
 
-        if self.gzip {
-            let mut encoder = ZlibEncoder::new(writer, Compression::default());
-            serialize_into(&mut encoder, &bucket).unwrap();
-        } else {
-            serialize_into(&mut writer, &bucket).unwrap();
+        if let Err(error) = result {
+            let _ = remove_file(&temporary);
+            return Err(format!("Failed to write {}: {}", filename, error));
         }
+
+        let bytes = metadata(&filename).map(|m| m.len()).unwrap_or(0);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
     }
 
-    pub fn write_chunks(&self, mut bucket: VecDeque<Candidate>, wasted_symbols: usize, permutations: usize) {
-        while bucket.len() > SPLIT_SIZE * 2 {
-            let remainder = bucket.split_off(SPLIT_SIZE);
+    pub fn write_chunks(&self, mut bucket: VecDeque<Candidate>, wasted_symbols: usize, permutations: usize) -> Result<(), String> {
+        while bucket.len() > self.split_size * 2 {
+            let remainder = bucket.split_off(self.split_size);
 
-            self.write(bucket, wasted_symbols, permutations);
+            self.write(bucket, wasted_symbols, permutations)?;
             bucket = remainder
         }
 
-        self.write(bucket, wasted_symbols, permutations);
+        self.write(bucket, wasted_symbols, permutations)
     }
 
     pub fn filename_for_reading(&self, wasted_symbols: usize, permutations: usize) -> Option<String> {
@@ -95,6 +419,16 @@ impl Disk {
         }
     }
 
+    /// How many chunks of a bucket are waiting to be read.
+    pub fn chunks(&self, wasted_symbols: usize, permutations: usize) -> usize {
+        let index = self.index.lock().unwrap();
+
+        match index.get(wasted_symbols).and_then(|nested| nested.get(permutations)) {
+            Some(&Some((min, max))) => (max + 1).saturating_sub(min),
+            _ => 0,
+        }
+    }
+
     pub fn index_to_write_to(&self, wasted_symbols: usize, permutations: usize) -> usize {
         let mut index = self.index.lock().unwrap();
         if index.len() <= wasted_symbols {
@@ -116,20 +450,48 @@ impl Disk {
     }
 
     pub fn basename(&self, wasted_symbols: usize, permutations: usize) -> String {
-        let gzip_component = match self.gzip {
-            true => ".gz",
-            false => "",
-        };
+        Self::basename_in(&self.path, self.codec, wasted_symbols, permutations)
+    }
 
+    pub fn basename_in(path: &str, codec: Codec, wasted_symbols: usize, permutations: usize) -> String {
         format!(
             "{}/candidates-with-{}-wasted-symbols-and-{}-permutations.dat{}",
-            self.path,
+            path,
             wasted_symbols,
             permutations,
-            gzip_component,
+            codec.extension(),
         )
     }
 }
 
+impl Scan {
+    pub fn index(&self) -> Index {
+        let mut index: Index = vec![];
+
+        for bucket in self.buckets.iter().filter(|b| b.chunks > 0) {
+            if index.len() <= bucket.waste {
+                index.resize(bucket.waste + 1, vec![]);
+            }
+
+            let nested = &mut index[bucket.waste];
+            if nested.len() <= bucket.permutations {
+                nested.resize(bucket.permutations + 1, None);
+            }
+
+            nested[bucket.permutations] = Some((bucket.first, bucket.first + bucket.chunks - 1));
+        }
+
+        index
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.buckets.iter().map(|b| b.bytes).sum()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.orphaned.is_empty() && self.missing.is_empty() && self.unreadable.is_empty()
+    }
+}
+
 #[cfg(test)]
 mod test;
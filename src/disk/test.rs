@@ -1,15 +1,21 @@
 use super::*;
+use super::super::codec::DEFAULT_LEVEL;
 
-use std::fs::metadata;
+use std::fs::{metadata, write};
 use std::path::Path;
 
+use bincode::serialize_into;
+
 type Subject = Disk;
 
-const PATH: &'static str = "/tmp/superpermutation-test";
+const PATH: &str = "/tmp/superpermutation-test";
+const ZLIB: Codec = Codec::Zlib(DEFAULT_LEVEL);
 
-fn subject(test_id: &'static str, gzip: bool) -> Subject {
+fn subject(test_id: &'static str, codec: Codec) -> Subject {
     let path = format!("{}/{}", PATH, test_id);
-    Subject::new(path, gzip)
+    let _ = remove_dir_all(&path);
+
+    Subject::new(path, 5, codec, SPLIT_SIZE).unwrap()
 }
 
 fn bucket() -> VecDeque<Candidate> {
@@ -21,14 +27,71 @@ mod new {
 
     #[test]
     fn it_builds_the_struct_with_the_path() {
-        let subject = subject("test-1", false);
+        let subject = subject("test-1", Codec::Raw);
         assert_eq!(subject.path, "/tmp/superpermutation-test/test-1");
     }
 
     #[test]
     fn it_creates_a_directory_at_the_path() {
-        subject("test-2", false);
-        assert_eq!(Path::new(PATH).exists(), true);
+        subject("test-2", Codec::Raw);
+        assert!(Path::new(PATH).exists());
+    }
+
+    #[test]
+    fn it_marks_the_directory_as_a_scratch_directory() {
+        let subject = subject("test-15", Codec::Raw);
+        let marker = format!("{}/{}", subject.path, MARKER);
+
+        assert!(Path::new(&marker).exists());
+    }
+
+    #[test]
+    fn it_wipes_files_left_over_from_a_previous_run() {
+        let path = format!("{}/test-16", PATH);
+
+        let first = Subject::new(path.clone(), 5, Codec::Raw, SPLIT_SIZE).unwrap();
+        first.write(bucket(), 3, 4).unwrap();
+        drop(first);
+
+        let second = Subject::new(path, 5, Codec::Raw, SPLIT_SIZE).unwrap();
+        assert!(!Path::new(&format!("{}.0", second.basename(3, 4))).exists());
+    }
+
+    #[test]
+    fn it_refuses_to_wipe_a_directory_without_the_marker() {
+        let path = format!("{}/test-17", PATH);
+        let _ = remove_dir_all(&path);
+        create_dir_all(&path).unwrap();
+        write(format!("{}/precious.txt", path), "do not delete").unwrap();
+
+        let result = Subject::new(path.clone(), 5, Codec::Raw, SPLIT_SIZE);
+
+        assert!(result.is_err());
+        assert!(Path::new(&format!("{}/precious.txt", path)).exists());
+    }
+
+    #[test]
+    fn it_refuses_to_share_a_directory_with_another_run() {
+        let path = format!("{}/test-18", PATH);
+
+        let _first = Subject::new(path.clone(), 5, Codec::Raw, SPLIT_SIZE).unwrap();
+        let second = Subject::new(path.clone(), 5, Codec::Raw, SPLIT_SIZE);
+
+        assert_eq!(second.err(), Some(format!("{} is already in use by another run", path)));
+    }
+}
+
+mod check {
+    use super::*;
+
+    #[test]
+    fn it_accepts_missing_and_empty_directories() {
+        let path = format!("{}/test-19", PATH);
+        let _ = remove_dir_all(&path);
+        assert_eq!(Subject::check(&path), Ok(()));
+
+        create_dir_all(&path).unwrap();
+        assert_eq!(Subject::check(&path), Ok(()));
     }
 }
 
@@ -37,7 +100,7 @@ mod basename {
 
     #[test]
     fn it_returns_a_name_based_on_the_number_of_wasted_symbols_and_permutations() {
-        let subject = subject("test-3", false);
+        let subject = subject("test-3", Codec::Raw);
         let actual = subject.basename(3, 4);
 
         let name = "test-3/candidates-with-3-wasted-symbols-and-4-permutations.dat";
@@ -52,7 +115,7 @@ mod filename_for_reading {
 
     #[test]
     fn it_returns_none_if_no_file_exists() {
-        let subject = subject("test-4", false);
+        let subject = subject("test-4", Codec::Raw);
         let filename = subject.filename_for_reading(3, 4);
 
         assert_eq!(filename, None);
@@ -60,11 +123,11 @@ mod filename_for_reading {
 
     #[test]
     fn it_returns_the_name_of_the_first_available_file() {
-        let subject = subject("test-5", false);
+        let subject = subject("test-5", Codec::Raw);
 
-        subject.write(bucket(), 3, 4); // 0
-        subject.write(bucket(), 3, 4); // 1
-        subject.write(bucket(), 3, 4); // 2
+        subject.write(bucket(), 3, 4).unwrap(); // 0
+        subject.write(bucket(), 3, 4).unwrap(); // 1
+        subject.write(bucket(), 3, 4).unwrap(); // 2
 
         let filename = subject.filename_for_reading(3, 4).unwrap();
         assert_eq!(&filename[70..], "-4-permutations.dat.0");
@@ -82,7 +145,7 @@ mod filename_for_writing {
 
     #[test]
     fn it_adds_a_suffix_to_the_basename() {
-        let subject = subject("test-6", false);
+        let subject = subject("test-6", Codec::Raw);
 
         let filename = subject.filename_for_writing(3, 4);
         assert_eq!(&filename[70..], "-4-permutations.dat.0");
@@ -90,7 +153,7 @@ mod filename_for_writing {
 
     #[test]
     fn it_increments_the_index_each_time() {
-        let subject = subject("test-7", false);
+        let subject = subject("test-7", Codec::Raw);
         let filename = subject.filename_for_writing(3, 4);
         assert_eq!(&filename[70..], "-4-permutations.dat.0");
 
@@ -107,11 +170,20 @@ mod write {
 
     #[test]
     fn it_writes_the_bucket_to_a_file() {
-        let subject = subject("test-8", false);
-        subject.write(bucket(), 3, 4);
+        let subject = subject("test-8", Codec::Raw);
+        subject.write(bucket(), 3, 4).unwrap();
 
         let filename = subject.filename_for_reading(3, 4).unwrap();
-        assert_eq!(Path::new(&filename).exists(), true);
+        assert!(Path::new(&filename).exists());
+    }
+
+    #[test]
+    fn it_returns_an_error_if_the_file_cannot_be_written() {
+        let subject = subject("test-37", Codec::Raw);
+        remove_dir_all(&subject.path).unwrap();
+
+        let error = subject.write(bucket(), 3, 4).unwrap_err();
+        assert!(error.starts_with(&format!("Failed to write {}.0: ", subject.basename(3, 4))));
     }
 }
 
@@ -120,11 +192,11 @@ mod read {
 
     #[test]
     fn it_reads_the_bucket_from_a_file() {
-        let subject = subject("test-9", false);
-        subject.write(bucket(), 3, 4);
+        let subject = subject("test-9", Codec::Raw);
+        subject.write(bucket(), 3, 4).unwrap();
 
         let bucket_from_file = subject.read(3, 4);
-        assert_eq!(bucket_from_file, Some(bucket()));
+        assert_eq!(bucket_from_file, Ok(Some(bucket())));
     }
 }
 
@@ -133,11 +205,11 @@ mod gzip_compression {
 
     #[test]
     fn it_writes_a_smaller_file_to_disk() {
-        let with_gzip = subject("test-11", true);
-        let without_gzip = subject("test-12", false);
+        let with_gzip = subject("test-11", ZLIB);
+        let without_gzip = subject("test-12", Codec::Raw);
 
-        with_gzip.write(bucket(), 3, 4);
-        without_gzip.write(bucket(), 5, 6);
+        with_gzip.write(bucket(), 3, 4).unwrap();
+        without_gzip.write(bucket(), 5, 6).unwrap();
 
         let file1 = with_gzip.filename_for_reading(3, 4).unwrap();
         let file2 = without_gzip.filename_for_reading(5, 6).unwrap();
@@ -145,10 +217,309 @@ mod gzip_compression {
         let with_gzip_size = metadata(file1).unwrap().len();
         let without_gzip_size = metadata(file2).unwrap().len();
 
-        assert_eq!(with_gzip_size > 0, true);
-        assert_eq!(with_gzip_size < without_gzip_size, true);
+        assert!(with_gzip_size > 0);
+        assert!(with_gzip_size < without_gzip_size);
 
         let compression_rate = without_gzip_size / with_gzip_size;
-        assert_eq!(compression_rate > 200, true);
+        assert!(compression_rate > 200);
+    }
+}
+
+mod write_chunks {
+    use super::*;
+
+    #[test]
+    fn it_splits_large_buckets_into_files_of_the_split_size() {
+        let path = format!("{}/{}", PATH, "test-13");
+        let subject = Subject::new(path, 5, Codec::Raw, 300).unwrap();
+
+        subject.write_chunks(bucket(), 3, 4).unwrap();
+
+        assert_eq!(subject.read(3, 4).unwrap().unwrap().len(), 300);
+        assert_eq!(subject.read(3, 4).unwrap().unwrap().len(), 300);
+        assert_eq!(subject.read(3, 4).unwrap().unwrap().len(), 400);
+        assert_eq!(subject.read(3, 4), Ok(None));
+    }
+}
+
+mod bytes {
+    use super::*;
+
+    #[test]
+    fn it_tracks_the_size_of_the_files_held_on_disk() {
+        let subject = subject("test-14", Codec::Raw);
+        assert_eq!(subject.bytes(), 0);
+
+        subject.write(bucket(), 3, 4).unwrap();
+        let filename = format!("{}.0", subject.basename(3, 4));
+        assert_eq!(subject.bytes(), metadata(filename).unwrap().len());
+
+        subject.read(3, 4).unwrap();
+        assert_eq!(subject.bytes(), 0);
+    }
+}
+
+mod chunks {
+    use super::*;
+
+    #[test]
+    fn it_counts_the_chunks_that_have_not_been_read_yet() {
+        let path = format!("{}/{}", PATH, "test-20");
+        let subject = Subject::new(path, 5, Codec::Raw, 300).unwrap();
+
+        assert_eq!(subject.chunks(3, 4), 0);
+
+        subject.write_chunks(bucket(), 3, 4).unwrap();
+        assert_eq!(subject.chunks(3, 4), 3);
+
+        subject.read(3, 4).unwrap();
+        assert_eq!(subject.chunks(3, 4), 2);
+
+        subject.read(3, 4).unwrap();
+        subject.read(3, 4).unwrap();
+        assert_eq!(subject.chunks(3, 4), 0);
+    }
+}
+
+mod open {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_files_from_the_previous_run_and_rebuilds_the_index() {
+        let path = format!("{}/test-21", PATH);
+        let _ = remove_dir_all(&path);
+
+        let first = Subject::new(path.clone(), 5, Codec::Raw, 300).unwrap();
+        first.write_chunks(bucket(), 3, 4).unwrap();
+        first.write(bucket(), 5, 6).unwrap();
+        first.read(3, 4).unwrap();
+        first.read(5, 6).unwrap();
+        let bytes = first.bytes();
+        drop(first);
+
+        let second = Subject::open(path, 5, Codec::Raw, 300).unwrap();
+
+        assert_eq!(second.chunks(3, 4), 2);
+        assert_eq!(second.chunks(5, 6), 0);
+        assert_eq!(second.bytes(), bytes);
+        assert_eq!(second.read(3, 4).unwrap().unwrap().len(), 300);
+    }
+
+    #[test]
+    fn it_refuses_a_directory_without_the_marker() {
+        let path = format!("{}/test-22", PATH);
+        let _ = remove_dir_all(&path);
+        create_dir_all(&path).unwrap();
+
+        assert!(Subject::open(path, 5, Codec::Raw, SPLIT_SIZE).is_err());
+    }
+}
+
+mod release {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_files_that_were_read_until_they_are_released() {
+        let mut subject = subject("test-23", Codec::Raw);
+        subject.retain_chunks();
+
+        subject.write(bucket(), 3, 4).unwrap();
+        let filename = format!("{}.0", subject.basename(3, 4));
+
+        subject.read(3, 4).unwrap();
+        assert!(Path::new(&filename).exists());
+        assert_eq!(subject.bytes(), 0);
+
+        subject.release().unwrap();
+        assert!(!Path::new(&filename).exists());
+    }
+}
+
+mod scan {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_chunks_of_each_bucket() {
+        let subject = subject("test-24", ZLIB);
+        subject.write(bucket(), 3, 4).unwrap();
+        subject.write(bucket(), 3, 4).unwrap();
+        subject.write(bucket(), 2, 7).unwrap();
+        subject.read(3, 4).unwrap();
+
+        let scan = Subject::scan(&subject.path, 5, ZLIB, true).unwrap();
+
+        assert_eq!(scan.buckets.len(), 2);
+        assert_eq!(scan.buckets[0].waste, 2);
+        assert_eq!(scan.buckets[1], Bucket {
+            waste: 3,
+            permutations: 4,
+            first: 1,
+            chunks: 1,
+            bytes: scan.buckets[1].bytes,
+            candidates: Some(1000),
+        });
+        assert_eq!(scan.index(), subject.index());
+        assert_eq!(scan.bytes(), subject.bytes());
+        assert!(scan.is_clean());
+    }
+
+    #[test]
+    fn it_reports_chunks_after_a_gap_and_chunks_of_the_other_codec_as_orphaned() {
+        let subject = subject("test-25", Codec::Raw);
+        subject.write(bucket(), 3, 4).unwrap();
+        subject.write(bucket(), 3, 4).unwrap();
+        subject.write(bucket(), 3, 4).unwrap();
+
+        let basename = subject.basename(3, 4);
+        remove_file(format!("{}.1", basename)).unwrap();
+        write(format!("{}/candidates-with-1-wasted-symbols-and-2-permutations.dat.gz.0", subject.path), "").unwrap();
+        write(format!("{}/notes.txt", subject.path), "").unwrap();
+
+        let scan = Subject::scan(&subject.path, 5, Codec::Raw, false).unwrap();
+
+        assert_eq!(scan.buckets[0].chunks, 1);
+        assert_eq!(scan.missing, vec![format!("{}.1", basename)]);
+        assert_eq!(scan.orphaned, vec![
+            format!("{}/candidates-with-1-wasted-symbols-and-2-permutations.dat.gz.0", subject.path),
+            format!("{}.2", basename),
+        ]);
+    }
+
+    #[test]
+    fn it_reports_chunks_that_cannot_be_read() {
+        let subject = subject("test-26", Codec::Raw);
+        subject.write(bucket(), 3, 4).unwrap();
+
+        let filename = format!("{}.0", subject.basename(3, 4));
+        write(&filename, [1, 2, 3]).unwrap();
+
+        let scan = Subject::scan(&subject.path, 5, Codec::Raw, true).unwrap();
+
+        assert_eq!(scan.unreadable.len(), 1);
+        assert_eq!(scan.unreadable[0].0, filename);
+        assert!(!scan.is_clean());
+    }
+}
+
+mod restore {
+    use super::*;
+
+    #[test]
+    fn it_removes_the_chunks_the_index_does_not_reach() {
+        let subject = subject("test-27", Codec::Raw);
+        subject.write(bucket(), 3, 4).unwrap();
+        let (index, bytes) = (subject.index(), subject.bytes());
+
+        subject.write(bucket(), 3, 4).unwrap();
+        subject.write(bucket(), 5, 6).unwrap();
+
+        let orphaned = subject.restore(index.clone(), bytes).unwrap();
+
+        assert_eq!(orphaned, vec![
+            format!("{}.1", subject.basename(3, 4)),
+            format!("{}.0", subject.basename(5, 6)),
+        ]);
+        assert_eq!(subject.index(), index);
+        assert!(!Path::new(&orphaned[0]).exists());
+    }
+
+    #[test]
+    fn it_refuses_an_index_that_needs_a_missing_chunk() {
+        let subject = subject("test-28", Codec::Raw);
+        subject.write(bucket(), 3, 4).unwrap();
+        let index = subject.index();
+
+        subject.read(3, 4).unwrap();
+
+        let error = subject.restore(index, 0).unwrap_err();
+        assert_eq!(error, format!("The checkpoint needs chunks that are missing: {}.0", subject.basename(3, 4)));
+    }
+}
+
+mod checksum {
+    use super::*;
+
+    #[test]
+    fn it_reads_back_chunks_with_each_codec() {
+        for (test_id, codec) in [("test-29", ZLIB), ("test-30", Codec::Raw), ("test-36", Codec::Packed)] {
+            let subject = subject(test_id, codec);
+            subject.write(bucket(), 3, 4).unwrap();
+
+            assert_eq!(subject.read(3, 4), Ok(Some(bucket())));
+        }
+    }
+
+    #[test]
+    fn it_leaves_no_temporary_file_behind() {
+        let subject = subject("test-31", Codec::Raw);
+        subject.write(bucket(), 3, 4).unwrap();
+
+        let filename = format!("{}.0", subject.basename(3, 4));
+
+        assert!(Path::new(&filename).exists());
+        assert!(!Path::new(&format!("{}{}", filename, TEMPORARY)).exists());
+    }
+
+    #[test]
+    fn it_names_the_bucket_of_a_corrupt_chunk() {
+        let subject = subject("test-32", ZLIB);
+        subject.write(bucket(), 3, 4).unwrap();
+
+        let filename = format!("{}.0", subject.basename(3, 4));
+        let mut bytes = ::std::fs::read(&filename).unwrap();
+        bytes[10] ^= 0xff;
+        write(&filename, &bytes).unwrap();
+
+        let error = subject.read(3, 4).unwrap_err();
+
+        assert!(error.starts_with(&format!("The chunk {} of bucket (3, 4) is corrupt: its checksum is", filename)));
+    }
+
+    #[test]
+    fn it_reports_a_truncated_chunk() {
+        let subject = subject("test-33", Codec::Raw);
+        subject.write(bucket(), 3, 4).unwrap();
+
+        let filename = format!("{}.0", subject.basename(3, 4));
+        write(&filename, [1, 2]).unwrap();
+
+        let error = subject.read(3, 4).unwrap_err();
+        assert!(error.ends_with("it is 2 bytes long, which is too short to hold a checksum"));
+    }
+}
+
+mod header {
+    use super::*;
+
+    #[test]
+    fn it_refuses_a_chunk_written_for_another_n() {
+        let path = format!("{}/test-34", PATH);
+        let _ = remove_dir_all(&path);
+
+        let first = Subject::new(path.clone(), 5, Codec::Raw, SPLIT_SIZE).unwrap();
+        first.write(bucket(), 3, 4).unwrap();
+        let filename = format!("{}.0", first.basename(3, 4));
+        drop(first);
+
+        let second = Subject::open(path, 6, Codec::Raw, SPLIT_SIZE).unwrap();
+        let error = second.read(3, 4).unwrap_err();
+
+        assert_eq!(error, format!("The chunk {} of bucket (3, 4) was written for n = 5 but the search has n = 6", filename));
+    }
+
+    #[test]
+    fn it_migrates_the_chunks_that_have_no_header() {
+        let subject = subject("test-35", Codec::Raw);
+        subject.write(bucket(), 3, 4).unwrap();
+        subject.write(bucket(), 5, 6).unwrap();
+
+        let filename = format!("{}.0", subject.basename(3, 4));
+        let mut bytes = vec![];
+        serialize_into(&mut bytes, &bucket()).unwrap();
+        write(&filename, &bytes).unwrap();
+
+        assert_eq!(subject.migrate(), Ok(vec![filename]));
+        assert_eq!(subject.migrate(), Ok(vec![]));
+        assert_eq!(subject.read(3, 4), Ok(Some(bucket())));
     }
 }
@@ -0,0 +1,75 @@
+use std::collections::BTreeSet;
+use std::mem::swap;
+
+/// Every distinct string that fits the most permutations into a number of
+/// wasted symbols.
+///
+/// Strings are equivalent if one can be turned into the other by relabeling
+/// its symbols and/or reversing it. Each class is represented by its smallest
+/// string after relabeling symbols in the order they first appear.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enumeration {
+    pub n: usize,
+    pub waste: usize,
+    pub permutations: usize,
+    /// How many strings were found that start with the first permutation.
+    pub found: usize,
+    /// One string per equivalence class, in ascending order.
+    pub strings: Vec<Vec<u8>>,
+}
+
+/// The strings that achieve the lower bound for the waste being enumerated, as
+/// the search expands them. A higher bound replaces the strings found so far.
+#[derive(Debug, Clone, Default)]
+pub struct Collector {
+    permutations: usize,
+    found: usize,
+    strings: BTreeSet<Vec<u8>>,
+}
+
+impl Collector {
+    pub fn collect(&mut self, string: &[u8], permutations: usize, n: usize) {
+        if permutations > self.permutations {
+            *self = Self { permutations, ..Self::default() };
+        }
+
+        self.found += 1;
+        self.strings.insert(canonical(string, n));
+    }
+
+    pub fn enumeration(self, n: usize, waste: usize) -> Enumeration {
+        Enumeration {
+            n,
+            waste,
+            permutations: self.permutations,
+            found: self.found,
+            strings: self.strings.into_iter().collect(),
+        }
+    }
+}
+
+pub fn canonical(string: &[u8], n: usize) -> Vec<u8> {
+    let mut forwards = relabel(string.iter(), n);
+    let mut backwards = relabel(string.iter().rev(), n);
+
+    if backwards < forwards {
+        swap(&mut forwards, &mut backwards);
+    }
+
+    forwards
+}
+
+fn relabel<'a, I: Iterator<Item=&'a u8>>(string: I, n: usize) -> Vec<u8> {
+    let mut labels = vec![None; n];
+    let mut next = 0;
+
+    string.map(|&symbol| {
+        *labels[symbol as usize].get_or_insert_with(|| {
+            next += 1;
+            next - 1
+        })
+    }).collect()
+}
+
+#[cfg(test)]
+mod test;
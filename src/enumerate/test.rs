@@ -0,0 +1,62 @@
+use super::*;
+use super::super::witness::Witnesses;
+
+fn string(text: &str) -> Vec<u8> {
+    Witnesses::parse(text).unwrap()
+}
+
+mod collector {
+    use super::*;
+
+    #[test]
+    fn it_counts_a_string_and_its_reverse_as_one() {
+        let mut subject = Collector::default();
+
+        subject.collect(&string("12312"), 3, 3);
+        subject.collect(&string("21321"), 3, 3);
+
+        let enumeration = subject.enumeration(3, 0);
+
+        assert_eq!(enumeration.found, 2);
+        assert_eq!(enumeration.strings, vec![canonical(&string("12312"), 3)]);
+    }
+
+    #[test]
+    fn it_starts_afresh_when_the_bound_goes_up() {
+        let mut subject = Collector::default();
+
+        subject.collect(&string("1231"), 2, 3);
+        subject.collect(&string("123121"), 3, 3);
+
+        let enumeration = subject.enumeration(3, 1);
+
+        assert_eq!(enumeration.permutations, 3);
+        assert_eq!(enumeration.found, 1);
+        assert_eq!(enumeration.strings, vec![canonical(&string("123121"), 3)]);
+    }
+}
+
+mod canonical {
+    use super::*;
+
+    #[test]
+    fn it_relabels_symbols_in_the_order_they_first_appear() {
+        assert_eq!(relabel(string("3121").iter(), 3), string("1232"));
+    }
+
+    #[test]
+    fn it_picks_the_smaller_of_the_string_and_its_reverse() {
+        assert_eq!(canonical(&string("1233"), 3), string("1123"));
+        assert_eq!(canonical(&string("1123"), 3), string("1123"));
+    }
+
+    #[test]
+    fn it_maps_equivalent_strings_to_the_same_string() {
+        let a = canonical(&string("12312"), 3);
+        let b = canonical(&string("21321"), 3);
+        let c = canonical(&string("31231"), 3);
+
+        assert_eq!(a, b);
+        assert_eq!(b, c);
+    }
+}
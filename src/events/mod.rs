@@ -0,0 +1,156 @@
+use super::checkpoint;
+use super::known::Comparison;
+use super::observer::{Observer, Snapshot};
+use super::offload::Watermarks;
+use super::witness::Witnesses;
+
+use std::fs::File;
+use std::io::{stdout, BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    PhaseStarted { waste: usize, permutations: usize },
+    LowerBoundFound { waste: usize, permutations: usize },
+    UpperBoundTightened { waste: usize, permutations: usize },
+    WitnessFound { waste: usize, permutations: usize, witness: String },
+    BoundCompared { waste: usize, permutations: usize, comparison: String, known: usize },
+    BucketPruned { waste: usize, permutations: usize },
+    BucketUnpruned { waste: usize, permutations: usize, from_disk: bool },
+    OffloadPlanned { queue_limit: usize, high_watermark: usize, low_watermark: usize, policy: String },
+    Offloaded { waste: usize, permutations: usize, candidates: usize },
+    SearchCompleted { waste: usize, permutations: usize, length: usize },
+    CheckpointSaved { bytes: u64 },
+    CheckpointFailed { error: String },
+    OrphansRemoved { chunks: usize },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: f64,
+    #[serde(flatten)]
+    event: &'a Event,
+    enabled: usize,
+    disabled: usize,
+}
+
+pub struct Events {
+    writer: Option<Box<dyn Write + Send>>,
+}
+
+impl Events {
+    pub fn none() -> Self {
+        Self { writer: None }
+    }
+
+    pub fn open(path: &str) -> Self {
+        let writer: Box<dyn Write + Send> = match path {
+            "-" => Box::new(stdout()),
+            _ => {
+                let file = File::create(path).unwrap_or_else(|_| panic!("Failed to create {}", path));
+                Box::new(BufWriter::new(file))
+            },
+        };
+
+        Self::to(writer)
+    }
+
+    pub fn append(path: &str) -> Self {
+        match path {
+            "-" => Self::to(Box::new(stdout())),
+            _ => Self::to(Box::new(BufWriter::new(checkpoint::append(path)))),
+        }
+    }
+
+    pub fn to(writer: Box<dyn Write + Send>) -> Self {
+        Self { writer: Some(writer) }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    pub fn emit(&mut self, event: Event, enabled: usize, disabled: usize) {
+        let writer = match &mut self.writer {
+            None => return,
+            Some(writer) => writer,
+        };
+
+        let record = Record { timestamp: Self::timestamp(), event: &event, enabled, disabled };
+
+        serde_json::to_writer(&mut *writer, &record).expect("Failed to write event.");
+        writeln!(writer).expect("Failed to write event.");
+        writer.flush().expect("Failed to flush events.");
+    }
+
+    fn timestamp() -> f64 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        now.as_secs() as f64 + now.subsec_millis() as f64 / 1000.
+    }
+}
+
+impl Observer for Events {
+    fn phase_started(&mut self, waste: usize, permutations: usize, s: &Snapshot) {
+        self.emit(Event::PhaseStarted { waste, permutations }, s.enabled, s.disabled);
+    }
+
+    fn lower_bound_found(&mut self, waste: usize, permutations: usize, s: &Snapshot) {
+        self.emit(Event::LowerBoundFound { waste, permutations }, s.enabled, s.disabled);
+    }
+
+    fn upper_bound_tightened(&mut self, waste: usize, permutations: usize, s: &Snapshot) {
+        self.emit(Event::UpperBoundTightened { waste, permutations }, s.enabled, s.disabled);
+    }
+
+    fn witness_found(&mut self, waste: usize, permutations: usize, witness: &[u8], s: &Snapshot) {
+        let witness = Witnesses::format(witness);
+        self.emit(Event::WitnessFound { waste, permutations, witness }, s.enabled, s.disabled);
+    }
+
+    fn bound_compared(&mut self, waste: usize, permutations: usize, comparison: Comparison, s: &Snapshot) {
+        let known = match comparison {
+            Comparison::Matches => permutations,
+            Comparison::Beats { known } | Comparison::Contradicts { known } => known,
+        };
+
+        let comparison = comparison.name().to_string();
+        self.emit(Event::BoundCompared { waste, permutations, comparison, known }, s.enabled, s.disabled);
+    }
+
+    fn bucket_pruned(&mut self, waste: usize, permutations: usize, s: &Snapshot) {
+        self.emit(Event::BucketPruned { waste, permutations }, s.enabled, s.disabled);
+    }
+
+    fn bucket_unpruned(&mut self, waste: usize, permutations: usize, from_disk: bool, s: &Snapshot) {
+        self.emit(Event::BucketUnpruned { waste, permutations, from_disk }, s.enabled, s.disabled);
+    }
+
+    fn offload_planned(&mut self, watermarks: &Watermarks, policy: &str, s: &Snapshot) {
+        let (queue_limit, high_watermark, low_watermark) = (watermarks.queue_limit, watermarks.high, watermarks.low);
+        self.emit(Event::OffloadPlanned { queue_limit, high_watermark, low_watermark, policy: policy.to_string() }, s.enabled, s.disabled);
+    }
+
+    fn offloaded(&mut self, waste: usize, permutations: usize, candidates: usize, s: &Snapshot) {
+        self.emit(Event::Offloaded { waste, permutations, candidates }, s.enabled, s.disabled);
+    }
+
+    fn search_completed(&mut self, waste: usize, permutations: usize, length: usize, s: &Snapshot) {
+        self.emit(Event::SearchCompleted { waste, permutations, length }, s.enabled, s.disabled);
+    }
+
+    fn checkpoint_saved(&mut self, bytes: u64, s: &Snapshot) {
+        self.emit(Event::CheckpointSaved { bytes }, s.enabled, s.disabled);
+    }
+
+    fn checkpoint_failed(&mut self, error: &str, s: &Snapshot) {
+        self.emit(Event::CheckpointFailed { error: error.to_string() }, s.enabled, s.disabled);
+    }
+
+    fn orphans_removed(&mut self, filenames: &[String], s: &Snapshot) {
+        self.emit(Event::OrphansRemoved { chunks: filenames.len() }, s.enabled, s.disabled);
+    }
+}
+
+#[cfg(test)]
+mod test;
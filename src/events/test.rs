@@ -0,0 +1,70 @@
+use super::*;
+use std::sync::{Arc, Mutex};
+
+type Subject = Events;
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> ::std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Buffer {
+    fn lines(&self) -> Vec<serde_json::Value> {
+        let bytes = self.0.lock().unwrap();
+        let text = String::from_utf8(bytes.clone()).unwrap();
+
+        text.lines().map(|l| serde_json::from_str(l).unwrap()).collect()
+    }
+}
+
+mod emit {
+    use super::*;
+
+    #[test]
+    fn it_writes_one_json_object_per_line() {
+        let buffer = Buffer::default();
+        let mut subject = Subject::to(Box::new(buffer.clone()));
+
+        subject.emit(Event::PhaseStarted { waste: 3, permutations: 20 }, 10, 2);
+        subject.emit(Event::BucketUnpruned { waste: 2, permutations: 9, from_disk: true }, 7, 1);
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 2);
+
+        assert_eq!(lines[0]["event"], "phase_started");
+        assert_eq!(lines[0]["waste"], 3);
+        assert_eq!(lines[0]["permutations"], 20);
+        assert_eq!(lines[0]["enabled"], 10);
+        assert_eq!(lines[0]["disabled"], 2);
+
+        assert_eq!(lines[1]["event"], "bucket_unpruned");
+        assert_eq!(lines[1]["from_disk"], true);
+    }
+
+    #[test]
+    fn it_includes_a_timestamp_in_seconds() {
+        let buffer = Buffer::default();
+        let mut subject = Subject::to(Box::new(buffer.clone()));
+
+        subject.emit(Event::LowerBoundFound { waste: 0, permutations: 5 }, 0, 0);
+
+        let timestamp = buffer.lines()[0]["timestamp"].as_f64().unwrap();
+        assert!(timestamp > 1_500_000_000.);
+    }
+
+    #[test]
+    fn it_does_nothing_when_there_is_no_output() {
+        let mut subject = Subject::none();
+
+        assert!(!subject.enabled());
+        subject.emit(Event::LowerBoundFound { waste: 0, permutations: 5 }, 0, 0);
+    }
+}
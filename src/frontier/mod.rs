@@ -1,34 +1,132 @@
-use super::candidate::Candidate;
-use super::disk::Disk;
-use super::ui::UI;
+use super::bounds::Bounds;
+use super::candidate::{Candidate, Path};
+use super::config::Config;
+use super::disk::{Disk, Index};
+use super::events::Events;
+use super::io_thread::{Batch, IoThread};
+use super::observer::{Observer, Observers, Snapshot};
+use super::offload::{Bucket, Offloads, Policy, Watermarks};
+use super::proof::{ProofLog, Pruned, Record};
+use super::witness::Witnesses;
 
 use ::bucket_queue::*;
 
+use std::cmp::max;
 use std::collections::VecDeque;
 use std::collections::HashSet;
-use rayon::prelude::*;
+use std::io::{Read, Write};
+use bincode::{serialize_into, deserialize_from};
 
 type PriorityQueue = BucketQueue<BucketQueue<VecDeque<Candidate>>>;
 type BucketID = (usize, usize);
 
+/// How many of the buckets on disk that unprune will enable next are read
+/// ahead of time. Each of them holds a chunk in memory until it's enabled.
+pub const PREFETCH_DEPTH: usize = 2;
+
+/// How often a chunk was already on its way into memory when unprune needed
+/// it, and how many chunks were read ahead but weren't needed after all.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Prefetches {
+    pub hits: usize,
+    pub misses: usize,
+    pub unused: usize,
+}
+
+impl Prefetches {
+    /// The share of the chunks read from disk that were prefetched.
+    pub fn hit_rate(&self) -> Option<f64> {
+        match self.hits + self.misses {
+            0 => None,
+            reads => Some(self.hits as f64 / reads as f64),
+        }
+    }
+}
+
 pub struct Frontier {
     enabled_queue: PriorityQueue,
     disabled_queue: PriorityQueue,
     disabled: HashSet<BucketID>,
-    disk: Disk,
-    queue_limit: usize,
-    verbose: bool,
+    io: IoThread,
+    watermarks: Watermarks,
+    offloaded_to: usize,
+    policy: Box<dyn Policy>,
+    lower_bounds: Vec<usize>,
+    upper_bounds: Vec<usize>,
+    observers: Observers,
+    proof: ProofLog,
+    prefetches: Prefetches,
+    offloads: Offloads,
 }
 
 impl Frontier {
-    pub fn new(memory_limit: f64, gzip: bool, verbose: bool, n: usize) -> Self {
+    pub fn new(config: &Config) -> Result<Self, String> {
+        let disk = Disk::new(config.scratch.clone(), config.n, config.codec, config.split_size)?;
+
+        Ok(Self::with(config, disk, false))
+    }
+
+    /// Opens the scratch directory of a checkpoint without wiping it and
+    /// restores the frontier that was saved after the checkpoint.
+    pub fn resume(config: &Config, reader: &mut dyn Read) -> Result<Self, String> {
+        let disk = Disk::open(config.scratch.clone(), config.n, config.codec, config.split_size)?;
+
+        let mut frontier = Self::with(config, disk, true);
+        frontier.restore(reader)?;
+
+        Ok(frontier)
+    }
+
+    // A resumed search adds to its outputs rather than replacing them.
+    fn with(config: &Config, mut disk: Disk, append: bool) -> Self {
+        let mut observers = Observers::new();
+
+        if let Some(path) = &config.events {
+            let events = match append { true => Events::append(path), false => Events::open(path) };
+            observers.push(Box::new(events));
+        }
+
+        if let Some(path) = &config.witnesses {
+            let witnesses = match append { true => Witnesses::append(path), false => Witnesses::open(path) };
+            observers.push(Box::new(witnesses));
+        }
+
+        let proof = match (&config.proof, append) {
+            (Some(path), true) => ProofLog::append(path),
+            (Some(path), false) => ProofLog::open(path),
+            (None, _) => ProofLog::none(),
+        };
+
+        if config.checkpoint_every.is_some() {
+            disk.retain_chunks();
+        }
+
+        let queue_limit = Self::queue_capacity(config.memory, config.n, config.paths);
+
+        let watermarks = Watermarks {
+            bytes_per_candidate: Self::memory_per_candidate(config.n, config.paths),
+            queue_limit,
+            high: (queue_limit as f64 * config.high_watermark).floor() as usize,
+            low: (queue_limit as f64 * config.low_watermark).floor() as usize,
+        };
+
+        let policy = config.offload.policy();
+        let offloads = Offloads { policy: policy.name(), ..Offloads::default() };
+
         Frontier {
             enabled_queue: PriorityQueue::new(),
             disabled_queue: PriorityQueue::new(),
             disabled: HashSet::new(),
-            disk: Disk::new("scratch-files".to_string(), gzip),
-            queue_limit: Self::queue_limit(memory_limit, n),
-            verbose,
+            io: IoThread::new(disk),
+            watermarks,
+            offloaded_to: 0,
+            policy,
+            lower_bounds: vec![],
+            upper_bounds: vec![],
+            observers,
+            proof,
+            prefetches: Prefetches::default(),
+            offloads,
         }
     }
 
@@ -40,9 +138,11 @@ impl Frontier {
             .bucket_for_adding(wasted_symbols)
             .enqueue(candidate, permutations);
 
+        self.finish_offloads();
         self.offload_buckets_to_disk();
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<Candidate> {
         let waste = self.min_waste()?;
         let bucket = self.enabled_queue.bucket_for_removing(waste)?;
@@ -56,6 +156,8 @@ impl Frontier {
             false => wasted_symbols,
         };
 
+        self.proof.record(&Record::Prune { waste: wasted_symbols, max_waste: max, threshold });
+
         for w in wasted_symbols..=max {
             for p in 0..threshold {
                 self.disable(&(w, p));
@@ -65,35 +167,126 @@ impl Frontier {
         None
     }
 
-    pub fn unprune(&mut self, wasted_symbols: usize, lower_bounds: &[usize], upper_bounds: &[usize]) -> usize {
+    /// Enables the next pruned bucket that could beat the lower bound of the
+    /// phase that just ended, or could tie it if that phase is `ties`, and
+    /// returns the waste to carry on from.
+    pub fn unprune(&mut self, wasted_symbols: usize, lower_bounds: &[usize], upper_bounds: &[usize], ties: Option<usize>) -> Result<usize, String> {
+        self.io.check()?;
+
+        // The offload policy ranks buckets with the bounds the search is at.
+        if self.lower_bounds != lower_bounds || self.upper_bounds != upper_bounds {
+            self.lower_bounds = lower_bounds.to_vec();
+            self.upper_bounds = upper_bounds.to_vec();
+        }
+
         if wasted_symbols < lower_bounds.len() {
-            return wasted_symbols;
+            return Ok(wasted_symbols);
         }
 
         let previous_waste = wasted_symbols - 1;
+        let ties = ties == Some(previous_waste);
+        let unpruned = self.unprune_next(previous_waste, lower_bounds, upper_bounds, ties)?;
+
+        self.prefetch(previous_waste, lower_bounds, upper_bounds, ties);
+
+        Ok(unpruned.unwrap_or(wasted_symbols))
+    }
+
+    fn unprune_next(&mut self, previous_waste: usize, lower_bounds: &[usize], upper_bounds: &[usize], ties: bool) -> Result<Option<usize>, String> {
         let lower_bound = lower_bounds[previous_waste];
 
-        for w in (1..previous_waste).rev() {
+        for ((w, p), max_permutations) in Self::unprune_order(previous_waste, lower_bounds, upper_bounds, ties) {
+            let (from_disk, candidates) = match self.enable(&(w, p))? {
+                None => continue,
+                Some(enabled) => enabled,
+            };
+
+            self.proof.record(&Record::Unprune {
+                waste: w,
+                permutations: p,
+                phase: previous_waste,
+                lower_bound,
+                max_permutations,
+                from_disk,
+                candidates,
+            });
+
+            if from_disk || candidates > 0 {
+                return Ok(Some(w));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // The buckets that unprune walks after a phase, in the order it walks
+    // them, with the most permutations that the rest of the phase's waste
+    // could still add to each of them. Buckets that could only tie the lower
+    // bound are left pruned unless `ties` is set.
+    fn unprune_order<'a>(previous_waste: usize, lower_bounds: &'a [usize], upper_bounds: &'a [usize], ties: bool) -> impl Iterator<Item = (BucketID, usize)> + 'a {
+        let least = lower_bounds[previous_waste] + !ties as usize;
+
+        (1..previous_waste).rev().flat_map(move |w| {
             let allowed_waste = previous_waste - w;
             let max_permutations = upper_bounds[allowed_waste];
 
-            let min = lower_bound + 1 - max_permutations;
+            let min = least - max_permutations;
             let max = upper_bounds[w];
 
-            for p in (min..max).rev() {
-                if self.enable(&(w, p)) {
-                    return w;
-                }
-            }
-        }
+            (min..max).rev().map(move |p| ((w, p), max_permutations))
+        })
+    }
 
-        wasted_symbols
+    // Starts reading the next few buckets on disk that unprune would enable,
+    // so their chunks are in memory or on the way by the time it gets there.
+    fn prefetch(&mut self, previous_waste: usize, lower_bounds: &[usize], upper_bounds: &[usize], ties: bool) {
+        let upcoming: Vec<_> = Self::unprune_order(previous_waste, lower_bounds, upper_bounds, ties)
+            .map(|(bucket_id, _)| bucket_id)
+            .filter(|id| self.disabled.contains(id) && self.io.holds(id.0, id.1))
+            .take(PREFETCH_DEPTH)
+            .collect();
+
+        for (waste, permutations) in upcoming {
+            self.io.request(waste, permutations);
+        }
     }
 
     pub fn len(&self) -> usize {
         self.enabled_queue.len() + self.disabled_queue.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn enabled_len(&self) -> usize {
+        self.enabled_queue.len()
+    }
+
+    pub fn disabled_len(&self) -> usize {
+        self.disabled_queue.len()
+    }
+
+    pub fn disabled_buckets(&self) -> usize {
+        self.disabled.len()
+    }
+
+    pub fn prefetches(&self) -> Prefetches {
+        self.prefetches
+    }
+
+    pub fn watermarks(&self) -> Watermarks {
+        self.watermarks
+    }
+
+    pub fn offloads(&self) -> Offloads {
+        self.offloads
+    }
+
+    pub fn disk_bytes(&self) -> u64 {
+        self.io.disk().bytes()
+    }
+
     pub fn min_waste(&self) -> Option<usize> {
         self.enabled_queue.min_priority()
     }
@@ -102,39 +295,169 @@ impl Frontier {
         self.enabled_queue.max_priority()
     }
 
-    fn enable(&mut self, bucket_id: &BucketID) -> bool {
-        if !self.disabled.contains(bucket_id) {
-            return false;
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            min_waste: self.min_waste(),
+            enabled: self.enabled_len(),
+            disabled: self.disabled_len(),
+            disabled_buckets: self.disabled_buckets(),
+            disk_bytes: self.disk_bytes(),
         }
+    }
+
+    pub fn proving(&self) -> bool {
+        self.proof.enabled()
+    }
+
+    pub fn record(&mut self, record: Record) {
+        self.proof.record(&record);
+    }
+
+    /// The disabled buckets up to a number of wasted symbols that still have
+    /// candidates in memory or on disk. It waits for the I/O thread first so
+    /// that candidates on their way to disk are counted.
+    pub fn pruned_buckets(&mut self, max_waste: usize) -> Result<Vec<Pruned>, String> {
+        self.flush()?;
+
+        let mut pruned: Vec<_> = self.disabled.iter()
+            .filter(|id| id.0 <= max_waste)
+            .map(|&(waste, permutations)| Pruned {
+                waste,
+                permutations,
+                candidates: Self::bucket_len(&self.disabled_queue, &(waste, permutations)),
+                chunks: self.io.disk().chunks(waste, permutations),
+            })
+            .filter(|b| b.candidates + b.chunks > 0)
+            .collect();
+
+        pruned.sort_by_key(|b| (b.waste, b.permutations));
+        Ok(pruned)
+    }
+
+    /// Waits for every chunk in flight to be written or read. Chunks that
+    /// were read before they were needed go back to their buckets in memory,
+    /// since the disk index no longer reaches them.
+    pub fn flush(&mut self) -> Result<(), String> {
+        let batches = self.io.flush()?;
+        self.notify_offloaded(batches);
+
+        for (bucket_id, read) in self.io.unclaimed() {
+            let mut bucket = match read? {
+                None => continue,
+                Some(bucket) => bucket,
+            };
+
+            self.prefetches.unused += 1;
+            self.offloads.onloaded += bucket.len();
 
-        if self.onload_from_disk(bucket_id) {
-            if self.verbose {
-                println!("  unpruning {:?} from disk ..... queue: {}", bucket_id, self.len());
+            let mut waste_bucket = self.queue_for(&bucket_id).bucket(bucket_id.0);
+
+            if let Some(candidates) = waste_bucket.replace(bucket_id.1, None) {
+                bucket.extend(candidates);
             }
 
-            return true;
+            waste_bucket.replace(bucket_id.1, Some(bucket));
         }
 
-        if self.disabled.remove(bucket_id) {
-            if Self::bucket_len(&self.disabled_queue, bucket_id) > 0 {
-                if self.verbose {
-                    println!("  unpruning {:?} from memory ... queue: {}", bucket_id, self.len());
-                }
+        Ok(())
+    }
+
+    /// Writes the disabled set, the disk index and every bucket in memory.
+    /// The frontier must be flushed first.
+    pub fn save(&self, writer: &mut dyn Write) -> Result<(), String> {
+        let mut disabled: Vec<_> = self.disabled.iter().copied().collect();
+        disabled.sort();
+
+        let error = |e: bincode::Error| format!("Failed to save the frontier: {}", e);
+
+        serialize_into(&mut *writer, &disabled).map_err(error)?;
+        serialize_into(&mut *writer, &self.io.disk().index()).map_err(error)?;
+        serialize_into(&mut *writer, &self.io.disk().bytes()).map_err(error)?;
+        serialize_into(&mut *writer, &Self::buckets(&self.enabled_queue)).map_err(error)?;
+        serialize_into(&mut *writer, &Self::buckets(&self.disabled_queue)).map_err(error)?;
+
+        Ok(())
+    }
+
+    fn restore(&mut self, reader: &mut dyn Read) -> Result<(), String> {
+        let error = |e: bincode::Error| format!("Failed to restore the frontier: {}", e);
+
+        let disabled: Vec<BucketID> = deserialize_from(&mut *reader).map_err(error)?;
+        let index: Index = deserialize_from(&mut *reader).map_err(error)?;
+        let bytes: u64 = deserialize_from(&mut *reader).map_err(error)?;
 
-                Self::swap(&mut self.disabled_queue, &mut self.enabled_queue, bucket_id);
-                return true;
+        self.disabled = disabled.into_iter().collect();
+        let orphaned = self.io.disk().restore(index, bytes)?;
+
+        for queue in [&mut self.enabled_queue, &mut self.disabled_queue] {
+            let buckets: Vec<(usize, usize, VecDeque<Candidate>)> = deserialize_from(&mut *reader).map_err(error)?;
+
+            for (waste, permutations, bucket) in buckets {
+                queue.bucket(waste).replace(permutations, Some(bucket));
             }
         }
 
-        false
+        if !orphaned.is_empty() {
+            self.notify(|o, s| o.orphans_removed(&orphaned, s));
+        }
+
+        Ok(())
+    }
+
+    /// Removes the chunks that were read before the last checkpoint was saved.
+    pub fn release(&self) -> Result<(), String> {
+        self.io.disk().release()
+    }
+
+    pub fn observe(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    pub fn notify<F: FnMut(&mut dyn Observer, &Snapshot)>(&mut self, mut f: F) {
+        let snapshot = self.snapshot();
+        self.observers.notify(|observer| f(observer, &snapshot));
+    }
+
+    // Returns whether the bucket was read from disk and how many candidates
+    // were enabled, or none if the bucket wasn't disabled.
+    fn enable(&mut self, bucket_id: &BucketID) -> Result<Option<(bool, usize)>, String> {
+        if !self.disabled.contains(bucket_id) {
+            return Ok(None);
+        }
+
+        if let Some(candidates) = self.onload_from_disk(bucket_id)? {
+            self.notify_unpruned(bucket_id, true);
+            return Ok(Some((true, candidates)));
+        }
+
+        self.disabled.remove(bucket_id);
+        let candidates = Self::bucket_len(&self.disabled_queue, bucket_id);
+
+        if candidates > 0 {
+            Self::swap(&mut self.disabled_queue, &mut self.enabled_queue, bucket_id);
+            self.notify_unpruned(bucket_id, false);
+        }
+
+        Ok(Some((false, candidates)))
     }
 
     fn disable(&mut self, bucket_id: &BucketID) -> bool {
-        if self.disabled.insert(*bucket_id) {
-            Self::swap(&mut self.enabled_queue, &mut self.disabled_queue, bucket_id).is_some()
-        } else {
-            false
+        if !self.disabled.insert(*bucket_id) {
+            return false;
+        }
+
+        if Self::swap(&mut self.enabled_queue, &mut self.disabled_queue, bucket_id).is_none() {
+            return false;
         }
+
+        let (waste, permutations) = *bucket_id;
+        self.notify(|o, s| o.bucket_pruned(waste, permutations, s));
+        true
+    }
+
+    fn notify_unpruned(&mut self, bucket_id: &BucketID, from_disk: bool) {
+        let (waste, permutations) = *bucket_id;
+        self.notify(|o, s| o.bucket_unpruned(waste, permutations, from_disk, s));
     }
 
     fn swap(from: &mut PriorityQueue, to: &mut PriorityQueue, bucket_id: &BucketID) -> Option<()> {
@@ -159,12 +482,25 @@ impl Frontier {
         }
     }
 
-    fn onload_from_disk(&mut self, bucket_id: &BucketID) -> bool {
-        let bucket = match self.disk.read(bucket_id.0, bucket_id.1) {
-            None => return false,
+    // The I/O thread reads a bucket only after the writes of it that came
+    // first, so a chunk that's still being written is waited for.
+    fn onload_from_disk(&mut self, bucket_id: &BucketID) -> Result<Option<usize>, String> {
+        let prefetched = self.io.requested(bucket_id.0, bucket_id.1);
+
+        let bucket = match self.io.read(bucket_id.0, bucket_id.1)? {
+            None => return Ok(None),
             Some(bucket) => bucket,
         };
 
+        match prefetched {
+            true => self.prefetches.hits += 1,
+            false => self.prefetches.misses += 1,
+        }
+
+        self.offloads.onloaded += bucket.len();
+
+        let candidates = bucket.len();
+
         if Self::bucket_len(&self.enabled_queue, bucket_id) > 0 {
             panic!("about to overwrite data");
         }
@@ -173,62 +509,108 @@ impl Frontier {
             .bucket(bucket_id.0)
             .replace(bucket_id.1, Some(bucket));
 
-        true
+        Ok(Some(candidates))
     }
 
+    // Once the frontier grows past the high watermark, the policy picks
+    // disabled buckets to write to disk until it's back under the low one.
+    //
+    // The enabled queue can hold the frontier above the low watermark by
+    // itself, so the next offload then waits until the frontier has grown by
+    // the gap between the watermarks again rather than writing a few
+    // candidates on every add.
     fn offload_buckets_to_disk(&mut self) {
-        if self.len() <= self.queue_limit {
-            return;
+        let mut len = self.len();
+
+        if len <= self.watermarks.low {
+            self.offloaded_to = 0;
         }
 
-        print!("running low on memory, offloading to disk... ");
-        UI::flush();
-        if self.verbose { println!(); }
+        let gap = self.watermarks.high - self.watermarks.low;
+        let threshold = max(self.watermarks.high, self.offloaded_to + gap);
+
+        if len <= threshold || self.disabled_queue.is_empty() {
+            return;
+        }
 
-        let queue = &mut self.disabled_queue;
+        let mut buckets: Vec<_> = Self::buckets(&self.disabled_queue).into_iter()
+            .map(|(waste, permutations, bucket)| Bucket { waste, permutations, candidates: bucket.len() })
+            .collect();
 
-        let waste_min = queue.min_priority().unwrap();
-        let waste_max = queue.max_priority().unwrap();
+        self.policy.rank(&mut buckets, &self.lower_bounds, &self.upper_bounds);
 
         let mut jobs = vec![];
 
-        for w in waste_min..=waste_max {
-            let mut waste_bucket = self.disabled_queue.bucket(w);
+        for bucket in buckets {
+            if len <= self.watermarks.low {
+                break;
+            }
 
-            let perm_min = match waste_bucket.min_priority() {
+            let candidates = match self.disabled_queue.bucket(bucket.waste).replace(bucket.permutations, None) {
                 None => continue,
-                Some(p) => p,
+                Some(candidates) => candidates,
             };
 
-            let perm_max = waste_bucket.max_priority().unwrap();
+            len -= candidates.len();
 
-            if self.verbose {
-                print!("  {:02}, {:03}..{:03} | ", w, perm_min, perm_max);
-            }
+            self.offloads.buckets += 1;
+            self.offloads.candidates += candidates.len();
 
-            for p in perm_min..=perm_max {
-                let bucket = match waste_bucket.replace(p, None) {
-                    None => continue,
-                    Some(b) => b,
-                };
+            jobs.push((bucket.waste, bucket.permutations, candidates));
+        }
 
-                if self.verbose {
-                    print!("{} ", bucket.len());
-                }
+        self.offloads.offloads += 1;
+        self.offloaded_to = len;
+        self.io.write(jobs);
+    }
 
-                jobs.push((bucket, w, p));
-            }
+    // Notifies the observers of the offloads that have finished since the
+    // last call, so they only hear of chunks that are complete on disk. Each
+    // offload is reported as a whole even if the next one has already started.
+    fn finish_offloads(&mut self) {
+        let batches = self.io.poll();
+        self.notify_offloaded(batches);
+    }
 
-            if self.verbose {
-                println!();
+    fn notify_offloaded(&mut self, batches: Vec<Batch>) {
+        for batch in batches {
+            self.notify(|o, s| o.offload_started(s));
+
+            for (waste, permutations, candidates) in batch {
+                self.notify(|o, s| o.offloaded(waste, permutations, candidates, s));
             }
+
+            self.notify(|o, s| o.offload_finished(s));
         }
+    }
+
+    fn buckets(queue: &PriorityQueue) -> Vec<(usize, usize, &VecDeque<Candidate>)> {
+        let mut buckets = vec![];
+
+        let (waste_min, waste_max) = match (queue.min_priority(), queue.max_priority()) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return buckets,
+        };
 
-        jobs.into_par_iter().for_each(|job| {
-            self.disk.write_chunks(job.0, job.1, job.2);
-        });
+        for w in waste_min..=waste_max {
+            let waste_bucket = match queue.bucket_for_peeking(w) {
+                None => continue,
+                Some(b) => b,
+            };
 
-        println!("done");
+            let (perm_min, perm_max) = match (waste_bucket.min_priority(), waste_bucket.max_priority()) {
+                (Some(min), Some(max)) => (min, max),
+                _ => continue,
+            };
+
+            for p in perm_min..=perm_max {
+                if let Some(bucket) = waste_bucket.bucket_for_peeking(p).filter(|b| !b.is_empty()) {
+                    buckets.push((w, p, bucket));
+                }
+            }
+        }
+
+        buckets
     }
 
     fn bucket_len(queue: &PriorityQueue, bucket_id: &BucketID) -> usize {
@@ -241,27 +623,26 @@ impl Frontier {
         }
     }
 
-    fn queue_limit(memory_limit: f64, n: usize) -> usize {
-        let bytes = Self::memory_per_candidate(n);
+    pub fn queue_capacity(memory_limit: f64, n: usize, paths: bool) -> usize {
+        let bytes = Self::memory_per_candidate(n, paths);
         let gigabytes = memory_limit * 1024. * 1024. * 1024.;
 
-        let limit = (gigabytes / bytes as f64).floor() as usize;
-        println!("The queue limit has been set to {}GiB / {}B = {} candidates.\n", memory_limit, bytes, limit);
-
-        limit
+        (gigabytes / bytes as f64).floor() as usize
     }
 
-    fn memory_per_candidate(n: usize) -> usize {
-        let factorial = super::Bounds::factorial(n);
+    pub fn memory_per_candidate(n: usize, paths: bool) -> usize {
+        let factorial = Bounds::factorial(n);
 
-        let bitset_bytes = (factorial + 7) / 8;
+        let bitset_bytes = factorial.div_ceil(8);
         let tail_bytes = (n - 1) * 8;
         let waste_bytes = 8;
+        // The path of a superpermutation has at least n! choices in it.
+        let path_bytes = match paths {
+            true => (factorial * Path::bits_per_choice(n)).div_ceil(8) + 8,
+            false => 0,
+        };
 
-        let bytes = bitset_bytes + tail_bytes + waste_bytes;
-        println!("\nEach candidate string consumes approximately {} bytes of memory.", bytes);
-
-        bytes
+        bitset_bytes + tail_bytes + waste_bytes + path_bytes
     }
 }
 
@@ -1,14 +1,47 @@
 use super::*;
-use std::usize::MAX;
+const MAX: usize = usize::MAX;
 use bit_set::BitSet;
+use super::super::codec::Codec;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 type Subject = Frontier;
 
 const N: usize = 5;
 const F: bool = false;
 
+static SCRATCH_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn scratch() -> String {
+    let id = SCRATCH_ID.fetch_add(1, Ordering::SeqCst);
+    format!("/tmp/superpermutation-test/frontier-{}", id)
+}
+
+fn candidate(wasted_symbols: u16, permutations: usize) -> Candidate {
+    let mut permutations_seen = BitSet::new();
+
+    for i in 0..permutations {
+        permutations_seen.insert(i);
+    }
+
+    Candidate {
+        permutations_seen,
+        tail_of_string: vec![0, 1, 2, 3],
+        wasted_symbols,
+        path: Path::default(),
+    }
+}
+
 fn subject() -> Subject {
-    Subject::new(1.0, true, true, N)
+    let config = Config {
+        n: N,
+        memory: 1.0,
+        codec: Codec::Zlib(6),
+        verbose: true,
+        scratch: scratch(),
+        ..Config::default()
+    };
+
+    Subject::new(&config).unwrap()
 }
 
 mod new {
@@ -47,7 +80,7 @@ mod add {
         let mut subject = subject();
 
         let seed = Candidate::seed(N);
-        let candidate = seed.expand(MAX, N).last().unwrap();
+        let candidate = seed.expand(MAX, N, true).last().unwrap();
 
         let total_waste = candidate.total_waste(N);
         let permutations = candidate.number_of_permutations();
@@ -67,7 +100,7 @@ mod add {
             let mut subject = subject();
             let seed = Candidate::seed(N);
 
-            let candidate = seed.expand(MAX, N).last().unwrap();
+            let candidate = seed.expand(MAX, N, true).last().unwrap();
 
             let total_waste = candidate.total_waste(N);
             let permutations = candidate.number_of_permutations();
@@ -89,7 +122,7 @@ mod prune {
         let mut subject = subject();
         let candidate = Candidate::seed(N);
 
-        for c in candidate.expand(MAX, N) {
+        for c in candidate.expand(MAX, N, true) {
             subject.add(c, N);
         }
 
@@ -128,7 +161,7 @@ mod prune {
             let mut subject = subject();
             let candidate = Candidate::seed(N);
 
-            for c in candidate.expand(MAX, N) {
+            for c in candidate.expand(MAX, N, true) {
                 subject.add(c, N);
             }
 
@@ -191,30 +224,30 @@ mod unprune {
         // Waste 2 is allowed 1 more wasted symbol which can add 8 permutations.
         // To improve on the lower bound of 14, we'd need to see 7 permutations.
 
-        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds);
+        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds, None).unwrap();
         assert_eq!(last_unpruned(&mut subject), (2, 9));
 
-        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds);
+        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds, None).unwrap();
         assert_eq!(last_unpruned(&mut subject), (2, 8));
 
-        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds);
+        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds, None).unwrap();
         assert_eq!(last_unpruned(&mut subject), (2, 7));
 
         // Waste 1 is allowed 2 more wasted symbols which can add 12 permutations.
         // To improve on the lower bound of 14, we'd need to see 3 permutations.
 
-        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds);
+        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds, None).unwrap();
         assert_eq!(last_unpruned(&mut subject), (1, 5));
 
-        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds);
+        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds, None).unwrap();
         assert_eq!(last_unpruned(&mut subject), (1, 4));
 
-        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds);
+        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds, None).unwrap();
         assert_eq!(last_unpruned(&mut subject), (1, 3));
 
         // Nothing left to unprune:
 
-        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds);
+        subject.unprune(wasted_symbols, &lower_bounds, &upper_bounds, None).unwrap();
         assert_eq!(subject.enabled_queue.len(), 0);
     }
 
@@ -228,8 +261,8 @@ mod unprune {
         let lower_bounds = vec![4, 8, 12, 16];
         let upper_bounds = vec![4, 8, 12, 18];
 
-        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds), 2);
-        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds), 1);
+        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds, None), Ok(2));
+        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds, None), Ok(1));
     }
 
     #[test]
@@ -242,33 +275,78 @@ mod unprune {
         let lower_bounds = vec![4, 8, 12, 16];
         let upper_bounds = vec![4, 8, 12, 18];
 
-        subject.unprune(4, &lower_bounds, &upper_bounds);
-        subject.unprune(4, &lower_bounds, &upper_bounds);
+        subject.unprune(4, &lower_bounds, &upper_bounds, None).unwrap();
+        subject.unprune(4, &lower_bounds, &upper_bounds, None).unwrap();
 
-        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds), 4);
-        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds), 4);
+        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds, None), Ok(4));
+        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds, None), Ok(4));
     }
 
-    fn add_pruned_candidate(frontier: &mut Frontier, wasted_symbols: u16, permutations: usize) {
-        let mut permutations_seen = BitSet::new();
+    #[test]
+    fn it_unprunes_buckets_that_could_tie_the_lower_bound_for_the_phase_in_ties() {
+        let mut subject = subject();
 
-        for i in 0..permutations {
-            permutations_seen.insert(i);
-        }
+        add_pruned_candidate(&mut subject, 2, 6);
 
-        let candidate = Candidate {
-            permutations_seen,
-            tail_of_string: vec![0, 1, 2, 3],
-            wasted_symbols
-        };
+        let lower_bounds = vec![4, 8, 12, 14];
+        let upper_bounds = vec![4, 8, 12, 16];
+
+        // 6 permutations plus the 8 that 1 more wasted symbol can add only ties 14.
+
+        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds, None), Ok(4));
+        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds, Some(2)), Ok(4));
+        assert_eq!(subject.unprune(4, &lower_bounds, &upper_bounds, Some(3)), Ok(2));
+
+        assert_eq!(last_unpruned(&mut subject), (2, 6));
+    }
+
+    #[test]
+    fn it_prefetches_the_buckets_on_disk_that_it_will_unprune_next() {
+        let mut subject = subject();
+
+        add_pruned_candidate(&mut subject, 1, 5);
+        add_pruned_candidate(&mut subject, 2, 7);
+        add_pruned_candidate(&mut subject, 2, 8);
+        add_pruned_candidate(&mut subject, 2, 9);
 
-        frontier.add(candidate, N);
+        subject.watermarks.high = 0;
+        subject.watermarks.low = 0;
+        subject.offload_buckets_to_disk();
+        subject.flush().unwrap();
+
+        let lower_bounds = vec![4, 8, 12, 14];
+        let upper_bounds = vec![4, 8, 12, 16];
+
+        subject.unprune(4, &lower_bounds, &upper_bounds, None).unwrap();
+        assert_eq!(last_unpruned(&mut subject), (2, 9));
+        assert_eq!(subject.prefetches(), Prefetches { hits: 0, misses: 1, unused: 0 });
+
+        assert!(subject.io.requested(2, 8));
+        assert!(subject.io.requested(2, 7));
+        assert!(!subject.io.requested(1, 5));
+
+        subject.unprune(4, &lower_bounds, &upper_bounds, None).unwrap();
+        assert_eq!(last_unpruned(&mut subject), (2, 8));
+        assert!(subject.io.requested(1, 5));
+
+        subject.unprune(4, &lower_bounds, &upper_bounds, None).unwrap();
+        assert_eq!(last_unpruned(&mut subject), (2, 7));
+
+        subject.unprune(4, &lower_bounds, &upper_bounds, None).unwrap();
+        assert_eq!(last_unpruned(&mut subject), (1, 5));
+
+        assert_eq!(subject.prefetches(), Prefetches { hits: 3, misses: 1, unused: 0 });
+        assert_eq!(subject.prefetches().hit_rate(), Some(0.75));
+    }
+
+    fn add_pruned_candidate(frontier: &mut Frontier, wasted_symbols: u16, permutations: usize) {
+        frontier.add(candidate(wasted_symbols, permutations), N);
         frontier.disable(&(wasted_symbols as usize, permutations));
     }
 
     fn last_unpruned(subject: &mut Subject) -> (usize, usize) {
         let candidate = subject.next().unwrap();
-        assert_eq!(subject.enabled_queue.is_empty(), true);
+        assert!(subject.enabled_queue.is_empty());
 
         (candidate.wasted_symbols as usize, candidate.number_of_permutations())
     }
@@ -282,7 +360,7 @@ mod next {
         let mut subject = subject();
         let candidate = Candidate::seed(N);
 
-        for c in candidate.expand(MAX, N) {
+        for c in candidate.expand(MAX, N, true) {
             subject.add(c, N);
         }
 
@@ -310,7 +388,7 @@ mod next {
         let mut subject = subject();
         let candidate = Candidate::seed(N);
 
-        for c in candidate.expand(MAX, N) {
+        for c in candidate.expand(MAX, N, true) {
             subject.add(c, N);
         }
 
@@ -339,7 +417,7 @@ mod min_waste {
         let mut subject = subject();
         let candidate = Candidate::seed(N);
 
-        for c in candidate.expand(MAX, N) {
+        for c in candidate.expand(MAX, N, true) {
             subject.add(c, N);
         }
 
@@ -361,7 +439,7 @@ mod max_waste {
         let mut subject = subject();
         let candidate = Candidate::seed(N);
 
-        for c in candidate.expand(MAX, N) {
+        for c in candidate.expand(MAX, N, true) {
             subject.add(c, N);
         }
 
@@ -378,10 +456,10 @@ mod enable_and_disable {
         let bucket_id = (2, 3);
 
         subject.disable(&bucket_id);
-        assert_eq!(subject.disabled.contains(&bucket_id), true);
+        assert!(subject.disabled.contains(&bucket_id));
 
-        subject.enable(&bucket_id);
-        assert_eq!(subject.disabled.contains(&bucket_id), false);
+        subject.enable(&bucket_id).unwrap();
+        assert!(!subject.disabled.contains(&bucket_id));
     }
 
     #[test]
@@ -389,7 +467,7 @@ mod enable_and_disable {
         let mut subject = subject();
 
         let seed = Candidate::seed(N);
-        let candidate = seed.expand(MAX, N).last().unwrap();
+        let candidate = seed.expand(MAX, N, true).last().unwrap();
 
         let total_waste = candidate.total_waste(N);
         let permutations = candidate.number_of_permutations();
@@ -406,39 +484,181 @@ mod enable_and_disable {
         assert_eq!(subject.enabled_queue.len(), 0);
         assert_eq!(subject.disabled_queue.len(), 1);
 
-        subject.enable(&bucket_id);
+        subject.enable(&bucket_id).unwrap();
 
         assert_eq!(subject.enabled_queue.len(), 1);
         assert_eq!(subject.disabled_queue.len(), 0);
     }
 
     #[test]
-    fn it_returns_true_if_the_bucket_that_was_moved_contained_something() {
+    fn it_returns_how_many_candidates_were_enabled_if_the_bucket_was_disabled() {
         let mut subject = subject();
 
         let seed = Candidate::seed(N);
-        let candidate = seed.expand(MAX, N).last().unwrap();
+        let candidate = seed.expand(MAX, N, true).last().unwrap();
 
         let total_waste = candidate.total_waste(N);
         let permutations = candidate.number_of_permutations();
 
         let bucket_id = (total_waste, permutations);
 
-        assert_eq!(subject.enable(&bucket_id), false);
-        assert_eq!(subject.disable(&bucket_id), false);
+        assert_eq!(subject.enable(&bucket_id), Ok(None));
+        assert!(!subject.disable(&bucket_id));
 
         subject.add(candidate, N);
 
-        assert_eq!(subject.disable(&bucket_id), false);
-        assert_eq!(subject.disable(&bucket_id), false);
+        assert!(!subject.disable(&bucket_id));
+        assert!(!subject.disable(&bucket_id));
 
-        assert_eq!(subject.enable(&bucket_id), true);
-        assert_eq!(subject.enable(&bucket_id), false);
+        assert_eq!(subject.enable(&bucket_id), Ok(Some((false, 1))));
+        assert_eq!(subject.enable(&bucket_id), Ok(None));
 
         subject.next();
         assert_eq!(subject.len(), 0);
 
-        assert_eq!(subject.disable(&bucket_id), false);
-        assert_eq!(subject.enable(&bucket_id), false);
+        assert!(!subject.disable(&bucket_id));
+        assert_eq!(subject.enable(&bucket_id), Ok(Some((false, 0))));
+    }
+}
+
+mod offload {
+    use super::*;
+
+    // Enough memory for a single candidate.
+    fn subject() -> Subject {
+        let bytes = Subject::memory_per_candidate(N, false) as f64;
+        let memory = bytes * 1.5 / 1024. / 1024. / 1024.;
+
+        Subject::new(&Config { n: N, memory, scratch: scratch(), ..Config::default() }).unwrap()
+    }
+
+    fn offloaded(subject: &mut Subject) -> BucketID {
+        let candidate = Candidate::seed(N).expand(MAX, N, true).last().unwrap();
+        let bucket_id = (candidate.total_waste(N), candidate.number_of_permutations());
+
+        subject.add(Candidate::seed(N), N);
+        subject.disable(&bucket_id);
+        subject.add(candidate, N);
+
+        bucket_id
+    }
+
+    #[test]
+    fn it_writes_disabled_buckets_to_disk_when_memory_runs_low() {
+        let mut subject = subject();
+        let bucket_id = offloaded(&mut subject);
+
+        assert_eq!(subject.disabled_len(), 0);
+
+        subject.flush().unwrap();
+
+        assert_eq!(subject.io.disk().chunks(bucket_id.0, bucket_id.1), 1);
+        assert!(subject.disk_bytes() > 0);
+    }
+
+    #[test]
+    fn it_offloads_buckets_in_the_policys_order_until_under_the_low_watermark() {
+        let mut subject = subject();
+
+        subject.watermarks.high = 3;
+        subject.watermarks.low = 1;
+
+        for (waste, permutations) in [(1, 2), (1, 3), (2, 6), (2, 7)] {
+            subject.disable(&(waste as usize, permutations));
+            subject.add(candidate(waste, permutations), N);
+        }
+
+        subject.flush().unwrap();
+
+        // Without any bounds yet, the buckets with the most waste go first.
+        let chunks: Vec<_> = [(2, 6), (2, 7), (1, 2), (1, 3)].iter()
+            .map(|&(w, p)| subject.io.disk().chunks(w, p))
+            .collect();
+
+        assert_eq!(chunks, vec![1, 1, 1, 0]);
+        assert_eq!(subject.disabled_len(), 1);
+        assert_eq!(subject.offloads(), Offloads { policy: "unlikely", offloads: 1, buckets: 3, candidates: 3, onloaded: 0 });
+    }
+
+    #[test]
+    fn it_waits_for_the_frontier_to_grow_again_when_the_enabled_queue_is_above_the_low_watermark() {
+        let mut subject = subject();
+
+        subject.watermarks.high = 3;
+        subject.watermarks.low = 1;
+
+        for permutations in 1..=3 {
+            subject.add(candidate(0, permutations), N);
+        }
+
+        for permutations in 2..=6 {
+            subject.disable(&(1, permutations));
+            subject.add(candidate(1, permutations), N);
+        }
+
+        subject.flush().unwrap();
+
+        // The first disabled candidate is written straight away, which leaves
+        // three, and the next offload waits until there are six.
+        let chunks: Vec<_> = (2..=6).map(|p| subject.io.disk().chunks(1, p)).collect();
+
+        assert_eq!(chunks, vec![1, 1, 1, 1, 0]);
+        assert_eq!(subject.len(), 4);
+        assert_eq!(subject.offloads().offloads, 2);
+    }
+
+    #[test]
+    fn it_reads_the_bucket_back_when_it_is_enabled() {
+        let mut subject = subject();
+        let bucket_id = offloaded(&mut subject);
+
+        assert_eq!(subject.enable(&bucket_id), Ok(Some((true, 1))));
+        assert_eq!(subject.enabled_len(), 2);
+        assert_eq!(subject.offloads().onloaded, 1);
+    }
+
+    #[test]
+    fn it_puts_chunks_that_were_read_early_back_in_memory_when_flushed() {
+        let mut subject = subject();
+        let bucket_id = offloaded(&mut subject);
+
+        subject.io.request(bucket_id.0, bucket_id.1);
+        subject.flush().unwrap();
+
+        assert_eq!(subject.io.disk().chunks(bucket_id.0, bucket_id.1), 0);
+        assert_eq!(subject.disabled_len(), 1);
+        assert_eq!(subject.prefetches().unused, 1);
+        assert_eq!(subject.enable(&bucket_id), Ok(Some((false, 1))));
+    }
+}
+
+mod events {
+    use super::*;
+    use std::fs::{create_dir_all, read_to_string};
+
+    #[test]
+    fn it_emits_an_event_for_each_bucket_that_is_pruned_or_unpruned() {
+        let path = "/tmp/superpermutation-test/frontier-events.jsonl";
+        create_dir_all("/tmp/superpermutation-test").unwrap();
+
+        let config = Config { n: N, memory: 1.0, scratch: scratch(), events: Some(path.to_string()), ..Config::default() };
+        let mut subject = Subject::new(&config).unwrap();
+
+        for c in Candidate::seed(N).expand(MAX, N, true) {
+            subject.add(c, N);
+        }
+
+        subject.prune(2, 2, true);
+        subject.enable(&(2, 1)).unwrap();
+
+        let events = read_to_string(path).unwrap();
+        let lines: Vec<&str> = events.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+
+        assert!(lines[0].contains(r#""event":"bucket_pruned","waste":2,"permutations":1"#));
+        assert!(lines[1].contains(r#""event":"bucket_pruned","waste":3,"permutations":1"#));
+        assert!(lines[2].contains(r#""event":"bucket_unpruned","waste":2,"permutations":1"#));
+        assert!(lines[2].contains(r#""enabled":3,"disabled":1"#));
     }
 }
@@ -0,0 +1,25 @@
+use super::*;
+
+type Subject = Interrupt;
+
+mod request {
+    use super::*;
+
+    #[test]
+    fn it_is_not_requested_until_a_signal_arrives() {
+        let subject = Subject::new();
+        assert!(!subject.requested());
+
+        subject.request();
+        assert!(subject.requested());
+    }
+
+    #[test]
+    fn it_is_shared_between_clones() {
+        let subject = Subject::new();
+        let handler = subject.clone();
+
+        handler.request();
+        assert!(subject.requested());
+    }
+}
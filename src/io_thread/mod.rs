@@ -0,0 +1,259 @@
+use super::candidate::Candidate;
+use super::disk::Disk;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::thread::{spawn, JoinHandle};
+use rayon::prelude::*;
+
+/// How many jobs can wait for the worker before submitting another blocks.
+/// Each offload is one job, so this bounds the candidates held in flight.
+pub const QUEUE_SIZE: usize = 2;
+
+const STOPPED: &str = "The I/O thread stopped unexpectedly";
+
+/// The buckets of an offload, with how many candidates each of them had.
+pub type Batch = Vec<(usize, usize, usize)>;
+
+type BucketID = (usize, usize);
+type Read = Result<Option<VecDeque<Candidate>>, String>;
+
+enum Job {
+    Write(Vec<(usize, usize, VecDeque<Candidate>)>),
+    Read(BucketID),
+}
+
+enum Done {
+    Written(Batch, Option<String>),
+    Read(BucketID, Read),
+}
+
+/// Reads and writes chunks on a thread of its own so the search can carry on
+/// expanding candidates while they are on their way to or from disk.
+///
+/// The worker runs jobs in the order they were submitted, so a read of a
+/// bucket always comes after every write of it that was submitted first. A
+/// bucket that leaves memory is never read back before it is fully written.
+pub struct IoThread {
+    disk: Arc<Disk>,
+    jobs: Option<SyncSender<Job>>,
+    done: Receiver<Done>,
+    worker: Option<JoinHandle<()>>,
+    writing: usize,
+    pending: HashMap<BucketID, usize>,
+    reading: HashSet<BucketID>,
+    reads: HashMap<BucketID, Read>,
+    written: Vec<Batch>,
+    error: Option<String>,
+}
+
+impl IoThread {
+    pub fn new(disk: Disk) -> Self {
+        let disk = Arc::new(disk);
+
+        let (jobs, receiver) = sync_channel(QUEUE_SIZE);
+        let (sender, done) = channel();
+
+        let worker_disk = Arc::clone(&disk);
+        let worker = spawn(move || Self::work(&worker_disk, receiver, sender));
+
+        Self {
+            disk,
+            jobs: Some(jobs),
+            done,
+            worker: Some(worker),
+            writing: 0,
+            pending: HashMap::new(),
+            reading: HashSet::new(),
+            reads: HashMap::new(),
+            written: vec![],
+            error: None,
+        }
+    }
+
+    pub fn disk(&self) -> &Disk {
+        &self.disk
+    }
+
+    /// Submits buckets to be written to disk. This blocks only while the
+    /// queue is full.
+    pub fn write(&mut self, buckets: Vec<(usize, usize, VecDeque<Candidate>)>) {
+        self.writing += 1;
+
+        for (w, p, _) in &buckets {
+            *self.pending.entry((*w, *p)).or_insert(0) += 1;
+        }
+
+        self.submit(Job::Write(buckets));
+    }
+
+    /// Starts reading the next chunk of a bucket unless a read of it is
+    /// already in flight or waiting to be taken.
+    pub fn request(&mut self, wasted_symbols: usize, permutations: usize) {
+        let bucket_id = (wasted_symbols, permutations);
+
+        if self.requested(wasted_symbols, permutations) {
+            return;
+        }
+
+        self.reading.insert(bucket_id);
+        self.submit(Job::Read(bucket_id));
+    }
+
+    /// Whether a read of the bucket was requested and hasn't been taken.
+    pub fn requested(&self, wasted_symbols: usize, permutations: usize) -> bool {
+        let bucket_id = (wasted_symbols, permutations);
+        self.reading.contains(&bucket_id) || self.reads.contains_key(&bucket_id)
+    }
+
+    /// Whether the bucket has chunks on disk, on their way there or read but
+    /// not yet taken.
+    pub fn holds(&self, wasted_symbols: usize, permutations: usize) -> bool {
+        self.requested(wasted_symbols, permutations)
+            || self.pending.contains_key(&(wasted_symbols, permutations))
+            || self.disk.chunks(wasted_symbols, permutations) > 0
+    }
+
+    /// Returns the next chunk of a bucket, waiting for it to be read if it
+    /// was requested earlier or starting the read if it wasn't. A bucket that
+    /// has nothing on disk doesn't need to wait for the thread.
+    pub fn read(&mut self, wasted_symbols: usize, permutations: usize) -> Read {
+        if !self.holds(wasted_symbols, permutations) {
+            return Ok(None);
+        }
+
+        let bucket_id = (wasted_symbols, permutations);
+        self.request(wasted_symbols, permutations);
+
+        loop {
+            if let Some(read) = self.reads.remove(&bucket_id) {
+                return read;
+            }
+
+            self.wait();
+        }
+    }
+
+    /// Collects the jobs that have finished without waiting for any others
+    /// and returns the batches written since the last call.
+    pub fn poll(&mut self) -> Vec<Batch> {
+        while let Ok(done) = self.done.try_recv() {
+            self.finish(done);
+        }
+
+        self.written.drain(..).collect()
+    }
+
+    /// Waits for every job in flight and returns the batches written since
+    /// the last call, or the first error since the thread started.
+    pub fn flush(&mut self) -> Result<Vec<Batch>, String> {
+        while self.writing > 0 || !self.reading.is_empty() {
+            self.wait();
+        }
+
+        self.check()?;
+        Ok(self.written.drain(..).collect())
+    }
+
+    /// Takes the chunks that were read but haven't been asked for.
+    pub fn unclaimed(&mut self) -> Vec<(BucketID, Read)> {
+        let mut reads: Vec<_> = self.reads.drain().collect();
+        reads.sort_by_key(|r| r.0);
+        reads
+    }
+
+    /// Returns the first write that failed, if any.
+    pub fn check(&self) -> Result<(), String> {
+        match &self.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
+    fn submit(&mut self, job: Job) {
+        let jobs = self.jobs.as_ref().unwrap();
+
+        if jobs.send(job).is_err() {
+            self.error.get_or_insert_with(|| STOPPED.to_string());
+        }
+    }
+
+    fn wait(&mut self) {
+        match self.done.recv() {
+            Ok(done) => self.finish(done),
+            Err(_) => {
+                self.error.get_or_insert_with(|| STOPPED.to_string());
+                self.writing = 0;
+                self.pending.clear();
+
+                for bucket_id in self.reading.drain() {
+                    self.reads.insert(bucket_id, Err(STOPPED.to_string()));
+                }
+            },
+        }
+    }
+
+    fn finish(&mut self, done: Done) {
+        match done {
+            Done::Written(buckets, error) => {
+                self.writing -= 1;
+
+                for (w, p, _) in &buckets {
+                    if let Some(count) = self.pending.get_mut(&(*w, *p)) {
+                        *count -= 1;
+
+                        if *count == 0 {
+                            self.pending.remove(&(*w, *p));
+                        }
+                    }
+                }
+
+                self.written.push(buckets);
+
+                if let Some(error) = error {
+                    self.error.get_or_insert(error);
+                }
+            },
+            Done::Read(bucket_id, read) => {
+                self.reading.remove(&bucket_id);
+                self.reads.insert(bucket_id, read);
+            },
+        }
+    }
+
+    fn work(disk: &Disk, jobs: Receiver<Job>, done: Sender<Done>) {
+        for job in jobs {
+            let result = match job {
+                Job::Write(buckets) => {
+                    let written = buckets.iter().map(|(w, p, bucket)| (*w, *p, bucket.len())).collect();
+
+                    let error = buckets.into_par_iter()
+                        .map(|(w, p, bucket)| disk.write_chunks(bucket, w, p))
+                        .collect::<Result<(), String>>()
+                        .err();
+
+                    Done::Written(written, error)
+                },
+                Job::Read(bucket_id) => Done::Read(bucket_id, disk.read(bucket_id.0, bucket_id.1)),
+            };
+
+            if done.send(result).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl Drop for IoThread {
+    fn drop(&mut self) {
+        self.jobs = None;
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,139 @@
+use super::*;
+use super::super::codec::Codec;
+use super::super::disk::SPLIT_SIZE;
+
+use std::fs::remove_dir_all;
+
+type Subject = IoThread;
+
+const PATH: &str = "/tmp/superpermutation-test";
+
+fn subject(test_id: &'static str) -> Subject {
+    let path = format!("{}/{}", PATH, test_id);
+    let _ = remove_dir_all(&path);
+
+    Subject::new(Disk::new(path, 5, Codec::Raw, SPLIT_SIZE).unwrap())
+}
+
+fn bucket(len: usize) -> VecDeque<Candidate> {
+    (0..len).map(|_| Candidate::seed(5)).collect()
+}
+
+mod write {
+    use super::*;
+
+    #[test]
+    fn it_reports_each_batch_once_it_has_been_written() {
+        let mut subject = subject("io-1");
+
+        subject.write(vec![(3, 4, bucket(10)), (5, 6, bucket(20))]);
+        subject.write(vec![(3, 4, bucket(30))]);
+
+        let written = subject.flush().unwrap();
+
+        assert_eq!(written, vec![vec![(3, 4, 10), (5, 6, 20)], vec![(3, 4, 30)]]);
+        assert_eq!(subject.disk().chunks(3, 4), 2);
+        assert_eq!(subject.disk().chunks(5, 6), 1);
+        assert_eq!(subject.poll(), Vec::<Vec<_>>::new());
+    }
+
+    #[test]
+    fn it_keeps_the_first_error_for_the_search_to_find() {
+        let mut subject = subject("io-2");
+        remove_dir_all(format!("{}/io-2", PATH)).unwrap();
+
+        subject.write(vec![(3, 4, bucket(10))]);
+
+        let error = subject.flush().unwrap_err();
+
+        assert!(error.starts_with("Failed to write"));
+        assert_eq!(subject.check(), Err(error));
+    }
+}
+
+mod read {
+    use super::*;
+
+    #[test]
+    fn it_reads_a_bucket_after_the_writes_submitted_before_it() {
+        let mut subject = subject("io-3");
+
+        subject.write(vec![(3, 4, bucket(10))]);
+
+        assert_eq!(subject.read(3, 4).unwrap().unwrap().len(), 10);
+        assert_eq!(subject.read(3, 4), Ok(None));
+        assert_eq!(subject.poll(), vec![vec![(3, 4, 10)]]);
+    }
+
+    #[test]
+    fn it_takes_a_read_that_was_requested_earlier() {
+        let mut subject = subject("io-4");
+
+        subject.write(vec![(3, 4, bucket(10))]);
+        subject.request(3, 4);
+        subject.flush().unwrap();
+
+        assert_eq!(subject.disk().chunks(3, 4), 0);
+        assert_eq!(subject.read(3, 4).unwrap().unwrap().len(), 10);
+    }
+
+    #[test]
+    fn it_does_not_request_a_bucket_twice() {
+        let mut subject = subject("io-5");
+
+        subject.write(vec![(3, 4, bucket(10))]);
+        subject.write(vec![(3, 4, bucket(20))]);
+
+        subject.request(3, 4);
+        subject.request(3, 4);
+        subject.flush().unwrap();
+
+        assert_eq!(subject.disk().chunks(3, 4), 1);
+    }
+}
+
+mod holds {
+    use super::*;
+
+    #[test]
+    fn it_knows_whether_a_bucket_has_anything_on_disk() {
+        let mut subject = subject("io-7");
+
+        subject.write(vec![(3, 4, bucket(10))]);
+
+        assert!(subject.holds(3, 4));
+        assert!(!subject.holds(5, 6));
+
+        subject.flush().unwrap();
+        assert!(subject.holds(3, 4));
+
+        subject.request(3, 4);
+        subject.flush().unwrap();
+        assert!(subject.holds(3, 4));
+
+        subject.read(3, 4).unwrap();
+        assert!(!subject.holds(3, 4));
+        assert_eq!(subject.read(3, 4), Ok(None));
+    }
+}
+
+mod unclaimed {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_reads_that_have_not_been_taken() {
+        let mut subject = subject("io-6");
+
+        subject.write(vec![(3, 4, bucket(10)), (5, 6, bucket(20))]);
+        subject.request(5, 6);
+        subject.request(3, 4);
+        subject.flush().unwrap();
+
+        let unclaimed: Vec<_> = subject.unclaimed().into_iter()
+            .map(|(id, read)| (id, read.unwrap().unwrap().len()))
+            .collect();
+
+        assert_eq!(unclaimed, vec![((3, 4), 10), ((5, 6), 20)]);
+        assert_eq!(subject.unclaimed().len(), 0);
+    }
+}
@@ -0,0 +1,83 @@
+// The published maximum number of permutations for each number of wasted
+// symbols, for the values of n where every bound has been proven.
+const N3: [usize; 2] = [3, 6];
+const N4: [usize; 7] = [4, 8, 12, 14, 18, 20, 24];
+const N5: [usize; 30] = [
+    5, 10, 15, 20, 23, 28, 33, 36, 41, 46, 49, 53, 58, 62, 66,
+    70, 74, 79, 83, 87, 92, 96, 99, 103, 107, 111, 114, 116, 118, 120,
+];
+
+/// How a bound found by the search compares with the known value.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Comparison {
+    /// The lower bound reached the known maximum.
+    Matches,
+    /// The lower bound is more than the known maximum, which is impossible.
+    Beats { known: usize },
+    /// The upper bound is less than the known maximum, which is impossible.
+    Contradicts { known: usize },
+}
+
+/// A bound that the search compared with the known maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Mark {
+    pub waste: usize,
+    pub permutations: usize,
+    pub comparison: Comparison,
+}
+
+pub fn table(n: usize) -> Option<&'static [usize]> {
+    match n {
+        3 => Some(&N3),
+        4 => Some(&N4),
+        5 => Some(&N5),
+        _ => None,
+    }
+}
+
+/// Compares a new lower bound with the known maximum. A lower bound that is
+/// still below it isn't marked because the search may not have finished with
+/// that number of wasted symbols yet.
+pub fn compare_lower(n: usize, waste: usize, permutations: usize) -> Option<Comparison> {
+    let known = *table(n)?.get(waste)?;
+
+    match permutations {
+        p if p == known => Some(Comparison::Matches),
+        p if p > known => Some(Comparison::Beats { known }),
+        _ => None,
+    }
+}
+
+pub fn compare_upper(n: usize, waste: usize, permutations: usize) -> Option<Comparison> {
+    let known = *table(n)?.get(waste)?;
+
+    match permutations < known {
+        true => Some(Comparison::Contradicts { known }),
+        false => None,
+    }
+}
+
+impl Comparison {
+    pub fn is_contradiction(&self) -> bool {
+        *self != Comparison::Matches
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Comparison::Matches => "matches",
+            Comparison::Beats { .. } => "beats",
+            Comparison::Contradicts { .. } => "contradicts",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Comparison::Matches => "matches the known maximum".to_string(),
+            Comparison::Beats { known } => format!("beats the known maximum of {}", known),
+            Comparison::Contradicts { known } => format!("rules out the known maximum of {}", known),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,70 @@
+use super::*;
+
+mod table {
+    use super::*;
+
+    #[test]
+    fn it_has_the_known_bounds_for_three_to_five_symbols() {
+        assert_eq!(table(3), Some(&[3, 6][..]));
+        assert_eq!(table(4).map(|t| t.len()), Some(7));
+        assert_eq!(table(5).map(|t| t.len()), Some(30));
+        assert_eq!(table(6), None);
+    }
+
+    #[test]
+    fn it_ends_with_every_permutation() {
+        assert_eq!(table(3).unwrap().last(), Some(&6));
+        assert_eq!(table(4).unwrap().last(), Some(&24));
+        assert_eq!(table(5).unwrap().last(), Some(&120));
+    }
+}
+
+mod compare_lower {
+    use super::*;
+
+    #[test]
+    fn it_marks_a_lower_bound_that_matches_or_beats_the_known_maximum() {
+        assert_eq!(compare_lower(4, 3, 14), Some(Comparison::Matches));
+        assert_eq!(compare_lower(4, 3, 15), Some(Comparison::Beats { known: 14 }));
+    }
+
+    #[test]
+    fn it_does_not_mark_a_lower_bound_that_is_still_below_the_known_maximum() {
+        assert_eq!(compare_lower(4, 3, 13), None);
+    }
+
+    #[test]
+    fn it_does_not_mark_bounds_without_a_known_value() {
+        assert_eq!(compare_lower(4, 7, 24), None);
+        assert_eq!(compare_lower(6, 0, 6), None);
+    }
+}
+
+mod compare_upper {
+    use super::*;
+
+    #[test]
+    fn it_marks_an_upper_bound_that_rules_out_the_known_maximum() {
+        assert_eq!(compare_upper(5, 4, 22), Some(Comparison::Contradicts { known: 23 }));
+        assert_eq!(compare_upper(5, 4, 23), None);
+        assert_eq!(compare_upper(5, 4, 25), None);
+    }
+}
+
+mod comparison {
+    use super::*;
+
+    #[test]
+    fn it_treats_anything_but_a_match_as_a_contradiction() {
+        assert!(!Comparison::Matches.is_contradiction());
+        assert!(Comparison::Beats { known: 14 }.is_contradiction());
+        assert!(Comparison::Contradicts { known: 14 }.is_contradiction());
+    }
+
+    #[test]
+    fn it_describes_the_comparison() {
+        assert_eq!(Comparison::Matches.describe(), "matches the known maximum");
+        assert_eq!(Comparison::Beats { known: 14 }.describe(), "beats the known maximum of 14");
+        assert_eq!(Comparison::Contradicts { known: 14 }.describe(), "rules out the known maximum of 14");
+    }
+}
@@ -0,0 +1,55 @@
+//! Leaps and Bounds: a tool to find superpermutation bounds.
+//!
+//! The search finds the maximum number of permutations that fit into a string
+//! that wastes W symbols, for W = 0, 1, 2, ... until every permutation fits.
+//! See the README for an overview of how it works.
+//!
+//! ```no_run
+//! use leaps_and_bounds::{Config, Search};
+//!
+//! let config = Config { n: 4, memory: 1., ..Config::default() };
+//! let outcome = Search::new(config).unwrap().run();
+//!
+//! assert_eq!(outcome.superpermutation_length, Some(33));
+//! ```
+
+extern crate bit_set;
+extern crate bucket_queue;
+extern crate rayon;
+
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+extern crate serde_bytes;
+extern crate bincode;
+
+pub mod bounds;
+pub mod candidate;
+pub mod checkpoint;
+pub mod chunk;
+pub mod codec;
+pub mod config;
+pub mod console;
+pub mod disk;
+pub mod enumerate;
+pub mod events;
+pub mod frontier;
+pub mod interrupt;
+pub mod io_thread;
+pub mod known;
+pub mod observer;
+pub mod offload;
+pub mod proof;
+pub mod reference;
+pub mod search;
+pub mod status;
+pub mod stop;
+pub mod ui;
+pub mod verify;
+pub mod witness;
+
+pub use self::bounds::Bounds;
+pub use self::candidate::Candidate;
+pub use self::config::Config;
+pub use self::observer::{Observer, Snapshot};
+pub use self::search::{Outcome, Search, Statistics};
@@ -1,74 +1,380 @@
-extern crate bit_set;
-extern crate bucket_queue;
-extern crate rayon;
-
-#[macro_use]
-extern crate serde_derive;
-extern crate serde;
-extern crate serde_bytes;
-extern crate bincode;
-
-mod bounds;
-mod candidate;
-mod disk;
-mod frontier;
-mod ui;
-
-use self::bounds::Bounds;
-use self::candidate::Candidate;
-use self::frontier::Frontier;
-use self::ui::UI;
+mod cli;
 
+use self::cli::{Args, Command};
+
+use leaps_and_bounds::checkpoint::Checkpoint;
+use leaps_and_bounds::chunk;
+use leaps_and_bounds::codec::{Benchmark, Codec, DEFAULT_LEVEL};
+use leaps_and_bounds::config::{Config, Settings};
+use leaps_and_bounds::console::Console;
+use leaps_and_bounds::disk::Disk;
+use leaps_and_bounds::known;
+use leaps_and_bounds::proof::ProofLog;
+use leaps_and_bounds::reference::{compare, solve};
+use leaps_and_bounds::search::{Outcome, Search};
+use leaps_and_bounds::stop::Reason;
+use leaps_and_bounds::ui::UI;
+use leaps_and_bounds::verify::{verify, verify_bounds};
+use leaps_and_bounds::witness::Witnesses;
+
+use std::collections::VecDeque;
+use std::env;
+use std::path::Path;
 use std::process::exit;
 
 fn main() {
-    UI::print_introduction();
-    let n = UI::ask_for_n();
-    let memory = UI::ask_for_memory();
-    let gzip = UI::ask_for_gzip();
-    let verbose = UI::ask_for_verbose();
-    UI::print_running();
-
-    let candidate = Candidate::seed(n);
-    let mut frontier = Frontier::new(memory, gzip, verbose, n);
-    let mut bounds = Bounds::new(n);
-
-    frontier.add(candidate, n);
-
-    while let Some(mut wasted_symbols) = frontier.min_waste() {
-        wasted_symbols = frontier.unprune(
-            wasted_symbols,
-            &bounds.lower_bounds,
-            &bounds.upper_bounds,
-        );
-
-        let candidate = frontier.next().unwrap();
-        let permutations = candidate.number_of_permutations();
-
-        if bounds.update(wasted_symbols, permutations) {
-            let threshold = bounds.thresholds[wasted_symbols];
-            frontier.prune(wasted_symbols, threshold, true);
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}\n\n{}", error, Args::usage());
+        exit(2);
+    });
+
+    if args.command == Command::Help {
+        println!("{}", Args::usage());
+        exit(0);
+    }
+
+    if args.command == Command::Verify {
+        run_verify(args);
+    }
+
+    if args.command == Command::Selftest {
+        run_selftest(args);
+    }
+
+    if args.command == Command::Resume {
+        run_resume(args);
+    }
+
+    if args.command == Command::Inspect {
+        run_inspect(args);
+    }
+
+    if args.command == Command::Migrate {
+        run_migrate(args);
+    }
+
+    if args.command == Command::BenchCodecs {
+        run_bench_codecs(args);
+    }
+
+    let command = args.command;
+
+    let settings = match &args.config {
+        Some(path) => Settings::read(path).map(|file| args.settings.or(file)),
+        None => Ok(args.settings),
+    };
+
+    let settings = settings.unwrap_or_else(|errors| exit_with_errors(&errors));
+    keep_stdout_for_events(&settings.events);
+
+    if settings.banner != Some(false) { UI::print_introduction(); }
+    let mut config = Config::resolve(settings);
+
+    // The strings are collected from the candidates' paths.
+    if command == Command::Enumerate {
+        config.paths = true;
+    }
+
+    config.validate().unwrap_or_else(|errors| exit_with_errors(&errors));
+    if config.banner { UI::print_running(); }
+
+    let bounds_path = config.bounds.clone();
+
+    let mut search = Search::new(config).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(1);
+    });
+
+    if let Some(path) = bounds_path {
+        UI::print_seeded(&path, search.bounds());
+    }
+
+    if command == Command::Enumerate {
+        search.enumerate().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            exit(1);
+        });
+    }
+
+    run(search, command);
+}
+
+fn run(mut search: Search, command: Command) {
+    let verbose = search.config().verbose;
+    search.observe(Box::new(Console::new(verbose)));
+
+    search.interrupt().install();
+    let outcome = search.run();
+
+    // A bound that contradicts the known results fails the run.
+    let contradicted = !outcome.contradictions().is_empty();
+
+    match &outcome.stopped {
+        Some(Reason::MaxWaste(_)) if command == Command::Enumerate => UI::print_bounds(&outcome.bounds),
+        Some(reason) => {
+            UI::print_stopped(reason, &outcome);
+            UI::print_contradictions(&outcome);
+            exit(if contradicted && reason.exit_code() == 0 { 1 } else { reason.exit_code() });
+        },
+        None => UI::print_done(&outcome),
+    }
+
+    UI::print_contradictions(&outcome);
+
+    if command == Command::Enumerate {
+        run_enumerate(&outcome);
+    }
+
+    if contradicted {
+        exit(1);
+    }
+}
+
+// The events stream keeps stdout to itself so it can be parsed, and the rest of
+// the output goes to stderr.
+fn keep_stdout_for_events(events: &Option<String>) {
+    if events.as_deref() == Some("-") {
+        UI::use_stderr();
+    }
+}
+
+fn run_resume(args: Args) -> ! {
+    let settings = match &args.config {
+        Some(path) => Settings::read(path).map(|file| args.settings.or(file)),
+        None => Ok(args.settings),
+    };
+
+    let mut settings = settings.unwrap_or_else(|errors| exit_with_errors(&errors));
+    let scratch = settings.scratch.take().unwrap_or(Config::default().scratch);
+
+    let search = Search::resume(&scratch, settings).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(1);
+    });
+
+    keep_stdout_for_events(&search.config().events);
+    UI::print_resumed(&scratch, search.bounds());
+
+    run(search, Command::Resume);
+    exit(0);
+}
+
+fn run_inspect(args: Args) -> ! {
+    let (scratch, n, codec) = scratch_settings(args);
+
+    let scan = Disk::scan(&scratch, n, codec, true).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(2);
+    });
+
+    UI::print_scan(&scratch, &scan);
+    exit(if scan.is_clean() { 0 } else { 1 });
+}
+
+fn run_migrate(args: Args) -> ! {
+    let (scratch, n, codec) = scratch_settings(args);
+
+    let disk = Disk::open(scratch.clone(), n, codec, Config::default().split_size).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(2);
+    });
+
+    let migrated = disk.migrate().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(1);
+    });
+
+    UI::print_migrated(&scratch, &migrated);
+    exit(0);
+}
+
+fn run_bench_codecs(args: Args) -> ! {
+    let (scratch, n, codec) = scratch_settings(args);
+
+    let scan = Disk::scan(&scratch, n, codec, false).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(2);
+    });
+
+    let bucket = scan.buckets.iter().max_by_key(|b| b.bytes).unwrap_or_else(|| {
+        eprintln!("{} has no chunks to measure the codecs on (a search with a small --memory offloads some).", scratch);
+        exit(2);
+    });
+
+    // The chunks of a bucket are joined up to the size of a full chunk.
+    let basename = Disk::basename_in(&scratch, codec, bucket.waste, bucket.permutations);
+    let mut candidates = VecDeque::new();
+
+    for number in bucket.first..bucket.first + bucket.chunks {
+        let filename = format!("{}.{}", basename, number);
+
+        candidates.extend(chunk::load(&filename, n, codec).unwrap_or_else(|error| {
+            eprintln!("The chunk {} {}", filename, error);
+            exit(1);
+        }));
+
+        if candidates.len() >= Config::default().split_size {
+            break;
         }
+    }
+
+    UI::print_benchmarking(&basename, candidates.len());
+
+    let benchmarks: Vec<_> = Codec::all().into_iter().map(|codec| {
+        Benchmark::run(codec, &candidates, n, 3).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            exit(1);
+        })
+    }).collect();
+
+    UI::print_benchmarks(&benchmarks);
+    exit(0);
+}
+
+// The scratch directory, n and codec for the commands that read chunks. Unless
+// they are given as flags, n and the codec come from the checkpoint.
+fn scratch_settings(args: Args) -> (String, usize, Codec) {
+    let scratch = args.settings.scratch.unwrap_or(Config::default().scratch);
+
+    let saved = match Path::new(&Checkpoint::path(&scratch)).exists() {
+        true => Some(Checkpoint::load(&scratch).map(|(c, _)| c.config).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            exit(2);
+        })),
+        false => None,
+    };
+
+    let n = args.settings.n.or(saved.as_ref().map(|c| c.n)).unwrap_or_else(|| {
+        eprintln!("{} has no checkpoint to read n from, so it needs --n.\n\n{}", scratch, Args::usage());
+        exit(2);
+    });
+
+    let gzip = args.settings.gzip.map(|gzip| match gzip {
+        true => Codec::Zlib(DEFAULT_LEVEL),
+        false => Codec::Raw,
+    });
+
+    let codec = args.settings.codec.or(gzip).or(saved.map(|c| c.codec)).unwrap_or_default();
+
+    (scratch, n, codec)
+}
+
+fn run_enumerate(outcome: &Outcome) {
+    match &outcome.enumeration {
+        Some(Ok(enumeration)) => UI::print_enumeration(enumeration),
+        Some(Err(error)) => {
+            eprintln!("{}", error);
+            exit(1);
+        },
+        None => {},
+    }
+}
 
-        let upper_bound = bounds.upper(wasted_symbols);
-        for child in candidate.expand(upper_bound, n) {
-            frontier.add(child, n);
+fn run_verify(args: Args) -> ! {
+    if let Some(path) = &args.settings.proof {
+        let phases = ProofLog::check(path).unwrap_or_else(|errors| {
+            errors.iter().for_each(|e| eprintln!("{}", e));
+            eprintln!("\nThe proof log does not check out.");
+            exit(1);
+        });
+
+        UI::print_proof(&phases);
+        exit(0);
+    }
+
+    if let Some(path) = &args.settings.bounds {
+        let n = args.settings.n.unwrap_or_else(|| {
+            eprintln!("Verifying a bounds file needs --n.\n\n{}", Args::usage());
+            exit(2);
+        });
+
+        let checks = verify_bounds(path, n).unwrap_or_else(|errors| {
+            errors.iter().for_each(|e| eprintln!("{}", e));
+            exit(2);
+        });
+
+        UI::print_checks(&checks);
+        exit(if checks.iter().all(|c| c.passed()) { 0 } else { 1 });
+    }
+
+    let text = args.string.unwrap_or_else(|| {
+        eprintln!("Missing the string to verify.\n\n{}", Args::usage());
+        exit(2);
+    });
+
+    let string = Witnesses::parse(&text).unwrap_or_else(|| {
+        eprintln!("Invalid string '{}' (write the symbols as 1-9 and then a-k).", text);
+        exit(2);
+    });
+
+    let n = args.settings.n.unwrap_or_else(|| string.iter().max().map_or(2, |&s| s as usize + 1));
+
+    let report = verify(&string, n).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(2);
+    });
+
+    UI::print_report(&string, &report);
+    exit(0);
+}
+
+fn run_selftest(args: Args) -> ! {
+    let symbols = match args.settings.n {
+        Some(n) => vec![n],
+        None => (2..=4).collect(),
+    };
+
+    let mut passed = true;
+
+    for n in symbols {
+        let reference = solve(n).ok();
+        let known = known::table(n);
+
+        if reference.is_none() && known.is_none() {
+            eprintln!("There is nothing to check the search against for n = {} (the brute-force solver handles 2 to 4 and the known results cover 3 to 5).", n);
+            exit(2);
         }
 
-        if bounds.found_for_superpermutation() {
-            let waste = bounds.lower_bounds.len() - 1;
-            let factorial = Bounds::factorial(n);
-            let length = n - 1 + factorial + waste;
+        let config = Config {
+            n,
+            memory: args.settings.memory.unwrap_or(1.),
+            banner: false,
+            status: false,
+            scratch: args.settings.scratch.clone().unwrap_or(Config::default().scratch),
+            ..Config::default()
+        };
+
+        config.validate().unwrap_or_else(|errors| exit_with_errors(&errors));
+
+        let mut search = Search::new(config).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            exit(1);
+        });
 
-            println!("{} wasted symbols: at most {} permutations", waste, factorial);
-            println!();
-            println!("--->>> Done!");
-            println!();
-            println!("A maximum of {} wasted symbols can fit all {}! = {} permutations.", waste, n, factorial);
-            println!("The shortest superpermutation contains {} + {} + {} = {} symbols.", n - 1, factorial, waste, length);
-            println!();
+        search.observe(Box::new(Console::new(false)));
+        let outcome = search.run();
 
-            exit(0);
+        let tables = [("brute-force solver", reference.as_deref()), ("known results", known)];
+
+        for (source, table) in tables.iter().filter_map(|(s, t)| t.map(|t| (s, t))) {
+            let mismatch = compare(table, outcome.proven());
+
+            UI::print_selftest(n, source, table, &mismatch);
+            passed &= mismatch.is_none();
         }
+
+        passed &= outcome.contradictions().is_empty();
     }
+
+    exit(if passed { 0 } else { 1 });
+}
+
+fn exit_with_errors(errors: &[String]) -> ! {
+    eprintln!("The configuration is invalid:");
+
+    for error in errors {
+        eprintln!("  - {}", error);
+    }
+
+    exit(2);
 }
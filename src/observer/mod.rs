@@ -0,0 +1,96 @@
+use super::candidate::Candidate;
+use super::known::Comparison;
+use super::offload::Watermarks;
+
+/// The size of the frontier at the moment an observer is notified.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Snapshot {
+    pub min_waste: Option<usize>,
+    pub enabled: usize,
+    pub disabled: usize,
+    pub disabled_buckets: usize,
+    pub disk_bytes: u64,
+}
+
+/// Receives a callback for everything interesting that happens in a search.
+///
+/// Every method does nothing by default so implementations only need to
+/// override the ones they care about. Buckets are identified by the number of
+/// wasted symbols and permutations of the candidates in them.
+#[allow(unused_variables)]
+pub trait Observer {
+    /// A candidate wasting one more symbol than before has been found.
+    fn phase_started(&mut self, waste: usize, permutations: usize, snapshot: &Snapshot) { }
+
+    fn lower_bound_found(&mut self, waste: usize, permutations: usize, snapshot: &Snapshot) { }
+
+    fn upper_bound_tightened(&mut self, waste: usize, permutations: usize, snapshot: &Snapshot) { }
+
+    /// A bound was compared with the known maximum for its number of wasted symbols.
+    fn bound_compared(&mut self, waste: usize, permutations: usize, comparison: Comparison, snapshot: &Snapshot) { }
+
+    /// A string achieving a new lower bound, as symbols from 0 to n - 1.
+    fn witness_found(&mut self, waste: usize, permutations: usize, witness: &[u8], snapshot: &Snapshot) { }
+
+    fn bucket_pruned(&mut self, waste: usize, permutations: usize, snapshot: &Snapshot) { }
+
+    fn bucket_unpruned(&mut self, waste: usize, permutations: usize, from_disk: bool, snapshot: &Snapshot) { }
+
+    /// The search is about to start with this much room for candidates in
+    /// memory and the named offload policy.
+    fn offload_planned(&mut self, watermarks: &Watermarks, policy: &str, snapshot: &Snapshot) { }
+
+    /// The frontier is over its queue limit and is about to write buckets to disk.
+    fn offload_started(&mut self, snapshot: &Snapshot) { }
+
+    fn offloaded(&mut self, waste: usize, permutations: usize, candidates: usize, snapshot: &Snapshot) { }
+
+    fn offload_finished(&mut self, snapshot: &Snapshot) { }
+
+    /// Called before the candidate's children are added to the frontier.
+    fn candidate_expanded(&mut self, candidate: &Candidate, snapshot: &Snapshot) { }
+
+    fn search_completed(&mut self, waste: usize, permutations: usize, length: usize, snapshot: &Snapshot) { }
+
+    /// A checkpoint of `bytes` was saved in the scratch directory.
+    fn checkpoint_saved(&mut self, bytes: u64, snapshot: &Snapshot) { }
+
+    /// The search carries on from the previous checkpoint, if there is one.
+    fn checkpoint_failed(&mut self, error: &str, snapshot: &Snapshot) { }
+
+    /// Chunks in the scratch directory that the checkpoint being resumed from
+    /// doesn't reach were removed.
+    fn orphans_removed(&mut self, filenames: &[String], snapshot: &Snapshot) { }
+}
+
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<Box<dyn Observer>>,
+}
+
+impl Observers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    pub fn notify<F: FnMut(&mut dyn Observer)>(&mut self, mut f: F) {
+        for observer in self.observers.iter_mut() {
+            f(observer.as_mut());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,52 @@
+use super::*;
+
+type Subject = Observers;
+
+#[derive(Default)]
+struct Counter {
+    pruned: usize,
+}
+
+impl Observer for Counter {
+    fn bucket_pruned(&mut self, _: usize, _: usize, _: &Snapshot) {
+        self.pruned += 1;
+    }
+}
+
+mod notify {
+    use super::*;
+
+    #[test]
+    fn it_calls_the_function_for_every_observer() {
+        let mut subject = Subject::new();
+        let mut calls = 0;
+
+        subject.push(Box::new(Counter::default()));
+        subject.push(Box::new(Counter::default()));
+
+        subject.notify(|o| {
+            o.bucket_pruned(1, 2, &Snapshot::default());
+            calls += 1;
+        });
+
+        assert_eq!(subject.len(), 2);
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn it_does_nothing_for_methods_that_are_not_overridden() {
+        let mut subject = Subject::new();
+        subject.push(Box::new(Counter::default()));
+
+        subject.notify(|o| o.bucket_unpruned(1, 2, false, &Snapshot::default()));
+        subject.notify(|o| o.offload_started(&Snapshot::default()));
+    }
+
+    #[test]
+    fn it_does_nothing_when_there_are_no_observers() {
+        let mut subject = Subject::new();
+
+        subject.notify(|_| panic!("there are no observers"));
+        assert!(subject.is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+use std::cmp::Reverse;
+
+/// Which policy decides the buckets to write to disk when memory runs low.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Offload {
+    #[default]
+    Unlikely,
+    Largest,
+    Waste,
+}
+
+/// The share of memory that starts an offload when the frontier grows past
+/// it, and the share that the offload brings the frontier back down to.
+pub const HIGH_WATERMARK: f64 = 1.;
+pub const LOW_WATERMARK: f64 = 0.75;
+
+/// How many candidates fit in memory and where offloading starts and stops.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Watermarks {
+    pub bytes_per_candidate: usize,
+    pub queue_limit: usize,
+    pub high: usize,
+    pub low: usize,
+}
+
+/// A disabled bucket that is held in memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub waste: usize,
+    pub permutations: usize,
+    pub candidates: usize,
+}
+
+/// Decides the order in which disabled buckets are written to disk. The
+/// frontier offloads them from the front until it's under the low watermark.
+pub trait Policy: Send {
+    fn name(&self) -> &'static str;
+
+    /// Sorts the buckets so the ones to offload first come first. The bounds
+    /// are the ones that the search last unpruned with.
+    fn rank(&self, buckets: &mut [Bucket], lower_bounds: &[usize], upper_bounds: &[usize]);
+}
+
+/// Offloads the buckets that unprune is least likely to enable soon.
+pub struct Unlikely;
+
+/// Offloads the buckets with the most candidates first, so that the fewest
+/// buckets are written.
+pub struct Largest;
+
+/// Offloads the buckets with the most wasted symbols first, since the search
+/// gets to them last.
+pub struct Waste;
+
+/// How the frontier's buckets have moved between memory and disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Offloads {
+    pub policy: &'static str,
+    /// How many times memory ran low.
+    pub offloads: usize,
+    pub buckets: usize,
+    pub candidates: usize,
+    /// How many candidates were read back from disk.
+    pub onloaded: usize,
+}
+
+impl Offload {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().trim() {
+            "unlikely" => Some(Offload::Unlikely),
+            "largest" => Some(Offload::Largest),
+            "waste" => Some(Offload::Waste),
+            _ => None,
+        }
+    }
+
+    pub fn policy(&self) -> Box<dyn Policy> {
+        match self {
+            Offload::Unlikely => Box::new(Unlikely),
+            Offload::Largest => Box::new(Largest),
+            Offload::Waste => Box::new(Waste),
+        }
+    }
+}
+
+impl Unlikely {
+    /// How many permutations the bucket is short of what the last unprune
+    /// needed, or none if unprune can't reach it until a later phase.
+    pub fn shortfall(bucket: &Bucket, lower_bounds: &[usize], upper_bounds: &[usize]) -> Option<usize> {
+        let previous_waste = lower_bounds.len().checked_sub(1)?;
+
+        if bucket.waste == 0 || bucket.waste >= previous_waste {
+            return None;
+        }
+
+        let allowed_waste = previous_waste - bucket.waste;
+        let min = (lower_bounds[previous_waste] + 1).saturating_sub(upper_bounds[allowed_waste]);
+
+        Some(min.saturating_sub(bucket.permutations))
+    }
+}
+
+impl Policy for Unlikely {
+    fn name(&self) -> &'static str {
+        "unlikely"
+    }
+
+    // Buckets that can't be reached yet go first, those with the most waste
+    // first. The rest go by how far they fall short.
+    fn rank(&self, buckets: &mut [Bucket], lower_bounds: &[usize], upper_bounds: &[usize]) {
+        buckets.sort_by_key(|b| {
+            let shortfall = Self::shortfall(b, lower_bounds, upper_bounds).unwrap_or(usize::MAX);
+            (Reverse(shortfall), Reverse(b.waste), b.permutations)
+        });
+    }
+}
+
+impl Policy for Largest {
+    fn name(&self) -> &'static str {
+        "largest"
+    }
+
+    fn rank(&self, buckets: &mut [Bucket], _: &[usize], _: &[usize]) {
+        buckets.sort_by_key(|b| (Reverse(b.candidates), Reverse(b.waste), b.permutations));
+    }
+}
+
+impl Policy for Waste {
+    fn name(&self) -> &'static str {
+        "waste"
+    }
+
+    fn rank(&self, buckets: &mut [Bucket], _: &[usize], _: &[usize]) {
+        buckets.sort_by_key(|b| (Reverse(b.waste), b.permutations));
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,76 @@
+use super::*;
+
+const LOWER_BOUNDS: [usize; 4] = [4, 8, 12, 14];
+const UPPER_BOUNDS: [usize; 4] = [4, 8, 12, 16];
+
+fn bucket(waste: usize, permutations: usize, candidates: usize) -> Bucket {
+    Bucket { waste, permutations, candidates }
+}
+
+fn ranked(policy: &dyn Policy, mut buckets: Vec<Bucket>) -> Vec<(usize, usize)> {
+    policy.rank(&mut buckets, &LOWER_BOUNDS, &UPPER_BOUNDS);
+    buckets.iter().map(|b| (b.waste, b.permutations)).collect()
+}
+
+fn buckets() -> Vec<Bucket> {
+    vec![bucket(2, 5, 30), bucket(2, 7, 10), bucket(1, 2, 50), bucket(3, 1, 20), bucket(4, 2, 40), bucket(0, 3, 5)]
+}
+
+mod parse {
+    use super::*;
+
+    #[test]
+    fn it_parses_each_policy_by_name() {
+        assert_eq!(Offload::parse("unlikely"), Some(Offload::Unlikely));
+        assert_eq!(Offload::parse("Largest"), Some(Offload::Largest));
+        assert_eq!(Offload::parse(" waste "), Some(Offload::Waste));
+        assert_eq!(Offload::parse("random"), None);
+    }
+
+    #[test]
+    fn it_builds_the_policy_with_the_same_name() {
+        for (name, offload) in [("unlikely", Offload::Unlikely), ("largest", Offload::Largest), ("waste", Offload::Waste)] {
+            assert_eq!(offload.policy().name(), name);
+        }
+    }
+}
+
+mod unlikely {
+    use super::*;
+
+    #[test]
+    fn it_works_out_how_far_a_bucket_falls_short_of_being_unpruned() {
+        // Waste 2 needs 7 permutations and waste 1 needs 3 to be unpruned.
+        assert_eq!(Unlikely::shortfall(&bucket(2, 5, 1), &LOWER_BOUNDS, &UPPER_BOUNDS), Some(2));
+        assert_eq!(Unlikely::shortfall(&bucket(2, 9, 1), &LOWER_BOUNDS, &UPPER_BOUNDS), Some(0));
+        assert_eq!(Unlikely::shortfall(&bucket(1, 2, 1), &LOWER_BOUNDS, &UPPER_BOUNDS), Some(1));
+
+        // Unprune doesn't reach these until a later phase, if ever.
+        assert_eq!(Unlikely::shortfall(&bucket(0, 3, 1), &LOWER_BOUNDS, &UPPER_BOUNDS), None);
+        assert_eq!(Unlikely::shortfall(&bucket(3, 1, 1), &LOWER_BOUNDS, &UPPER_BOUNDS), None);
+        assert_eq!(Unlikely::shortfall(&bucket(2, 5, 1), &[], &[]), None);
+    }
+
+    #[test]
+    fn it_offloads_the_buckets_that_unprune_is_furthest_from_first() {
+        assert_eq!(ranked(&Unlikely, buckets()), vec![(4, 2), (3, 1), (0, 3), (2, 5), (1, 2), (2, 7)]);
+    }
+}
+
+mod largest {
+    use super::*;
+
+    #[test]
+    fn it_offloads_the_buckets_with_the_most_candidates_first() {
+        assert_eq!(ranked(&Largest, buckets()), vec![(1, 2), (4, 2), (2, 5), (3, 1), (2, 7), (0, 3)]);
+    }
+}
+
+mod waste {
+    use super::*;
+
+    #[test]
+    fn it_offloads_the_buckets_with_the_most_waste_first() {
+        assert_eq!(ranked(&Waste, buckets()), vec![(4, 2), (3, 1), (2, 5), (2, 7), (1, 2), (0, 3)]);
+    }
+}
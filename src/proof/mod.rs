@@ -0,0 +1,283 @@
+use super::checkpoint;
+
+use std::collections::HashSet;
+use std::fs::{read_to_string, File};
+use std::io::{BufWriter, Write};
+
+/// A line of the proof log.
+///
+/// The search writes one of these for every bound it finds, every prune and
+/// unprune decision and every phase it finishes, so that `ProofLog::check` can
+/// replay the run and confirm each upper bound without trusting the frontier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum Record {
+    /// The search started with n symbols, so it can't fit more than n! permutations.
+    Started { n: usize, permutations: usize },
+    /// The lower bound for a number of wasted symbols went up. The upper bound
+    /// is the one the search derived from the other bounds at the time.
+    Bound { waste: usize, permutations: usize, upper_bound: usize, seeded: bool },
+    /// Every bucket from `waste` to `max_waste` wasted symbols with fewer than
+    /// `threshold` permutations was disabled.
+    Prune { waste: usize, max_waste: usize, threshold: usize },
+    /// A disabled bucket was enabled again because adding the remaining waste
+    /// of the phase to it might beat the phase's lower bound. A bucket that is
+    /// read from disk stays disabled until all of its chunks have been read.
+    Unprune {
+        waste: usize,
+        permutations: usize,
+        phase: usize,
+        lower_bound: usize,
+        max_permutations: usize,
+        from_disk: bool,
+        candidates: usize,
+    },
+    /// The search is about to expand a candidate with more wasted symbols, so
+    /// the lower bound for `waste` is final. Lists the pruned buckets that still
+    /// had candidates in memory or on disk at the time.
+    PhaseFinished { waste: usize, permutations: usize, seeded: bool, claimed: bool, pruned: Vec<Pruned> },
+    /// A superpermutation was found, which needs no upper bound.
+    Completed { waste: usize, permutations: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pruned {
+    pub waste: usize,
+    pub permutations: usize,
+    pub candidates: usize,
+    pub chunks: usize,
+}
+
+/// What replaying the proof log established about one number of wasted symbols.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    pub waste: usize,
+    pub permutations: usize,
+    /// The bound came from a bounds file so its pruned buckets weren't checked.
+    pub seeded: bool,
+    /// The bound was claimed in a bounds file and the search never matched it.
+    pub claimed: bool,
+    /// How many pruned buckets with candidates in them were ruled out.
+    pub ruled_out: usize,
+    pub completed: bool,
+}
+
+pub struct ProofLog {
+    writer: Option<Box<dyn Write + Send>>,
+}
+
+#[derive(Default)]
+struct Replay {
+    max: Option<usize>,
+    lower_bounds: Vec<usize>,
+    finished: Vec<usize>,
+    disabled: HashSet<(usize, usize)>,
+    phases: Vec<Phase>,
+    errors: Vec<String>,
+}
+
+impl ProofLog {
+    pub fn none() -> Self {
+        Self { writer: None }
+    }
+
+    pub fn open(path: &str) -> Self {
+        let file = File::create(path).unwrap_or_else(|_| panic!("Failed to create {}", path));
+        Self::to(Box::new(BufWriter::new(file)))
+    }
+
+    pub fn append(path: &str) -> Self {
+        Self::to(Box::new(BufWriter::new(checkpoint::append(path))))
+    }
+
+    pub fn to(writer: Box<dyn Write + Send>) -> Self {
+        Self { writer: Some(writer) }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    pub fn record(&mut self, record: &Record) {
+        let writer = match &mut self.writer {
+            None => return,
+            Some(writer) => writer,
+        };
+
+        serde_json::to_writer(&mut *writer, record).expect("Failed to write to the proof log.");
+        writeln!(writer).expect("Failed to write to the proof log.");
+        writer.flush().expect("Failed to flush the proof log.");
+    }
+
+    /// Replays a proof log and returns the phases it proves.
+    pub fn check(path: &str) -> Result<Vec<Phase>, Vec<String>> {
+        let text = read_to_string(path)
+            .map_err(|e| vec![format!("{}: {}", path, e)])?;
+
+        Self::replay(&text).map_err(|errors| {
+            errors.into_iter().map(|e| format!("{}: {}", path, e)).collect()
+        })
+    }
+
+    pub fn replay(text: &str) -> Result<Vec<Phase>, Vec<String>> {
+        let mut replay = Replay::default();
+
+        for (number, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let result = serde_json::from_str(line)
+                .map_err(|e| format!("invalid record ({})", e))
+                .and_then(|record| replay.apply(record));
+
+            if let Err(error) = result {
+                replay.errors.push(format!("line {}: {}", number + 1, error));
+            }
+        }
+
+        if replay.max.is_none() && replay.errors.is_empty() {
+            replay.errors.push("the log has no started record".to_string());
+        }
+
+        match replay.errors.is_empty() {
+            true => Ok(replay.phases),
+            false => Err(replay.errors),
+        }
+    }
+}
+
+impl Replay {
+    fn apply(&mut self, record: Record) -> Result<(), String> {
+        let max = match (&record, self.max) {
+            (Record::Started { permutations, .. }, None) => { self.max = Some(*permutations); return Ok(()) },
+            (Record::Started { .. }, Some(_)) => return Err("the search started twice".to_string()),
+            (_, None) => return Err("expected a started record first".to_string()),
+            (_, Some(max)) => max,
+        };
+
+        match record {
+            Record::Started { .. } => unreachable!(),
+            Record::Bound { waste, permutations, upper_bound, seeded } => self.bound(waste, permutations, upper_bound, seeded, max),
+            Record::Prune { waste, max_waste, threshold } => self.prune(waste, max_waste, threshold),
+            Record::Unprune { waste, permutations, from_disk, .. } => self.unprune(waste, permutations, from_disk),
+            Record::PhaseFinished { waste, permutations, seeded, claimed, pruned } => self.finish(waste, permutations, seeded, claimed, &pruned, max),
+            Record::Completed { waste, permutations } => self.complete(waste, permutations, max),
+        }
+    }
+
+    fn bound(&mut self, waste: usize, permutations: usize, upper_bound: usize, seeded: bool, max: usize) -> Result<(), String> {
+        if let Some(&finished) = self.finished.get(waste) {
+            return Err(format!("the bound for {} wasted symbols went up to {} after its phase finished at {}", waste, permutations, finished));
+        }
+
+        if let Some(&previous) = self.lower_bounds.get(waste).filter(|&&b| b > permutations) {
+            return Err(format!("the bound for {} wasted symbols went down from {} to {}", waste, previous, permutations));
+        }
+
+        if self.lower_bounds.len() <= waste {
+            self.lower_bounds.resize(waste + 1, 0);
+        }
+
+        self.lower_bounds[waste] = permutations;
+
+        // The upper bound can only be rederived once every phase before it has finished.
+        if seeded || waste == 0 || waste != self.finished.len() {
+            return Ok(());
+        }
+
+        let expected = (0..waste)
+            .map(|w| self.finished[w] + self.finished[waste - w - 1])
+            .fold(max, |a, b| a.min(b));
+
+        if upper_bound != expected {
+            return Err(format!("the upper bound for {} wasted symbols is {} but the finished phases give {}", waste, upper_bound, expected));
+        }
+
+        Ok(())
+    }
+
+    fn prune(&mut self, waste: usize, max_waste: usize, threshold: usize) -> Result<(), String> {
+        let lower_bound = self.lower_bounds.get(waste).ok_or_else(|| {
+            format!("pruned at {} wasted symbols before it had a bound", waste)
+        })?;
+
+        let allowed = lower_bound.saturating_sub(self.lower_bounds[0]);
+
+        for w in waste..=max_waste {
+            for p in 0..threshold {
+                self.disabled.insert((w, p));
+            }
+        }
+
+        if threshold > allowed {
+            return Err(format!("pruned below {} permutations at {} wasted symbols but its bound of {} only allows {}", threshold, waste, lower_bound, allowed));
+        }
+
+        Ok(())
+    }
+
+    fn unprune(&mut self, waste: usize, permutations: usize, from_disk: bool) -> Result<(), String> {
+        if !self.disabled.contains(&(waste, permutations)) {
+            return Err(format!("unpruned bucket ({}, {}) which was not pruned", waste, permutations));
+        }
+
+        if !from_disk {
+            self.disabled.remove(&(waste, permutations));
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self, waste: usize, permutations: usize, seeded: bool, claimed: bool, pruned: &[Pruned], max: usize) -> Result<(), String> {
+        if waste != self.finished.len() {
+            return Err(format!("expected the phase for {} wasted symbols to finish next (got {})", self.finished.len(), waste));
+        }
+
+        if self.lower_bounds.get(waste) != Some(&permutations) {
+            return Err(format!("the phase for {} wasted symbols finished at {} permutations but its bound is {:?}", waste, permutations, self.lower_bounds.get(waste)));
+        }
+
+        let mut ruled_out = 0;
+
+        for bucket in pruned.iter().filter(|b| b.waste <= waste && b.candidates + b.chunks > 0) {
+            let id = (bucket.waste, bucket.permutations);
+
+            if !self.disabled.contains(&id) {
+                return Err(format!("bucket ({}, {}) is pruned but the log never pruned it", id.0, id.1));
+            }
+
+            if seeded {
+                continue;
+            }
+
+            // The rest of the phase's waste can't add more permutations than
+            // the finished phase for that much waste.
+            let remaining = self.finished.get(waste - bucket.waste).copied().unwrap_or(max);
+            let reachable = bucket.permutations + remaining;
+
+            if reachable > permutations {
+                return Err(format!(
+                    "bucket ({}, {}) was left unexplored with {} candidates and {} chunks on disk but could reach {} permutations with {} wasted symbols (the bound is {})",
+                    id.0, id.1, bucket.candidates, bucket.chunks, reachable, waste, permutations,
+                ));
+            }
+
+            ruled_out += 1;
+        }
+
+        self.finished.push(permutations);
+        self.phases.push(Phase { waste, permutations, seeded, claimed, ruled_out, completed: false });
+
+        Ok(())
+    }
+
+    fn complete(&mut self, waste: usize, permutations: usize, max: usize) -> Result<(), String> {
+        if permutations != max {
+            return Err(format!("completed with {} permutations instead of {}", permutations, max));
+        }
+
+        self.phases.push(Phase { waste, permutations, seeded: false, claimed: false, ruled_out: 0, completed: true });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
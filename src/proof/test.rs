@@ -0,0 +1,161 @@
+use super::*;
+use std::sync::{Arc, Mutex};
+
+type Subject = ProofLog;
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> ::std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// A made up search for four symbols that prunes one bucket and finishes the
+// phases for zero and one wasted symbols.
+const LOG: &str = r#"
+{"record":"started","n":4,"permutations":24}
+{"record":"bound","waste":0,"permutations":4,"upper_bound":24,"seeded":false}
+{"record":"phase_finished","waste":0,"permutations":4,"seeded":false,"claimed":false,"pruned":[]}
+{"record":"bound","waste":1,"permutations":8,"upper_bound":8,"seeded":false}
+{"record":"prune","waste":1,"max_waste":2,"threshold":4}
+{"record":"phase_finished","waste":1,"permutations":8,"seeded":false,"claimed":false,"pruned":[{"waste":1,"permutations":3,"candidates":1,"chunks":0}]}
+"#;
+
+// The bucket that was pruned for one wasted symbol could reach 3 + 8 = 11
+// permutations with one more wasted symbol, which beats this phase's bound.
+const PHASE_2: [&str; 3] = [
+    r#"{"record":"bound","waste":2,"permutations":10,"upper_bound":12,"seeded":false}"#,
+    r#"{"record":"prune","waste":2,"max_waste":2,"threshold":6}"#,
+    r#"{"record":"phase_finished","waste":2,"permutations":10,"seeded":false,"claimed":false,"pruned":[{"waste":1,"permutations":3,"candidates":3,"chunks":2}]}"#,
+];
+
+fn with(lines: &[&str]) -> String {
+    format!("{}{}\n", LOG, lines.join("\n"))
+}
+
+mod record {
+    use super::*;
+
+    #[test]
+    fn it_writes_one_json_object_per_line() {
+        let buffer = Buffer::default();
+        let mut subject = Subject::to(Box::new(buffer.clone()));
+
+        subject.record(&Record::Started { n: 4, permutations: 24 });
+        subject.record(&Record::Prune { waste: 1, max_waste: 2, threshold: 4 });
+
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+
+        assert_eq!(text, concat!(
+            r#"{"record":"started","n":4,"permutations":24}"#, "\n",
+            r#"{"record":"prune","waste":1,"max_waste":2,"threshold":4}"#, "\n",
+        ));
+    }
+
+    #[test]
+    fn it_does_nothing_without_a_writer() {
+        let mut subject = Subject::none();
+
+        subject.record(&Record::Started { n: 4, permutations: 24 });
+        assert!(!subject.enabled());
+    }
+}
+
+mod replay {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_phases_that_the_log_proves() {
+        let phases = Subject::replay(LOG).unwrap();
+
+        assert_eq!(phases, vec![
+            Phase { waste: 0, permutations: 4, seeded: false, claimed: false, ruled_out: 0, completed: false },
+            Phase { waste: 1, permutations: 8, seeded: false, claimed: false, ruled_out: 1, completed: false },
+        ]);
+    }
+
+    #[test]
+    fn it_fails_if_an_unexplored_bucket_could_reach_more_permutations_than_the_bound() {
+        let errors = Subject::replay(&with(&PHASE_2)).unwrap_err();
+
+        assert_eq!(errors, vec![
+            "line 10: bucket (1, 3) was left unexplored with 3 candidates and 2 chunks on disk but could reach 11 permutations with 2 wasted symbols (the bound is 10)",
+        ]);
+    }
+
+    #[test]
+    fn it_fails_if_the_frontier_has_a_pruned_bucket_that_the_log_never_pruned() {
+        let text = with(&PHASE_2).replace(r#""waste":1,"permutations":3,"candidates":3"#, r#""waste":1,"permutations":5,"candidates":3"#);
+
+        let errors = Subject::replay(&text).unwrap_err();
+        assert_eq!(errors, vec!["line 10: bucket (1, 5) is pruned but the log never pruned it"]);
+    }
+
+    #[test]
+    fn it_fails_if_an_upper_bound_does_not_follow_from_the_finished_phases() {
+        let text = LOG.replace(r#""waste":1,"permutations":8,"upper_bound":8"#, r#""waste":1,"permutations":8,"upper_bound":9"#);
+
+        let errors = Subject::replay(&text).unwrap_err();
+        assert_eq!(errors, vec!["line 5: the upper bound for 1 wasted symbols is 9 but the finished phases give 8"]);
+    }
+
+    #[test]
+    fn it_fails_if_a_bucket_is_unpruned_that_was_not_pruned() {
+        let text = with(&[
+            r#"{"record":"unprune","waste":1,"permutations":3,"phase":2,"lower_bound":9,"max_permutations":8,"from_disk":false,"candidates":1}"#,
+            r#"{"record":"unprune","waste":1,"permutations":3,"phase":2,"lower_bound":9,"max_permutations":8,"from_disk":false,"candidates":0}"#,
+        ]);
+
+        let errors = Subject::replay(&text).unwrap_err();
+        assert_eq!(errors, vec!["line 9: unpruned bucket (1, 3) which was not pruned"]);
+    }
+
+    #[test]
+    fn it_fails_if_a_bound_changes_after_its_phase_finished() {
+        let text = with(&[r#"{"record":"bound","waste":1,"permutations":9,"upper_bound":8,"seeded":false}"#]);
+
+        let errors = Subject::replay(&text).unwrap_err();
+        assert_eq!(errors, vec!["line 8: the bound for 1 wasted symbols went up to 9 after its phase finished at 8"]);
+    }
+
+    #[test]
+    fn it_fails_if_a_threshold_is_above_the_bound() {
+        let text = LOG.replace(r#""threshold":4"#, r#""threshold":5"#);
+
+        let errors = Subject::replay(&text).unwrap_err();
+        assert_eq!(errors, vec!["line 6: pruned below 5 permutations at 1 wasted symbols but its bound of 8 only allows 4"]);
+    }
+
+    #[test]
+    fn it_does_not_check_the_pruned_buckets_of_seeded_phases() {
+        let text = with(&PHASE_2).replace(r#""waste":2,"permutations":10,"seeded":false"#, r#""waste":2,"permutations":10,"seeded":true"#);
+
+        let phases = Subject::replay(&text).unwrap();
+
+        assert!(phases[2].seeded);
+        assert_eq!(phases[2].ruled_out, 0);
+    }
+
+    #[test]
+    fn it_needs_a_started_record_first() {
+        let errors = Subject::replay(&LOG.replace(r#"{"record":"started","n":4,"permutations":24}"#, "")).unwrap_err();
+        assert_eq!(errors[0], "line 3: expected a started record first");
+
+        let errors = Subject::replay("").unwrap_err();
+        assert_eq!(errors, vec!["the log has no started record"]);
+    }
+
+    #[test]
+    fn it_reports_lines_that_are_not_records() {
+        let errors = Subject::replay(&with(&["not json"])).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("line 8: invalid record"));
+    }
+}
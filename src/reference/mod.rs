@@ -0,0 +1,100 @@
+const MAX_N: usize = 4;
+
+/// Where the reference solver and the search first disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub waste: usize,
+    pub reference: Option<usize>,
+    pub search: Option<usize>,
+}
+
+struct Solver {
+    n: usize,
+    max_waste: usize,
+    string: Vec<u8>,
+    seen: Vec<Vec<u8>>,
+    best: Vec<usize>,
+}
+
+/// Finds the maximum number of permutations for every number of wasted
+/// symbols by trying every string, without any of the search's bounds.
+///
+/// Every string that wastes at most `max_waste` symbols is tried for
+/// increasing values of `max_waste` until one of them contains all n!
+/// permutations. Strings are only tried if they start with the first
+/// permutation, which loses nothing: leading symbols that don't complete a
+/// permutation are wasted and can be dropped, and then the symbols can be
+/// relabeled. This is far too slow for n above 4.
+pub fn solve(n: usize) -> Result<Vec<usize>, String> {
+    if !(2..=MAX_N).contains(&n) {
+        return Err(format!("the reference solver needs n between 2 and {} (got {})", MAX_N, n));
+    }
+
+    let factorial: usize = (1..=n).product();
+
+    for max_waste in 0.. {
+        let first: Vec<u8> = (0..n as u8).collect();
+
+        let mut solver = Solver {
+            n,
+            max_waste,
+            string: first.clone(),
+            seen: vec![first],
+            best: vec![0; max_waste + 1],
+        };
+
+        solver.extend(0);
+
+        if solver.best[max_waste] == factorial {
+            return Ok(solver.best);
+        }
+    }
+
+    unreachable!()
+}
+
+/// Compares two bounds tables and returns the first number of wasted symbols
+/// where they differ, including where one table is longer than the other.
+pub fn compare(reference: &[usize], search: &[usize]) -> Option<Mismatch> {
+    let len = reference.len().max(search.len());
+
+    (0..len).find(|&w| reference.get(w) != search.get(w)).map(|waste| Mismatch {
+        waste,
+        reference: reference.get(waste).copied(),
+        search: search.get(waste).copied(),
+    })
+}
+
+impl Solver {
+    fn extend(&mut self, wasted: usize) {
+        let permutations = self.seen.len();
+
+        for waste in wasted..=self.max_waste {
+            self.best[waste] = self.best[waste].max(permutations);
+        }
+
+        for symbol in 0..self.n as u8 {
+            self.string.push(symbol);
+
+            let window = self.string[self.string.len() - self.n..].to_vec();
+            let new = Self::is_permutation(&window) && !self.seen.contains(&window);
+
+            if new {
+                self.seen.push(window);
+                self.extend(wasted);
+                self.seen.pop();
+            } else if wasted < self.max_waste {
+                self.extend(wasted + 1);
+            }
+
+            self.string.pop();
+        }
+    }
+
+    fn is_permutation(window: &[u8]) -> bool {
+        (0..window.len() as u8).all(|symbol| window.contains(&symbol))
+    }
+}
+
+#[cfg(test)]
+mod test;
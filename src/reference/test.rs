@@ -0,0 +1,39 @@
+use super::*;
+
+mod solve {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_bounds_for_every_number_of_wasted_symbols() {
+        assert_eq!(solve(2), Ok(vec![2]));
+        assert_eq!(solve(3), Ok(vec![3, 6]));
+        assert_eq!(solve(4), Ok(vec![4, 8, 12, 14, 18, 20, 24]));
+    }
+
+    #[test]
+    fn it_only_handles_small_numbers_of_symbols() {
+        assert_eq!(solve(1), Err("the reference solver needs n between 2 and 4 (got 1)".to_string()));
+        assert_eq!(solve(5), Err("the reference solver needs n between 2 and 4 (got 5)".to_string()));
+    }
+}
+
+mod compare {
+    use super::*;
+
+    #[test]
+    fn it_returns_none_if_the_tables_are_the_same() {
+        assert_eq!(compare(&[3, 6], &[3, 6]), None);
+    }
+
+    #[test]
+    fn it_returns_the_first_number_of_wasted_symbols_where_the_tables_differ() {
+        let mismatch = compare(&[4, 8, 12, 14], &[4, 8, 13, 15]);
+        assert_eq!(mismatch, Some(Mismatch { waste: 2, reference: Some(12), search: Some(13) }));
+    }
+
+    #[test]
+    fn it_returns_where_one_table_stops() {
+        let mismatch = compare(&[4, 8, 12], &[4, 8]);
+        assert_eq!(mismatch, Some(Mismatch { waste: 2, reference: Some(12), search: None }));
+    }
+}
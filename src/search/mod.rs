@@ -0,0 +1,622 @@
+use super::bounds::Bounds;
+use super::candidate::Candidate;
+use super::checkpoint::{Checkpoint, REQUEST};
+use super::config::{Config, Prune, Settings};
+use super::enumerate::{Collector, Enumeration};
+use super::frontier::{Frontier, Prefetches};
+use super::interrupt::Interrupt;
+use super::known::{self, Comparison, Mark};
+use super::observer::Observer;
+use super::offload::Offloads;
+use super::proof::Record;
+use super::status::Status;
+use super::stop::Reason;
+use super::ui::UI;
+
+use std::cmp::min;
+use std::fs::remove_file;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// How often to look for a checkpoint request in the scratch directory.
+const POLL: Duration = Duration::from_secs(1);
+
+/// Runs the best-first search for a single configuration.
+///
+/// This owns the bounds, the frontier of candidates and the scratch directory
+/// for the duration of the run. Call `run` to search until the shortest
+/// superpermutation is found or one of the stop conditions fires. Nothing is
+/// printed; progress can be followed by adding an `Observer` such as `Console`.
+///
+/// With `checkpoint_every` set, the search saves a checkpoint that `resume`
+/// can carry on from at that interval, when asked to and when it stops early.
+pub struct Search {
+    config: Config,
+    bounds: Bounds,
+    frontier: Frontier,
+    interrupt: Interrupt,
+    expansions: usize,
+    pruned_seeds: usize,
+    phases_finished: usize,
+    witnesses: Vec<Option<Vec<u8>>>,
+    marks: Vec<Mark>,
+    enumeration: Option<Collector>,
+    started_at: Instant,
+    resumed: bool,
+    expansions_before: usize,
+    elapsed_before: Duration,
+    checkpointed_at: Instant,
+    polled_at: Instant,
+}
+
+/// What the search found and how much work it took to find it.
+#[derive(Debug)]
+pub struct Outcome {
+    pub n: usize,
+    /// The lower and upper bounds on permutations for each number of wasted symbols.
+    pub bounds: Bounds,
+    /// The length of the shortest superpermutation, if the search got that far.
+    pub superpermutation_length: Option<usize>,
+    /// The shortest superpermutation, as symbols from 0 to n - 1, unless the
+    /// search didn't keep paths.
+    pub superpermutation: Option<Vec<u8>>,
+    /// A string that achieves the lower bound for each number of wasted
+    /// symbols. This is empty if the search didn't keep paths.
+    pub witnesses: Vec<Option<Vec<u8>>>,
+    /// The bounds that were compared with the known maximums for n.
+    pub marks: Vec<Mark>,
+    /// Every string that achieves the bound the search enumerated, or why it
+    /// couldn't, if it was asked to enumerate them.
+    pub enumeration: Option<Result<Enumeration, String>>,
+    /// Why the search stopped early, if it did.
+    pub stopped: Option<Reason>,
+    pub statistics: Statistics,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    pub expansions: usize,
+    pub elapsed: Duration,
+    pub enabled: usize,
+    pub disabled: usize,
+    pub disabled_buckets: usize,
+    pub disk_bytes: u64,
+    pub prefetches: Prefetches,
+    pub offloads: Offloads,
+}
+
+impl Search {
+    /// Prepares a search, which creates and locks the scratch directory and
+    /// reads the bounds file if there is one.
+    pub fn new(config: Config) -> Result<Self, String> {
+        let bounds = match &config.bounds {
+            Some(path) => Bounds::read(path, config.n).map_err(|errors| errors.join("\n"))?,
+            None => Bounds::new(config.n),
+        };
+
+        let mut frontier = Frontier::new(&config)?;
+
+        if config.status && UI::terminal() {
+            frontier.observe(Box::new(Status::new(true)));
+        }
+
+        Ok(Self {
+            config,
+            bounds,
+            frontier,
+            interrupt: Interrupt::new(),
+            expansions: 0,
+            pruned_seeds: 0,
+            phases_finished: 0,
+            witnesses: vec![],
+            marks: vec![],
+            enumeration: None,
+            started_at: Instant::now(),
+            resumed: false,
+            expansions_before: 0,
+            elapsed_before: Duration::from_secs(0),
+            checkpointed_at: Instant::now(),
+            polled_at: Instant::now(),
+        })
+    }
+
+    /// Carries on from the checkpoint in a scratch directory. The settings
+    /// can change how the search reports and when it stops, but not what it
+    /// searches for.
+    pub fn resume(scratch: &str, settings: Settings) -> Result<Self, String> {
+        let (checkpoint, mut reader) = Checkpoint::load(scratch)?;
+        let events = checkpoint.config.events.clone();
+
+        let config = Config { scratch: scratch.to_string(), ..checkpoint.config }
+            .resumed(settings)
+            .map_err(|errors| errors.join("\n"))?;
+
+        Checkpoint::truncate(&config.witnesses, checkpoint.witnesses_len)?;
+        Checkpoint::truncate(&config.proof, checkpoint.proof_len)?;
+
+        // Events sent somewhere else when resuming start afresh there.
+        if config.events == events {
+            Checkpoint::truncate(&config.events, checkpoint.events_len)?;
+        }
+
+        let mut frontier = Frontier::resume(&config, &mut reader)?;
+
+        if config.status && UI::terminal() {
+            frontier.observe(Box::new(Status::new(true)));
+        }
+
+        Ok(Self {
+            config,
+            bounds: checkpoint.bounds,
+            frontier,
+            interrupt: Interrupt::new(),
+            expansions: checkpoint.expansions,
+            pruned_seeds: checkpoint.pruned_seeds,
+            phases_finished: checkpoint.phases_finished,
+            witnesses: checkpoint.witnesses,
+            marks: checkpoint.marks,
+            enumeration: None,
+            started_at: Instant::now(),
+            resumed: true,
+            expansions_before: checkpoint.expansions,
+            elapsed_before: checkpoint.elapsed,
+            checkpointed_at: Instant::now(),
+            polled_at: Instant::now(),
+        })
+    }
+
+    /// Returns a handle that stops the search at the next candidate boundary.
+    pub fn interrupt(&self) -> Interrupt {
+        self.interrupt.clone()
+    }
+
+    /// The settings the search runs with.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The bounds the search starts from, or has found so far.
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    /// Collects every string that achieves the bound for the stop's maximum
+    /// waste, or every shortest superpermutation without one. The phase for
+    /// that waste also expands the pruned buckets that could only tie its
+    /// bound, and the search doesn't stop at the first superpermutation.
+    pub fn enumerate(&mut self) -> Result<(), String> {
+        if !self.config.paths {
+            return Err("enumerating needs the search to keep paths".to_string());
+        }
+
+        self.enumeration = Some(Collector::default());
+        Ok(())
+    }
+
+    /// Adds an observer that is notified of bounds, buckets and expansions.
+    pub fn observe(&mut self, observer: Box<dyn Observer>) {
+        self.frontier.observe(observer);
+    }
+
+    /// Searches until the shortest superpermutation is found or a stop condition fires.
+    pub fn run(mut self) -> Outcome {
+        let n = self.config.n;
+
+        self.started_at = Instant::now();
+        self.checkpointed_at = Instant::now();
+
+        let watermarks = self.frontier.watermarks();
+        let policy = self.frontier.offloads().policy;
+        self.frontier.notify(|o, s| o.offload_planned(&watermarks, policy, s));
+
+        if !self.resumed {
+            self.frontier.add(Candidate::seed(n), n);
+            self.frontier.record(Record::Started { n, permutations: self.bounds.max });
+
+            for waste in 0..self.bounds.seeded {
+                self.lower_bound_found(waste);
+            }
+        }
+
+        while let Some(wasted_symbols) = self.frontier.min_waste() {
+            if let Some(reason) = self.stop_reason() {
+                if self.config.checkpoint_every.is_some() {
+                    self.save_checkpoint();
+                }
+
+                return self.outcome(Some(reason), None);
+            }
+
+            if self.checkpoint_due() {
+                self.save_checkpoint();
+            }
+
+            if let Err(error) = self.step(wasted_symbols) {
+                return self.outcome(Some(Reason::Failed(error)), None);
+            }
+
+            match self.finished() {
+                Ok(false) => {},
+                Ok(true) => {
+                    let length = self.complete();
+                    return self.outcome(None, Some(length));
+                },
+                Err(error) => return self.outcome(Some(Reason::Failed(error)), None),
+            }
+        }
+
+        self.outcome(None, None)
+    }
+
+    fn step(&mut self, mut wasted_symbols: usize) -> Result<(), String> {
+        let n = self.config.n;
+        let eager = self.config.prune == Prune::Eager;
+
+        wasted_symbols = self.frontier.unprune(
+            wasted_symbols,
+            &self.bounds.lower_bounds,
+            &self.bounds.upper_bounds,
+            self.enumerated_waste(),
+        )?;
+
+        self.prune_seeds(wasted_symbols);
+        self.finish_phases(wasted_symbols)?;
+
+        let candidate = self.frontier.next().unwrap();
+        let permutations = candidate.number_of_permutations();
+
+        let previous_len = self.bounds.lower_bounds.len();
+
+        if self.bounds.update(wasted_symbols, permutations) {
+            self.report_bounds(previous_len, wasted_symbols, permutations);
+
+            if self.config.paths {
+                self.report_witnesses(previous_len, wasted_symbols, permutations, &candidate);
+            }
+
+            let threshold = self.bounds.thresholds[wasted_symbols];
+            self.frontier.prune(wasted_symbols, threshold, eager);
+        }
+
+        if self.enumerated_waste() == Some(wasted_symbols) && self.bounds.lower_bounds[wasted_symbols] == permutations {
+            let string = candidate.string(n);
+            self.enumeration.as_mut().unwrap().collect(&string, permutations, n);
+        }
+
+        self.frontier.notify(|o, s| o.candidate_expanded(&candidate, s));
+
+        let upper_bound = self.bounds.upper(wasted_symbols);
+        for child in candidate.expand(upper_bound, n, self.config.paths) {
+            if self.could_tie(&child) {
+                self.frontier.add(child, n);
+            }
+        }
+
+        self.expansions += 1;
+
+        Ok(())
+    }
+
+    // The search stops at the first superpermutation unless it's enumerating
+    // them. Then it carries on until no pruned bucket could tie it either.
+    fn finished(&mut self) -> Result<bool, String> {
+        if !self.bounds.found_for_superpermutation() {
+            return Ok(false);
+        }
+
+        let waste = match (&self.enumeration, self.frontier.min_waste()) {
+            (Some(_), Some(waste)) => waste,
+            _ => return Ok(true),
+        };
+
+        let last = self.bounds.lower_bounds.len() - 1;
+        let lower_bounds = &self.bounds.lower_bounds;
+        let upper_bounds = &self.bounds.upper_bounds;
+
+        Ok(self.frontier.unprune(waste, lower_bounds, upper_bounds, Some(last))? > last)
+    }
+
+    // After the superpermutation, only the candidates that could tie it are
+    // expanded, so children that can't reach it within its waste are dropped.
+    fn could_tie(&self, child: &Candidate) -> bool {
+        if !self.bounds.found_for_superpermutation() {
+            return true;
+        }
+
+        let last = self.bounds.lower_bounds.len() - 1;
+        let waste = child.total_waste(self.config.n);
+
+        waste <= last && child.number_of_permutations() + self.bounds.upper_bounds[last - waste] >= self.bounds.max
+    }
+
+    // The waste whose strings are being collected, once the search knows it.
+    fn enumerated_waste(&self) -> Option<usize> {
+        self.enumeration.as_ref()?;
+
+        match self.config.stop.max_waste {
+            Some(waste) => Some(waste),
+            None if self.bounds.found_for_superpermutation() => Some(self.bounds.lower_bounds.len() - 1),
+            None => None,
+        }
+    }
+
+    fn enumerated(&mut self) -> Option<Result<Enumeration, String>> {
+        let waste = self.enumerated_waste();
+        let collector = self.enumeration.take()?;
+
+        Some(match waste {
+            None => Err("no superpermutation was found, so there is nothing to enumerate".to_string()),
+            Some(w) if self.bounds.proven().len() <= w => Err(format!("the bound for {} wasted symbols has not been proven", w)),
+            Some(w) if w < self.bounds.seeded => Err(format!("the bound for {} wasted symbols was seeded, so the search didn't enumerate it", w)),
+            Some(w) => Ok(collector.enumeration(self.config.n, w)),
+        })
+    }
+
+    // Seeded bounds are never updated by the search so their thresholds are
+    // applied as soon as it reaches each number of wasted symbols instead.
+    fn prune_seeds(&mut self, wasted_symbols: usize) {
+        let eager = self.config.prune == Prune::Eager;
+
+        while self.pruned_seeds < self.bounds.seeded && self.pruned_seeds <= wasted_symbols {
+            let waste = self.pruned_seeds;
+            let threshold = self.bounds.thresholds[waste];
+
+            self.frontier.prune(waste, threshold, eager);
+            self.pruned_seeds += 1;
+        }
+    }
+
+    // Every bucket with fewer wasted symbols than the next candidate has been
+    // expanded or is still pruned, so the bounds below it can't change.
+    fn finish_phases(&mut self, wasted_symbols: usize) -> Result<(), String> {
+        while self.phases_finished < wasted_symbols && self.phases_finished < self.bounds.lower_bounds.len() {
+            let waste = self.phases_finished;
+
+            if self.frontier.proving() {
+                let record = Record::PhaseFinished {
+                    waste,
+                    permutations: self.bounds.lower_bounds[waste],
+                    seeded: waste < self.bounds.seeded,
+                    claimed: self.bounds.claimed.contains(&waste),
+                    pruned: self.frontier.pruned_buckets(waste)?,
+                };
+
+                self.frontier.record(record);
+            }
+
+            self.phases_finished += 1;
+        }
+
+        Ok(())
+    }
+
+    // The time limit and the maximum number of expansions of a resumed search
+    // count from when it was resumed.
+    fn stop_reason(&self) -> Option<Reason> {
+        let expansions = self.expansions - self.expansions_before;
+
+        match self.interrupt.requested() {
+            true => Some(Reason::Interrupted),
+            false => self.config.stop.check(&self.bounds, self.started_at.elapsed(), expansions),
+        }
+    }
+
+    /// Saves a checkpoint in the scratch directory and returns its size. The
+    /// chunks that were read from disk since the last one are removed after it
+    /// has been saved.
+    pub fn checkpoint(&mut self) -> Result<u64, String> {
+        if self.config.checkpoint_every.is_none() {
+            return Err("checkpoints need checkpoint_every to be set before the search starts".to_string());
+        }
+
+        // Finishing the offloads in flight writes events and proof records, so
+        // the lengths of those files are taken after it.
+        self.frontier.flush()?;
+
+        let checkpoint = Checkpoint {
+            config: self.config.clone(),
+            bounds: self.bounds.clone(),
+            expansions: self.expansions,
+            pruned_seeds: self.pruned_seeds,
+            phases_finished: self.phases_finished,
+            witnesses: self.witnesses.clone(),
+            marks: self.marks.clone(),
+            elapsed: self.elapsed(),
+            witnesses_len: Checkpoint::len_of(&self.config.witnesses),
+            proof_len: Checkpoint::len_of(&self.config.proof),
+            events_len: Checkpoint::len_of(&self.config.events),
+        };
+
+        let frontier = &self.frontier;
+        let bytes = checkpoint.save(|writer| frontier.save(writer))?;
+
+        self.checkpointed_at = Instant::now();
+        self.frontier.release()?;
+
+        Ok(bytes)
+    }
+
+    fn save_checkpoint(&mut self) {
+        match self.checkpoint() {
+            Ok(bytes) => self.frontier.notify(|o, s| o.checkpoint_saved(bytes, s)),
+            Err(error) => self.frontier.notify(|o, s| o.checkpoint_failed(&error, s)),
+        }
+    }
+
+    fn checkpoint_due(&mut self) -> bool {
+        let every = match self.config.checkpoint_every {
+            None => return false,
+            Some(every) => every,
+        };
+
+        if self.checkpointed_at.elapsed() >= every {
+            return true;
+        }
+
+        if self.polled_at.elapsed() < POLL {
+            return false;
+        }
+
+        self.polled_at = Instant::now();
+
+        let request = Path::new(&self.config.scratch).join(REQUEST);
+        request.exists() && remove_file(&request).is_ok()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.elapsed_before + self.started_at.elapsed()
+    }
+
+    fn report_bounds(&mut self, previous_len: usize, index: usize, permutations: usize) {
+        if index < previous_len {
+            self.lower_bound_found(index);
+            return;
+        }
+
+        self.frontier.notify(|o, s| o.phase_started(index, permutations, s));
+
+        for waste in (previous_len - 1)..=index {
+            let permutations = self.bounds.upper_bounds[waste];
+
+            if permutations < self.bounds.max {
+                self.upper_bound_tightened(waste, permutations);
+            }
+        }
+
+        for waste in previous_len..=index {
+            self.lower_bound_found(waste);
+        }
+    }
+
+    fn lower_bound_found(&mut self, waste: usize) {
+        let permutations = self.bounds.lower_bounds[waste];
+
+        self.frontier.notify(|o, s| o.lower_bound_found(waste, permutations, s));
+
+        self.frontier.record(Record::Bound {
+            waste,
+            permutations,
+            upper_bound: self.bounds.upper_bounds[waste],
+            seeded: waste < self.bounds.seeded,
+        });
+
+        let comparison = known::compare_lower(self.config.n, waste, permutations);
+        self.mark(waste, permutations, comparison);
+    }
+
+    fn upper_bound_tightened(&mut self, waste: usize, permutations: usize) {
+        self.frontier.notify(|o, s| o.upper_bound_tightened(waste, permutations, s));
+
+        let comparison = known::compare_upper(self.config.n, waste, permutations);
+        self.mark(waste, permutations, comparison);
+    }
+
+    // An upper bound can be reported again once it's fixed to the lower bound
+    // so a bound is only marked the first time.
+    fn mark(&mut self, waste: usize, permutations: usize, comparison: Option<Comparison>) {
+        let mark = match comparison {
+            Some(comparison) => Mark { waste, permutations, comparison },
+            None => return,
+        };
+
+        if !self.marks.contains(&mark) {
+            self.frontier.notify(|o, s| o.bound_compared(waste, permutations, mark.comparison, s));
+            self.marks.push(mark);
+        }
+    }
+
+    fn report_witnesses(&mut self, previous_len: usize, index: usize, permutations: usize, candidate: &Candidate) {
+        let inherited = previous_len - 1;
+
+        for waste in min(previous_len, index)..=index {
+            let bound = self.bounds.lower_bounds[waste];
+
+            // The levels that only inherited the last bound inherit its witness, too.
+            let inherits = waste > inherited && bound == self.bounds.lower_bounds[inherited];
+
+            let witness = if waste == index && bound == permutations {
+                candidate.string(self.config.n)
+            } else if let Some(witness) = self.witnesses.get(inherited).cloned().flatten().filter(|_| inherits) {
+                witness
+            } else {
+                continue;
+            };
+
+            self.report_witness(waste, bound, witness);
+        }
+    }
+
+    fn report_witness(&mut self, waste: usize, permutations: usize, witness: Vec<u8>) {
+        self.frontier.notify(|o, s| o.witness_found(waste, permutations, &witness, s));
+
+        if self.witnesses.len() <= waste {
+            self.witnesses.resize(waste + 1, None);
+        }
+
+        self.witnesses[waste] = Some(witness);
+    }
+
+    fn complete(&mut self) -> usize {
+        let n = self.config.n;
+        let waste = self.bounds.lower_bounds.len() - 1;
+        let factorial = Bounds::factorial(n);
+        let length = n - 1 + factorial + waste;
+
+        self.upper_bound_tightened(waste, factorial);
+        self.frontier.notify(|o, s| o.search_completed(waste, factorial, length, s));
+        self.frontier.record(Record::Completed { waste, permutations: factorial });
+
+        length
+    }
+
+    fn outcome(mut self, stopped: Option<Reason>, superpermutation_length: Option<usize>) -> Outcome {
+        // Offloads still in flight count towards the statistics. The result
+        // doesn't depend on them, so a chunk that fails to write is ignored.
+        let _ = self.frontier.flush();
+
+        let enumeration = self.enumerated();
+
+        let superpermutation = match superpermutation_length {
+            Some(_) => self.witnesses.last().cloned().flatten(),
+            None => None,
+        };
+
+        let statistics = Statistics {
+            expansions: self.expansions,
+            elapsed: self.elapsed(),
+            enabled: self.frontier.enabled_len(),
+            disabled: self.frontier.disabled_len(),
+            disabled_buckets: self.frontier.disabled_buckets(),
+            disk_bytes: self.frontier.disk_bytes(),
+            prefetches: self.frontier.prefetches(),
+            offloads: self.frontier.offloads(),
+        };
+
+        Outcome {
+            n: self.config.n,
+            bounds: self.bounds,
+            superpermutation_length,
+            superpermutation,
+            witnesses: self.witnesses,
+            marks: self.marks,
+            enumeration,
+            stopped,
+            statistics,
+        }
+    }
+}
+
+impl Outcome {
+    /// The maximum number of permutations for each number of wasted symbols
+    /// that the search has proven.
+    pub fn proven(&self) -> &[usize] {
+        self.bounds.proven()
+    }
+
+    pub fn contradictions(&self) -> Vec<Mark> {
+        self.marks.iter().filter(|m| m.comparison.is_contradiction()).copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod test;
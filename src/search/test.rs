@@ -0,0 +1,585 @@
+use super::*;
+use super::super::observer::Snapshot;
+use super::super::offload::Watermarks;
+use super::super::stop::Stop;
+use std::fs::{create_dir_all, read_to_string};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl Observer for Recorder {
+    fn offload_planned(&mut self, _: &Watermarks, policy: &str, _: &Snapshot) {
+        self.0.lock().unwrap().push(format!("planned {}", policy));
+    }
+
+    fn phase_started(&mut self, waste: usize, permutations: usize, _: &Snapshot) {
+        self.0.lock().unwrap().push(format!("phase {} {}", waste, permutations));
+    }
+
+    fn candidate_expanded(&mut self, _: &Candidate, _: &Snapshot) {
+        self.0.lock().unwrap().push("expanded".to_string());
+    }
+
+    fn search_completed(&mut self, waste: usize, permutations: usize, length: usize, _: &Snapshot) {
+        self.0.lock().unwrap().push(format!("completed {} {} {}", waste, permutations, length));
+    }
+}
+
+fn config(n: usize, scratch: &str) -> Config {
+    Config {
+        n,
+        memory: 1.,
+        status: false,
+        scratch: format!("/tmp/superpermutation-test/{}", scratch),
+        ..Config::default()
+    }
+}
+
+mod run {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_shortest_superpermutation_for_three_symbols() {
+        let outcome = Search::new(Config { paths: true, ..config(3, "search-1") }).unwrap().run();
+
+        assert_eq!(outcome.superpermutation_length, Some(9));
+        assert_eq!(outcome.superpermutation, Some(vec![0, 1, 2, 0, 1, 0, 2, 1, 0]));
+        assert_eq!(outcome.proven(), &[3, 6]);
+        assert_eq!(outcome.stopped, None);
+    }
+
+    #[test]
+    fn it_finds_the_bounds_for_every_number_of_wasted_symbols_for_four_symbols() {
+        let outcome = Search::new(config(4, "search-2")).unwrap().run();
+
+        assert_eq!(outcome.superpermutation_length, Some(33));
+        assert_eq!(outcome.proven(), &[4, 8, 12, 14, 18, 20, 24]);
+    }
+
+    #[test]
+    fn it_reports_statistics_about_the_search() {
+        let outcome = Search::new(config(4, "search-3")).unwrap().run();
+        let statistics = outcome.statistics;
+
+        assert!(statistics.expansions > 0);
+        assert_eq!(statistics.disk_bytes, 0);
+    }
+
+    #[test]
+    fn it_finds_the_same_bounds_without_keeping_paths() {
+        let config = Config { paths: false, ..config(4, "search-29") };
+        let outcome = Search::new(config).unwrap().run();
+
+        assert_eq!(outcome.proven(), &[4, 8, 12, 14, 18, 20, 24]);
+        assert_eq!(outcome.superpermutation_length, Some(33));
+        assert_eq!(outcome.superpermutation, None);
+        assert_eq!(outcome.witnesses, vec![]);
+    }
+
+    #[test]
+    fn it_stops_early_when_a_stop_condition_fires() {
+        let stop = Stop { max_waste: Some(2), ..Stop::default() };
+        let config = Config { stop, ..config(5, "search-4") };
+
+        let outcome = Search::new(config).unwrap().run();
+
+        assert_eq!(outcome.stopped, Some(Reason::MaxWaste(2)));
+        assert_eq!(outcome.superpermutation_length, None);
+        assert_eq!(outcome.proven(), &[5, 10, 15]);
+    }
+
+    #[test]
+    fn it_stops_when_interrupted() {
+        let search = Search::new(config(5, "search-5")).unwrap();
+
+        search.interrupt().request();
+        let outcome = search.run();
+
+        assert_eq!(outcome.stopped, Some(Reason::Interrupted));
+        assert_eq!(outcome.statistics.expansions, 0);
+    }
+}
+
+mod witnesses {
+    use super::*;
+    use super::super::super::verify::verify_bounds;
+
+    fn permutations_in(string: &[u8], n: usize) -> usize {
+        let mut seen: Vec<&[u8]> = string.windows(n)
+            .filter(|w| (0..n as u8).all(|s| w.contains(&s)))
+            .collect();
+
+        seen.sort();
+        seen.dedup();
+        seen.len()
+    }
+
+    #[test]
+    fn it_keeps_a_string_that_achieves_each_lower_bound() {
+        let outcome = Search::new(Config { paths: true, ..config(4, "search-8") }).unwrap().run();
+
+        assert_eq!(outcome.witnesses.len(), 7);
+
+        for (waste, witness) in outcome.witnesses.iter().enumerate() {
+            let witness = witness.as_ref().unwrap();
+            let permutations = outcome.bounds.lower_bounds[waste];
+
+            assert_eq!(permutations_in(witness, 4), permutations);
+            assert!(witness.len() <= 3 + permutations + waste);
+        }
+
+        let superpermutation = outcome.superpermutation.unwrap();
+
+        assert_eq!(superpermutation.len(), 33);
+        assert_eq!(permutations_in(&superpermutation, 4), 24);
+    }
+
+    #[test]
+    fn it_saves_the_witnesses_to_a_file() {
+        let path = "/tmp/superpermutation-test/search-witnesses.txt";
+        create_dir_all("/tmp/superpermutation-test").unwrap();
+
+        let config = Config { witnesses: Some(path.to_string()), paths: true, ..config(3, "search-9") };
+        Search::new(config).unwrap().run();
+
+        let text = read_to_string(path).unwrap();
+        assert_eq!(text.lines().last(), Some("1 = 6 claimed 123121321"));
+    }
+
+    #[test]
+    fn it_saves_a_file_that_can_be_verified_and_seeded_from() {
+        let path = "/tmp/superpermutation-test/search-witnesses-4.txt";
+        create_dir_all("/tmp/superpermutation-test").unwrap();
+
+        let stop = Stop { max_waste: Some(3), ..Stop::default() };
+        let saving = Config { witnesses: Some(path.to_string()), paths: true, stop, ..config(4, "search-30") };
+        let stopped = Search::new(saving).unwrap().run();
+
+        let checks = verify_bounds(path, 4).unwrap();
+        assert!(checks.len() > 4 && checks.iter().all(|c| c.passed()));
+
+        let seeded = Config { bounds: Some(path.to_string()), ..config(4, "search-31") };
+        let search = Search::new(seeded).unwrap();
+
+        assert_eq!(search.bounds().lower_bounds, stopped.bounds.lower_bounds);
+        assert_eq!(search.run().proven(), &[4, 8, 12, 14, 18, 20, 24]);
+    }
+
+    #[test]
+    fn it_gives_the_levels_that_inherit_a_bound_the_witness_they_inherit_it_from() {
+        let mut search = Search::new(Config { paths: true, ..config(3, "search-37") }).unwrap();
+        let witness = vec![0, 1, 2, 0, 1];
+
+        search.bounds.update(0, 3);
+        search.witnesses = vec![Some(witness.clone())];
+
+        search.bounds.update(2, 1);
+        search.report_witnesses(1, 2, 1, &Candidate::seed(3));
+
+        assert_eq!(search.bounds.lower_bounds, vec![3, 3, 3]);
+        assert_eq!(search.witnesses, vec![Some(witness.clone()), Some(witness.clone()), Some(witness)]);
+    }
+}
+
+mod enumerate {
+    use super::*;
+    use super::super::super::verify::verify;
+    use super::super::super::witness::Witnesses;
+
+    fn enumerate(n: usize, max_waste: Option<usize>, scratch: &str) -> Result<Enumeration, String> {
+        let stop = Stop { max_waste, ..Stop::default() };
+        let mut search = Search::new(Config { paths: true, stop, ..config(n, scratch) }).unwrap();
+
+        search.enumerate().unwrap();
+        search.run().enumeration.unwrap()
+    }
+
+    #[test]
+    fn it_finds_every_shortest_superpermutation() {
+        let enumeration = enumerate(4, None, "search-33").unwrap();
+
+        assert_eq!(enumeration.waste, 6);
+        assert_eq!(enumeration.permutations, 24);
+        assert_eq!(enumeration.found, 1);
+        assert_eq!(enumeration.strings, vec![Witnesses::parse("123412314231243121342132413214321").unwrap()]);
+    }
+
+    // How many strings the separate depth-first search that enumerate used
+    // to run found for each waste, and how many were left up to reversal.
+    #[test]
+    fn it_finds_every_string_that_achieves_each_bound() {
+        let expected = [
+            (4, vec![(1, 1), (1, 1), (1, 1), (2, 1), (1, 1), (7, 4), (1, 1)]),
+            (5, vec![(1, 1), (1, 1), (1, 1), (1, 1), (3, 2), (2, 1), (1, 1), (4, 3), (2, 1), (1, 1), (2, 1), (10, 5)]),
+        ];
+
+        for (n, counts) in &expected {
+            for (waste, &(found, classes)) in counts.iter().enumerate() {
+                let enumeration = enumerate(*n, Some(waste), "search-34").unwrap();
+
+                assert_eq!((enumeration.found, enumeration.strings.len()), (found, classes), "n = {}, waste = {}", n, waste);
+
+                for string in &enumeration.strings {
+                    let report = verify(string, *n).unwrap();
+                    assert!(report.achieves(waste, enumeration.permutations));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_needs_the_search_to_keep_paths() {
+        let mut search = Search::new(config(3, "search-35")).unwrap();
+        assert_eq!(search.enumerate(), Err("enumerating needs the search to keep paths".to_string()));
+    }
+
+    #[test]
+    fn it_needs_the_bound_for_the_waste_to_be_proven() {
+        let stop = Stop { max_waste: Some(5), max_expansions: Some(10), ..Stop::default() };
+        let mut search = Search::new(Config { paths: true, stop, ..config(4, "search-36") }).unwrap();
+
+        search.enumerate().unwrap();
+        let error = search.run().enumeration.unwrap().unwrap_err();
+
+        assert_eq!(error, "the bound for 5 wasted symbols has not been proven");
+    }
+}
+
+mod seed {
+    use super::*;
+    use std::fs::write;
+
+    #[test]
+    fn it_starts_from_the_bounds_in_the_file_and_finds_the_same_result() {
+        let path = "/tmp/superpermutation-test/search-seed.bounds";
+        create_dir_all("/tmp/superpermutation-test").unwrap();
+        write(path, "0 = 4 proven\n1 = 8 proven\n2 = 12 claimed\n").unwrap();
+
+        let config = Config { bounds: Some(path.to_string()), ..config(4, "search-7") };
+        let search = Search::new(config).unwrap();
+
+        assert_eq!(search.bounds().proven(), &[4, 8]);
+
+        let outcome = search.run();
+
+        assert_eq!(outcome.superpermutation_length, Some(33));
+        assert_eq!(outcome.proven(), &[4, 8, 12, 14, 18, 20, 24]);
+    }
+}
+
+mod reference {
+    use super::*;
+    use super::super::super::reference::{compare, solve};
+
+    #[test]
+    fn it_agrees_with_the_brute_force_solver() {
+        for n in 2..=4 {
+            let outcome = Search::new(config(n, "search-11")).unwrap().run();
+            assert_eq!(compare(&solve(n).unwrap(), outcome.proven()), None);
+        }
+    }
+}
+
+mod known {
+    use super::*;
+    use super::super::super::known::{table, Comparison, Mark};
+    use std::fs::write;
+
+    #[test]
+    fn it_matches_the_known_results_for_three_and_four_symbols() {
+        for n in 3..=4 {
+            let outcome = Search::new(config(n, "search-12")).unwrap().run();
+
+            assert_eq!(outcome.proven(), table(n).unwrap());
+            assert_eq!(outcome.marks.len(), table(n).unwrap().len());
+            assert_eq!(outcome.contradictions(), vec![]);
+        }
+    }
+
+    // This takes a few minutes so run it with --release --ignored.
+    #[test]
+    #[ignore]
+    fn it_matches_the_known_results_for_five_symbols() {
+        let outcome = Search::new(config(5, "search-13")).unwrap().run();
+
+        assert_eq!(outcome.proven(), table(5).unwrap());
+        assert_eq!(outcome.contradictions(), vec![]);
+    }
+
+    #[test]
+    fn it_flags_bounds_that_contradict_the_known_results() {
+        let path = "/tmp/superpermutation-test/search-wrong.bounds";
+        create_dir_all("/tmp/superpermutation-test").unwrap();
+        write(path, "0 = 4 proven\n1 = 7 proven\n").unwrap();
+
+        let config = Config { bounds: Some(path.to_string()), ..config(4, "search-14") };
+        let outcome = Search::new(config).unwrap().run();
+        let contradictions = outcome.contradictions();
+
+        assert_eq!(contradictions[0], Mark { waste: 1, permutations: 7, comparison: Comparison::Contradicts { known: 8 } });
+        assert_eq!(contradictions.len(), 6);
+    }
+}
+
+mod proof {
+    use super::*;
+    use super::super::super::proof::ProofLog;
+
+    #[test]
+    fn it_writes_a_proof_log_that_checks_out() {
+        let path = "/tmp/superpermutation-test/search-4.proof";
+        create_dir_all("/tmp/superpermutation-test").unwrap();
+
+        let config = Config { proof: Some(path.to_string()), ..config(4, "search-10") };
+        Search::new(config).unwrap().run();
+
+        let phases = ProofLog::check(path).unwrap();
+        let bounds: Vec<_> = phases.iter().map(|p| p.permutations).collect();
+
+        assert_eq!(bounds, &[4, 8, 12, 14, 18, 20, 24]);
+        assert!(phases.last().unwrap().completed);
+        assert!(phases.iter().any(|p| p.ruled_out > 0));
+    }
+}
+
+mod resume {
+    use super::*;
+    use super::super::super::proof::ProofLog;
+
+    // Small enough that the frontier offloads buckets to disk.
+    const MEMORY: f64 = 0.000005;
+
+    fn checkpointed(scratch: &str, max_expansions: usize) -> Config {
+        Config {
+            memory: MEMORY,
+            paths: true,
+            checkpoint_every: Some(Duration::from_secs(3600)),
+            stop: Stop { max_expansions: Some(max_expansions), ..Stop::default() },
+            ..config(4, scratch)
+        }
+    }
+
+    fn settings(max_expansions: usize) -> Settings {
+        Settings { max_expansions: Some(max_expansions), ..Settings::default() }
+    }
+
+    #[test]
+    fn it_finds_the_same_result_as_an_uninterrupted_search() {
+        let uninterrupted = Search::new(Config { memory: MEMORY, paths: true, ..config(4, "search-15") }).unwrap().run();
+        assert!(uninterrupted.statistics.disk_bytes > 0);
+        assert!(uninterrupted.statistics.prefetches.hit_rate().is_some());
+
+        let proof = "/tmp/superpermutation-test/search-16.proof";
+        let config = Config { proof: Some(proof.to_string()), ..checkpointed("search-16", 40) };
+        let scratch = config.scratch.clone();
+
+        let mut outcome = Search::new(config).unwrap().run();
+        let mut resumes = 0;
+
+        while outcome.stopped.is_some() {
+            assert_eq!(outcome.stopped, Some(Reason::MaxExpansions(40)));
+
+            outcome = Search::resume(&scratch, settings(40)).unwrap().run();
+            resumes += 1;
+        }
+
+        assert!(resumes > 2);
+        assert_eq!(outcome.proven(), uninterrupted.proven());
+        assert_eq!(outcome.superpermutation, uninterrupted.superpermutation);
+        assert_eq!(outcome.witnesses, uninterrupted.witnesses);
+        assert_eq!(outcome.statistics.expansions, uninterrupted.statistics.expansions);
+        assert!(ProofLog::check(proof).is_ok());
+    }
+
+    #[test]
+    fn it_finds_the_chunks_it_had_read_since_the_checkpoint() {
+        // Buckets offloaded before the checkpoint are read back after it.
+        let config = checkpointed("search-17", 90);
+        let scratch = config.scratch.clone();
+        Search::new(config).unwrap().run();
+
+        // Run to the end without saving another checkpoint, as if it crashed
+        // just before finishing.
+        let mut crashed = Search::resume(&scratch, Settings::default()).unwrap();
+        crashed.config.checkpoint_every = None;
+        crashed.run();
+
+        let outcome = Search::resume(&scratch, Settings::default()).unwrap().run();
+        assert_eq!(outcome.proven(), &[4, 8, 12, 14, 18, 20, 24]);
+    }
+
+    #[test]
+    fn it_drops_the_events_written_after_the_checkpoint() {
+        let lower_bounds = |path: &str| {
+            read_to_string(path).unwrap().lines().filter(|l| l.contains("lower_bound_found")).count()
+        };
+
+        let events = "/tmp/superpermutation-test/search-26.jsonl";
+        let checkpointed = Config { events: Some(events.to_string()), ..checkpointed("search-26", 90) };
+        let scratch = checkpointed.scratch.clone();
+        Search::new(checkpointed).unwrap().run();
+
+        let mut crashed = Search::resume(&scratch, Settings::default()).unwrap();
+        crashed.config.checkpoint_every = None;
+        crashed.run();
+
+        Search::resume(&scratch, Settings::default()).unwrap().run();
+
+        let uninterrupted = "/tmp/superpermutation-test/search-27.jsonl";
+        let config = Config { events: Some(uninterrupted.to_string()), ..config(4, "search-27") };
+        Search::new(config).unwrap().run();
+
+        assert_eq!(lower_bounds(events), lower_bounds(uninterrupted));
+    }
+
+    // The events written after the length in each checkpoint was taken.
+    #[derive(Clone, Default)]
+    struct AfterCheckpoint(String, Arc<Mutex<Vec<Vec<String>>>>);
+
+    impl Observer for AfterCheckpoint {
+        fn checkpoint_saved(&mut self, _: u64, _: &Snapshot) {
+            let (checkpoint, _) = Checkpoint::load(&self.0).unwrap();
+            let events = read_to_string(checkpoint.config.events.as_ref().unwrap()).unwrap();
+
+            let after = events[checkpoint.events_len.unwrap() as usize..].lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["event"].as_str().unwrap().to_string())
+                .collect();
+
+            self.1.lock().unwrap().push(after);
+        }
+    }
+
+    #[test]
+    fn it_takes_the_lengths_of_the_logs_after_finishing_the_offloads_in_flight() {
+        let events = "/tmp/superpermutation-test/search-32.jsonl";
+        let config = Config { events: Some(events.to_string()), ..checkpointed("search-32", 40) };
+        let scratch = config.scratch.clone();
+        let observer = AfterCheckpoint(scratch.clone(), Arc::default());
+
+        let mut search = Search::new(config).unwrap();
+        search.observe(Box::new(observer.clone()));
+
+        while search.run().stopped.is_some() {
+            search = Search::resume(&scratch, settings(40)).unwrap();
+            search.observe(Box::new(observer.clone()));
+        }
+
+        let saved = observer.1.lock().unwrap();
+
+        assert!(saved.len() > 2);
+        assert!(saved.iter().all(|after| after == &["checkpoint_saved"]));
+    }
+
+    #[test]
+    fn it_refuses_to_resume_without_a_checkpoint() {
+        let scratch = "/tmp/superpermutation-test/search-18";
+        Search::new(config(3, "search-18")).unwrap().run();
+
+        assert!(Search::resume(scratch, Settings::default()).is_err());
+    }
+}
+
+mod codec {
+    use super::*;
+    use super::super::super::codec::Codec;
+
+    #[test]
+    fn it_finds_the_same_result_with_each_codec_when_offloading() {
+        for (scratch, codec) in [("search-20", Codec::Zlib(1)), ("search-21", Codec::Packed), ("search-22", Codec::Compact)] {
+            let config = Config { memory: 0.000005, codec, ..config(4, scratch) };
+            let outcome = Search::new(config).unwrap().run();
+
+            assert_eq!(outcome.proven(), &[4, 8, 12, 14, 18, 20, 24]);
+        }
+    }
+}
+
+mod offload {
+    use super::*;
+    use super::super::super::offload::Offload;
+
+    #[test]
+    fn it_finds_the_same_result_with_each_offload_policy() {
+        for (scratch, offload) in [("search-23", Offload::Unlikely), ("search-24", Offload::Largest), ("search-25", Offload::Waste)] {
+            let config = Config { memory: 0.000005, offload, ..config(4, scratch) };
+            let outcome = Search::new(config).unwrap().run();
+
+            assert_eq!(outcome.proven(), &[4, 8, 12, 14, 18, 20, 24]);
+            assert_eq!(outcome.statistics.offloads.policy, offload.policy().name());
+            assert!(outcome.statistics.offloads.candidates > 0);
+        }
+    }
+}
+
+mod corrupt {
+    use super::*;
+    use std::fs::{read_dir, remove_dir_all, rename, write};
+
+    struct Corrupter(String);
+
+    // Chunks are read on the I/O thread while this runs, so each one is
+    // replaced in a single step rather than truncated and rewritten.
+    impl Observer for Corrupter {
+        fn offload_finished(&mut self, _: &Snapshot) {
+            let garbage = format!("{}/garbage", self.0);
+
+            for entry in read_dir(&self.0).unwrap().filter_map(|e| e.ok()) {
+                if entry.file_name().to_string_lossy().starts_with("candidates") {
+                    write(&garbage, "garbage").unwrap();
+                    let _ = rename(&garbage, entry.path());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_stops_with_an_error_when_a_chunk_cannot_be_written() {
+        let config = Config { memory: 0.000005, ..config(4, "search-28") };
+        let search = Search::new(config.clone()).unwrap();
+        remove_dir_all(&config.scratch).unwrap();
+
+        let error = match search.run().stopped {
+            Some(Reason::Failed(error)) => error,
+            other => panic!("expected the search to fail (got {:?})", other),
+        };
+
+        assert!(error.starts_with(&format!("Failed to write {}/candidates-with-", config.scratch)));
+    }
+
+    #[test]
+    fn it_stops_with_an_error_that_names_the_bucket_of_a_corrupt_chunk() {
+        let config = Config { memory: 0.000002, ..config(4, "search-19") };
+        let mut search = Search::new(config.clone()).unwrap();
+        search.observe(Box::new(Corrupter(config.scratch.clone())));
+
+        let outcome = search.run();
+        let error = match outcome.stopped {
+            Some(Reason::Failed(error)) => error,
+            other => panic!("expected the search to fail (got {:?})", other),
+        };
+
+        assert!(error.starts_with(&format!("The chunk {}/candidates-with-", config.scratch)));
+        assert!(error.contains("is corrupt: its checksum is"));
+    }
+}
+
+mod observe {
+    use super::*;
+
+    #[test]
+    fn it_notifies_the_observer_as_the_search_progresses() {
+        let recorder = Recorder::default();
+        let mut search = Search::new(config(3, "search-6")).unwrap();
+
+        search.observe(Box::new(recorder.clone()));
+        let outcome = search.run();
+
+        let calls = recorder.0.lock().unwrap();
+        let expanded = calls.iter().filter(|c| *c == "expanded").count();
+        let other: Vec<_> = calls.iter().filter(|c| *c != "expanded").collect();
+
+        assert_eq!(expanded, outcome.statistics.expansions);
+        assert_eq!(other, vec!["planned unlikely", "phase 1 3", "completed 1 6 9"]);
+    }
+}
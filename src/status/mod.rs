@@ -0,0 +1,126 @@
+use super::candidate::Candidate;
+use super::observer::{Observer, Snapshot};
+use super::ui::UI;
+use super::out;
+
+use std::time::{Duration, Instant};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct Status {
+    enabled: bool,
+    expanded: usize,
+    expanded_at_refresh: usize,
+    refreshed_at: Instant,
+    last_bound_at: Instant,
+}
+
+impl Status {
+    pub fn new(enabled: bool) -> Self {
+        let now = Instant::now();
+
+        Self {
+            enabled,
+            expanded: 0,
+            expanded_at_refresh: 0,
+            refreshed_at: now,
+            last_bound_at: now,
+        }
+    }
+
+    pub fn expanded(&mut self) {
+        self.expanded += 1;
+    }
+
+    pub fn bound_found(&mut self) {
+        self.last_bound_at = Instant::now();
+    }
+
+    pub fn refresh(&mut self, snapshot: &Snapshot) {
+        if !self.enabled {
+            return;
+        }
+
+        let elapsed = self.refreshed_at.elapsed();
+
+        if elapsed < REFRESH_INTERVAL {
+            return;
+        }
+
+        let per_second = (self.expanded - self.expanded_at_refresh) as f64 / elapsed.as_secs_f64();
+
+        let line = Self::line(
+            snapshot.min_waste,
+            snapshot.enabled,
+            snapshot.disabled,
+            per_second,
+            snapshot.disk_bytes,
+            self.last_bound_at.elapsed(),
+        );
+
+        UI::clear_line();
+        out!("{}", line);
+        UI::flush();
+
+        self.expanded_at_refresh = self.expanded;
+        self.refreshed_at = Instant::now();
+    }
+
+    pub fn line(min_waste: Option<usize>, enabled: usize, disabled: usize, per_second: f64, disk_bytes: u64, since_bound: Duration) -> String {
+        let waste = match min_waste {
+            Some(w) => w.to_string(),
+            None => "-".to_string(),
+        };
+
+        format!(
+            "waste {} | enabled {} | disabled {} | {:.0}/s | disk {} | last bound {} ago",
+            waste,
+            enabled,
+            disabled,
+            per_second,
+            Self::format_bytes(disk_bytes),
+            Self::format_duration(since_bound),
+        )
+    }
+
+    pub fn format_bytes(bytes: u64) -> String {
+        let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+        let mut value = bytes as f64;
+        let mut unit = 0;
+
+        while value >= 1024. && unit < units.len() - 1 {
+            value /= 1024.;
+            unit += 1;
+        }
+
+        match unit {
+            0 => format!("{}B", bytes),
+            _ => format!("{:.1}{}", value, units[unit]),
+        }
+    }
+
+    pub fn format_duration(duration: Duration) -> String {
+        let seconds = duration.as_secs();
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+        match (hours, minutes) {
+            (0, 0) => format!("{}s", seconds),
+            (0, _) => format!("{}m {:02}s", minutes, seconds),
+            _ => format!("{}h {:02}m {:02}s", hours, minutes, seconds),
+        }
+    }
+}
+
+impl Observer for Status {
+    fn phase_started(&mut self, _: usize, _: usize, _: &Snapshot) {
+        self.bound_found();
+    }
+
+    fn candidate_expanded(&mut self, _: &Candidate, snapshot: &Snapshot) {
+        self.expanded();
+        self.refresh(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod test;
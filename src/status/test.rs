@@ -0,0 +1,60 @@
+use super::*;
+
+type Subject = Status;
+
+mod line {
+    use super::*;
+
+    #[test]
+    fn it_summarises_the_progress_of_the_search() {
+        let since_bound = Duration::from_secs(3 * 3600 + 2 * 60 + 10);
+        let line = Subject::line(Some(12), 1234567, 89012, 45678.4, 1536, since_bound);
+
+        assert_eq!(line, "waste 12 | enabled 1234567 | disabled 89012 | 45678/s | disk 1.5KiB | last bound 3h 02m 10s ago");
+    }
+
+    #[test]
+    fn it_shows_a_dash_when_the_frontier_is_empty() {
+        let line = Subject::line(None, 0, 0, 0., 0, Duration::from_secs(0));
+
+        assert_eq!(line, "waste - | enabled 0 | disabled 0 | 0/s | disk 0B | last bound 0s ago");
+    }
+}
+
+mod format_bytes {
+    use super::*;
+
+    #[test]
+    fn it_uses_the_largest_binary_unit_that_fits() {
+        assert_eq!(Subject::format_bytes(1023), "1023B");
+        assert_eq!(Subject::format_bytes(1024), "1.0KiB");
+        assert_eq!(Subject::format_bytes(5 * 1024 * 1024 + 512 * 1024), "5.5MiB");
+        assert_eq!(Subject::format_bytes(3 * 1024 * 1024 * 1024 * 1024), "3.0TiB");
+    }
+}
+
+mod format_duration {
+    use super::*;
+
+    #[test]
+    fn it_formats_seconds_minutes_and_hours() {
+        assert_eq!(Subject::format_duration(Duration::from_secs(9)), "9s");
+        assert_eq!(Subject::format_duration(Duration::from_secs(61)), "1m 01s");
+        assert_eq!(Subject::format_duration(Duration::from_secs(50 * 3600)), "50h 00m 00s");
+    }
+}
+
+mod expanded {
+    use super::*;
+
+    #[test]
+    fn it_counts_the_candidates_expanded_since_the_last_refresh() {
+        let mut subject = Subject::new(false);
+
+        subject.expanded();
+        subject.expanded();
+
+        assert_eq!(subject.expanded, 2);
+        assert_eq!(subject.expanded_at_refresh, 0);
+    }
+}
@@ -0,0 +1,85 @@
+use super::bounds::Bounds;
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stop {
+    pub max_waste: Option<usize>,
+    pub time_limit: Option<Duration>,
+    pub max_expansions: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Reason {
+    MaxWaste(usize),
+    TimeLimit(Duration),
+    MaxExpansions(usize),
+    Interrupted,
+    /// The search can't carry on, such as when a chunk on disk is corrupt.
+    Failed(String),
+}
+
+impl Stop {
+    pub fn check(&self, bounds: &Bounds, elapsed: Duration, expansions: usize) -> Option<Reason> {
+        if let Some(waste) = self.max_waste {
+            if bounds.proven().len() > waste {
+                return Some(Reason::MaxWaste(waste));
+            }
+        }
+
+        if let Some(limit) = self.time_limit {
+            if elapsed >= limit {
+                return Some(Reason::TimeLimit(limit));
+            }
+        }
+
+        if let Some(limit) = self.max_expansions {
+            if expansions >= limit {
+                return Some(Reason::MaxExpansions(limit));
+            }
+        }
+
+        None
+    }
+
+    pub fn parse_duration(input: &str) -> Option<Duration> {
+        let input = input.trim();
+        let split = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+        let (number, unit) = input.split_at(split);
+
+        let number: u64 = number.parse().ok()?;
+
+        let seconds = match unit.trim() {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return None,
+        };
+
+        Some(Duration::from_secs(number.checked_mul(seconds)?))
+    }
+}
+
+impl Reason {
+    pub fn describe(&self) -> String {
+        match self {
+            Reason::MaxWaste(w) => format!("the bound for {} wasted symbols has been proven", w),
+            Reason::TimeLimit(d) => format!("the time limit of {}s was reached", d.as_secs()),
+            Reason::MaxExpansions(e) => format!("{} candidates have been expanded", e),
+            Reason::Interrupted => "the search was interrupted".to_string(),
+            Reason::Failed(error) => format!("the search failed: {}", error),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Reason::Interrupted => 130,
+            Reason::Failed(_) => 1,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,88 @@
+use super::*;
+
+type Subject = Stop;
+
+const N: usize = 5;
+
+fn bounds_proven_up_to(waste: usize) -> Bounds {
+    let mut bounds = Bounds::new(N);
+
+    for w in 0..=(waste + 1) {
+        bounds.update(w, 5 * (w + 1));
+    }
+
+    bounds
+}
+
+mod check {
+    use super::*;
+
+    #[test]
+    fn it_does_not_stop_when_there_are_no_conditions() {
+        let subject = Subject::default();
+        let bounds = bounds_proven_up_to(10);
+
+        assert_eq!(subject.check(&bounds, Duration::from_secs(1_000_000), 1_000_000), None);
+    }
+
+    #[test]
+    fn it_stops_once_the_bound_for_the_max_waste_is_proven() {
+        let subject = Subject { max_waste: Some(3), ..Subject::default() };
+
+        assert_eq!(subject.check(&bounds_proven_up_to(2), Duration::from_secs(0), 0), None);
+        assert_eq!(subject.check(&bounds_proven_up_to(3), Duration::from_secs(0), 0), Some(Reason::MaxWaste(3)));
+    }
+
+    #[test]
+    fn it_stops_once_the_time_limit_is_reached() {
+        let limit = Duration::from_secs(60);
+        let subject = Subject { time_limit: Some(limit), ..Subject::default() };
+        let bounds = Bounds::new(N);
+
+        assert_eq!(subject.check(&bounds, Duration::from_secs(59), 0), None);
+        assert_eq!(subject.check(&bounds, Duration::from_secs(60), 0), Some(Reason::TimeLimit(limit)));
+    }
+
+    #[test]
+    fn it_stops_once_enough_candidates_have_been_expanded() {
+        let subject = Subject { max_expansions: Some(100), ..Subject::default() };
+        let bounds = Bounds::new(N);
+
+        assert_eq!(subject.check(&bounds, Duration::from_secs(0), 99), None);
+        assert_eq!(subject.check(&bounds, Duration::from_secs(0), 100), Some(Reason::MaxExpansions(100)));
+    }
+}
+
+mod parse_duration {
+    use super::*;
+
+    #[test]
+    fn it_parses_seconds_minutes_hours_and_days() {
+        assert_eq!(Subject::parse_duration("45"), Some(Duration::from_secs(45)));
+        assert_eq!(Subject::parse_duration("45s"), Some(Duration::from_secs(45)));
+        assert_eq!(Subject::parse_duration("90m"), Some(Duration::from_secs(5400)));
+        assert_eq!(Subject::parse_duration("12h"), Some(Duration::from_secs(43200)));
+        assert_eq!(Subject::parse_duration("2d"), Some(Duration::from_secs(172800)));
+    }
+
+    #[test]
+    fn it_returns_none_for_anything_else() {
+        assert_eq!(Subject::parse_duration(""), None);
+        assert_eq!(Subject::parse_duration("h"), None);
+        assert_eq!(Subject::parse_duration("2w"), None);
+        assert_eq!(Subject::parse_duration("1.5h"), None);
+    }
+}
+
+mod describe {
+    use super::*;
+
+    #[test]
+    fn it_says_which_condition_fired() {
+        assert_eq!(Reason::MaxWaste(8).describe(), "the bound for 8 wasted symbols has been proven");
+        assert_eq!(Reason::TimeLimit(Duration::from_secs(60)).describe(), "the time limit of 60s was reached");
+        assert_eq!(Reason::MaxExpansions(1000).describe(), "1000 candidates have been expanded");
+        assert_eq!(Reason::Interrupted.describe(), "the search was interrupted");
+        assert_eq!(Reason::Failed("disk full".to_string()).describe(), "the search failed: disk full");
+    }
+}
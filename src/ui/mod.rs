@@ -1,104 +1,414 @@
-use std::io::{prelude::*, stdin, stdout};
+use super::bounds::Bounds;
+use super::codec::Benchmark;
+use super::config::Settings;
+use super::disk::Scan;
+use super::enumerate::Enumeration;
+use super::proof::Phase;
+use super::reference::Mismatch;
+use super::search::Outcome;
+use super::status::Status;
+use super::stop::Reason;
+use super::verify::{Check, Report};
+use super::witness::Witnesses;
+
+use std::fmt::Arguments;
+use std::io::{prelude::*, stderr, stdin, stdout, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Like `print!`, but to stderr once `UI::use_stderr` has been called.
+#[macro_export]
+macro_rules! out {
+    ($($arg:tt)*) => { $crate::ui::UI::write(format_args!($($arg)*)) };
+}
+
+/// Like `println!`, but to stderr once `UI::use_stderr` has been called.
+#[macro_export]
+macro_rules! outln {
+    () => { $crate::out!("\n") };
+    ($($arg:tt)*) => { $crate::out!("{}\n", format_args!($($arg)*)) };
+}
 
 pub struct UI { }
 
 impl UI {
     pub fn print_introduction() {
-        println!();
-        println!(">---------------------------------------------------------------v");
-        println!("                                                                |");
-        println!("    Leaps and Bounds: A tool to find superpermutation bounds    |");
-        println!("         Written by Chris Patuzzo in 2018, MIT License          |");
-        println!("                                                                |");
-        println!("v---------------------------------------------------------------<");
-        println!("|");
-        println!("|");
-        println!("> What is it? --------------------------------------------------v");
-        println!("                                                                |");
-        println!("  This tool tries to find the maximum number of permutations    |");
-        println!("  that can fit into a string that 'wastes' N symbols. A symbol  |");
-        println!("  is said to be 'wasted' if it does not add a new permutation.  |");
-        println!("                                                                |");
-        println!("  For example, the string '1234123421' wastes two symbols.      |");
-        println!("                                  ^^                            |");
-        println!("                               these two                        |");
-        println!("                                                                |");
-        println!("  This is because '1234' has already appeared and '2342' is     |");
-        println!("  not a valid permutation because the '2' is repeated.          |");
-        println!("                                                                |");
-        println!("  Without wasting any symbols, the best we can do is fit four   |");
-        println!("  permutations in a string (e.g. 1234123). What about if        |");
-        println!("  we're allowed to waste one symbol? What about two? Three?     |");
-        println!("                                                                |");
-        println!("  That's what this tool tries to find out.                      |");
-        println!("                                                                |");
-        println!("                                                                |");
-        println!("v How does it work? <-------------------------------------------|");
-        println!("|                                                                ");
-        println!("| This tool works incrementally. It starts by finding the        ");
-        println!("| number of permutations that can fit into a string wasting no   ");
-        println!("| symbols, then moves on to one symbol, then two, etc.           ");
-        println!("|                                                                ");
-        println!("| As it does this, it accumulates information that is used to    ");
-        println!("| limit the regions of the search space to be explored and       ");
-        println!("| guide the search to regions more likely to yield results.      ");
-        println!("|                                                                ");
-        println!("| The way it actually works is quite complicated and is based    ");
-        println!("| on reasoning about upper and lower bounds. For more detail,    ");
-        println!("| check out the README in this repository.                       ");
-        println!("|                                                                ");
-        println!("| Also, check out this blog post, which was the inspiration      ");
-        println!("| for this tool: https://tinyurl.com/minimal-superpermutations   ");
-        println!("|                                                                ");
-        println!("|                                                                ");
-        println!("> How do I use it? ---------------------------------------------v");
-        println!("                                                                |");
-        println!("  You answer a few basic questions, then leave it running...    |");
-        println!("                                                                |");
-        println!("  ...and running ...and running                                 |");
-        println!("                                                                |");
-        println!("  For anything above five symbols, it's unlikely the tool will  |");
-        println!("  ever finish, but that's ok! We'll find out some things along  |");
-        println!("  the way that might be useful.                                 |");
-        println!("                                                                |");
-        println!("  The tool is very memory and disk hungry, so chances are       |");
-        println!("  you'll run out of space before long.                          |");
-        println!("                                                                |");
-        println!("                                                                |");
-        println!("v---------------------------------------------------------------<");
-        println!("|                                                                ");
+        outln!();
+        outln!(">---------------------------------------------------------------v");
+        outln!("                                                                |");
+        outln!("    Leaps and Bounds: A tool to find superpermutation bounds    |");
+        outln!("         Written by Chris Patuzzo in 2018, MIT License          |");
+        outln!("                                                                |");
+        outln!("v---------------------------------------------------------------<");
+        outln!("|");
+        outln!("|");
+        outln!("> What is it? --------------------------------------------------v");
+        outln!("                                                                |");
+        outln!("  This tool tries to find the maximum number of permutations    |");
+        outln!("  that can fit into a string that 'wastes' N symbols. A symbol  |");
+        outln!("  is said to be 'wasted' if it does not add a new permutation.  |");
+        outln!("                                                                |");
+        outln!("  For example, the string '1234123421' wastes two symbols.      |");
+        outln!("                                  ^^                            |");
+        outln!("                               these two                        |");
+        outln!("                                                                |");
+        outln!("  This is because '1234' has already appeared and '2342' is     |");
+        outln!("  not a valid permutation because the '2' is repeated.          |");
+        outln!("                                                                |");
+        outln!("  Without wasting any symbols, the best we can do is fit four   |");
+        outln!("  permutations in a string (e.g. 1234123). What about if        |");
+        outln!("  we're allowed to waste one symbol? What about two? Three?     |");
+        outln!("                                                                |");
+        outln!("  That's what this tool tries to find out.                      |");
+        outln!("                                                                |");
+        outln!("                                                                |");
+        outln!("v How does it work? <-------------------------------------------|");
+        outln!("|                                                                ");
+        outln!("| This tool works incrementally. It starts by finding the        ");
+        outln!("| number of permutations that can fit into a string wasting no   ");
+        outln!("| symbols, then moves on to one symbol, then two, etc.           ");
+        outln!("|                                                                ");
+        outln!("| As it does this, it accumulates information that is used to    ");
+        outln!("| limit the regions of the search space to be explored and       ");
+        outln!("| guide the search to regions more likely to yield results.      ");
+        outln!("|                                                                ");
+        outln!("| The way it actually works is quite complicated and is based    ");
+        outln!("| on reasoning about upper and lower bounds. For more detail,    ");
+        outln!("| check out the README in this repository.                       ");
+        outln!("|                                                                ");
+        outln!("| Also, check out this blog post, which was the inspiration      ");
+        outln!("| for this tool: https://tinyurl.com/minimal-superpermutations   ");
+        outln!("|                                                                ");
+        outln!("|                                                                ");
+        outln!("> How do I use it? ---------------------------------------------v");
+        outln!("                                                                |");
+        outln!("  You answer a few basic questions, then leave it running...    |");
+        outln!("                                                                |");
+        outln!("  ...and running ...and running                                 |");
+        outln!("                                                                |");
+        outln!("  For anything above five symbols, it's unlikely the tool will  |");
+        outln!("  ever finish, but that's ok! We'll find out some things along  |");
+        outln!("  the way that might be useful.                                 |");
+        outln!("                                                                |");
+        outln!("  The tool is very memory and disk hungry, so chances are       |");
+        outln!("  you'll run out of space before long.                          |");
+        outln!("                                                                |");
+        outln!("                                                                |");
+        outln!("v---------------------------------------------------------------<");
+        outln!("|                                                                ");
     }
 
     pub fn print_running() {
-        println!("|");
-        println!("|");
-        println!("> Ok, here we go! --->>>");
+        outln!("|");
+        outln!("|");
+        outln!("> Ok, here we go! --->>>");
+    }
+
+    pub fn print_done(outcome: &Outcome) {
+        let n = outcome.n;
+        let waste = outcome.bounds.lower_bounds.len() - 1;
+        let factorial = outcome.bounds.max;
+
+        outln!("--->>> Done!");
+        outln!();
+        outln!("A maximum of {} wasted symbols can fit all {}! = {} permutations.", waste, n, factorial);
+
+        if let Some(length) = outcome.superpermutation_length {
+            outln!("The shortest superpermutation contains {} + {} + {} = {} symbols.", n - 1, factorial, waste, length);
+        }
+
+        if let Some(superpermutation) = &outcome.superpermutation {
+            outln!();
+            outln!("{}", Witnesses::format(superpermutation));
+        }
+
+        let offloads = &outcome.statistics.offloads;
+
+        if offloads.offloads > 0 {
+            outln!();
+            outln!("The '{}' policy offloaded {} candidates in {} buckets {} times and {} of them were read back.",
+                     offloads.policy, offloads.candidates, offloads.buckets, offloads.offloads, offloads.onloaded);
+        }
+
+        if let Some(rate) = outcome.statistics.prefetches.hit_rate() {
+            let prefetches = &outcome.statistics.prefetches;
+
+            outln!();
+            outln!("Prefetching read {} of {} chunks from disk before they were needed ({:.1}%).",
+                     prefetches.hits, prefetches.hits + prefetches.misses, rate * 100.);
+        }
+
+        outln!();
+    }
+
+    pub fn print_contradictions(outcome: &Outcome) {
+        let contradictions = outcome.contradictions();
+
+        if contradictions.is_empty() {
+            return;
+        }
+
+        eprintln!("!!! The search disagrees with the known results for n = {} !!!", outcome.n);
+        eprintln!();
+
+        for mark in contradictions {
+            eprintln!("  {} permutations for {} wasted symbols {}", mark.permutations, mark.waste, mark.comparison.describe());
+        }
+
+        eprintln!();
+    }
+
+    pub fn print_stopped(reason: &Reason, outcome: &Outcome) {
+        let statistics = &outcome.statistics;
+
+        Self::clear_line();
+        outln!();
+        outln!("> Stopped after {} because {} --->>>", Status::format_duration(statistics.elapsed), reason.describe());
+        outln!();
+
+        Self::print_bounds(&outcome.bounds);
+
+        outln!("  Frontier: {} enabled, {} disabled in {} pruned buckets, {} on disk",
+                 statistics.enabled,
+                 statistics.disabled,
+                 statistics.disabled_buckets,
+                 Status::format_bytes(statistics.disk_bytes));
+        outln!("  Expanded: {} candidates", statistics.expansions);
+
+        if statistics.offloads.offloads > 0 {
+            let offloads = &statistics.offloads;
+
+            outln!("  Offloads: {} candidates in {} buckets over {} offloads ('{}' policy), {} read back",
+                     offloads.candidates, offloads.buckets, offloads.offloads, offloads.policy, offloads.onloaded);
+        }
+
+        if let Some(rate) = statistics.prefetches.hit_rate() {
+            outln!("  Prefetch: {} hits, {} misses ({:.1}% hit rate), {} unused",
+                     statistics.prefetches.hits, statistics.prefetches.misses, rate * 100., statistics.prefetches.unused);
+        }
+
+        outln!();
+    }
+
+    pub fn print_report(string: &[u8], report: &Report) {
+        let markers: String = (0..string.len())
+            .map(|i| match report.wasted.contains(&i) { true => '^', false => ' ' })
+            .collect();
+
+        let permutations: Vec<_> = report.permutations.iter().map(|p| Witnesses::format(p)).collect();
+        let wasted: Vec<_> = report.wasted.iter().map(|i| (i + 1).to_string()).collect();
+
+        outln!("  {}", Witnesses::format(string));
+        outln!("  {}", markers.trim_end());
+        outln!();
+        outln!("  {} symbols: {} permutations and {} wasted symbols in a string of length {}",
+                 report.n, permutations.len(), report.waste(), string.len());
+        outln!();
+        outln!("  Permutations: {}", permutations.join(" "));
+        outln!("  Wasted at:    {}", wasted.join(" "));
+    }
+
+    pub fn print_checks(checks: &[Check]) {
+        for check in checks {
+            let entry = &check.entry;
+            out!("  line {}: {} wasted symbols, {} permutations ... ", check.line, entry.waste, entry.permutations);
+
+            match &check.report {
+                None => outln!("no witness"),
+                Some(_) if check.passed() => outln!("ok"),
+                Some(report) => outln!("FAILED (the witness has {} permutations and {} wasted symbols)",
+                                         report.permutations.len(), report.waste()),
+            }
+        }
+
+        let passed = checks.iter().filter(|c| c.passed()).count();
+
+        outln!();
+        outln!("  {} of {} lines have a witness that achieves their bound.", passed, checks.len());
+    }
+
+    pub fn print_scan(path: &str, scan: &Scan) {
+        outln!("> Chunks in {} --->>>", path);
+        outln!();
+        outln!("  waste | permutations | chunks | candidates | size");
+        outln!("  ------+--------------+--------+------------+-----");
+
+        for bucket in &scan.buckets {
+            let candidates = bucket.candidates.map_or("?".to_string(), |c| c.to_string());
+
+            outln!("  {:>5} | {:>12} | {:>6} | {:>10} | {}",
+                     bucket.waste, bucket.permutations, bucket.chunks, candidates, Status::format_bytes(bucket.bytes));
+        }
+
+        let chunks: usize = scan.buckets.iter().map(|b| b.chunks).sum();
+
+        outln!();
+        outln!("  {} chunks in {} buckets, {}", chunks, scan.buckets.len(), Status::format_bytes(scan.bytes()));
+
+        for filename in &scan.orphaned {
+            outln!("  orphaned: {}", filename);
+        }
+
+        for filename in &scan.missing {
+            outln!("  missing: {}", filename);
+        }
+
+        for (filename, error) in &scan.unreadable {
+            outln!("  unreadable: {} ({})", filename, error);
+        }
+
+        outln!();
+    }
+
+    pub fn print_benchmarking(filename: &str, candidates: usize) {
+        outln!("> Measuring the codecs on {} candidates from {} --->>>", candidates, filename);
+        outln!();
+    }
+
+    pub fn print_benchmarks(benchmarks: &[Benchmark]) {
+        outln!("  codec   | size      | ratio | encode       | decode");
+        outln!("  --------+-----------+-------+--------------+-------------");
+
+        for b in benchmarks {
+            outln!("  {:<7} | {:>9} | {:>5.2} | {:>7.1}MiB/s | {:>7.1}MiB/s",
+                     b.codec.to_string(), Status::format_bytes(b.bytes as u64), b.ratio(), b.encode_throughput(), b.decode_throughput());
+        }
+
+        outln!();
+        outln!("  The ratio and speeds are relative to {} of raw candidates.", Status::format_bytes(benchmarks[0].raw_bytes as u64));
+        outln!();
+    }
+
+    pub fn print_migrated(path: &str, filenames: &[String]) {
+        outln!("> Migrating the chunks in {} --->>>", path);
+        outln!();
+
+        for filename in filenames {
+            outln!("  migrated: {}", filename);
+        }
+
+        match filenames.len() {
+            0 => outln!("  Every chunk already has a header."),
+            1 => outln!("  Added a header to 1 chunk."),
+            count => outln!("  Added headers to {} chunks.", count),
+        }
+
+        outln!();
+    }
+
+    pub fn print_enumeration(enumeration: &Enumeration) {
+        outln!("> Every string with {} permutations and {} wasted symbols --->>>", enumeration.permutations, enumeration.waste);
+        outln!();
+
+        for string in &enumeration.strings {
+            outln!("  {}", Witnesses::format(string));
+        }
+
+        outln!();
+        outln!("  {} strings up to relabeling, {} up to relabeling and reversal.", enumeration.found, enumeration.strings.len());
+        outln!();
+    }
+
+    pub fn print_proof(phases: &[Phase]) {
+        for phase in phases {
+            out!("  {} wasted symbols: {} permutations ... ", phase.waste, phase.permutations);
+
+            match phase {
+                Phase { completed: true, .. } => outln!("superpermutation"),
+                Phase { seeded: true, .. } => outln!("seeded"),
+                Phase { claimed: true, .. } => outln!("ok, but relies on a claimed bound"),
+                Phase { ruled_out, .. } => outln!("ok ({} pruned buckets ruled out)", ruled_out),
+            }
+        }
+
+        outln!();
+        outln!("  No bucket that could beat a bound was left unexplored.");
+    }
+
+    pub fn print_selftest(n: usize, source: &str, reference: &[usize], mismatch: &Option<Mismatch>) {
+        let describe = |bound: Option<usize>| match bound {
+            Some(permutations) => format!("{} permutations", permutations),
+            None => "no bound".to_string(),
+        };
+
+        match mismatch {
+            None => outln!("> n = {}: the search agrees with the {} on all {} bounds --->>>", n, source, reference.len()),
+            Some(m) => outln!("> n = {}: MISMATCH at {} wasted symbols: the {} has {} but the search found {} --->>>",
+                                n, m.waste, source, describe(m.reference), describe(m.search)),
+        }
+
+        outln!();
+    }
+
+    pub fn print_seeded(path: &str, bounds: &Bounds) {
+        outln!("> Starting from the bounds in {} --->>>", path);
+        outln!();
+
+        Self::print_bounds(bounds);
+    }
+
+    pub fn print_resumed(scratch: &str, bounds: &Bounds) {
+        outln!("> Resuming from the checkpoint in {} --->>>", scratch);
+        outln!();
+
+        Self::print_bounds(bounds);
+    }
+
+    pub fn print_bounds(bounds: &Bounds) {
+        let proven = bounds.proven();
+
+        outln!("  waste | permutations");
+        outln!("  ------+-------------");
+
+        for (waste, permutations) in proven.iter().enumerate() {
+            outln!("  {:>5} | {}", waste, permutations);
+        }
+
+        let last = bounds.lower_bounds.len() - 1;
+
+        for waste in proven.len()..=last {
+            let lower = bounds.lower_bounds[waste];
+            let upper = bounds.upper(waste);
+
+            match bounds.claimed.contains(&waste) {
+                true => outln!("  {:>5} | {} (claimed)", waste, lower),
+                false if waste < last => outln!("  {:>5} | {} (not proven)", waste, lower),
+                false => outln!("  {:>5} | {}..={} (not proven)", waste, lower, upper),
+            }
+        }
+
+        outln!();
     }
 
     pub fn ask_for_n() -> usize {
-        let input = Self::prompt("How many symbols should the string contain?", "5");
-        Self::parse_integer(&input)
+        Self::ask("How many symbols should the string contain?", "5", |input| Settings::parse_value("n", input))
     }
 
     pub fn ask_for_memory() -> f64 {
-        let input = Self::prompt("How many gigabytes of memory may this tool use?", "12");
-        Self::parse_float(&input)
+        Self::ask("How many gigabytes of memory may this tool use?", "12", |input| Settings::parse_value("memory", input))
     }
 
     pub fn ask_for_gzip() -> bool {
-        let input = Self::prompt("Do you want to gzip scratch files to save space?", "no");
-        Self::parse_boolean(&input)
+        Self::ask("Do you want to gzip scratch files to save space?", "no", |input| Settings::parse_boolean("gzip", input))
     }
 
     pub fn ask_for_verbose() -> bool {
-        let input = Self::prompt("Do you want to print verbose output?", "no");
-        Self::parse_boolean(&input)
+        Self::ask("Do you want to print verbose output?", "no", |input| Settings::parse_boolean("verbose", input))
+    }
+
+    pub fn interactive() -> bool {
+        stdin().is_terminal()
     }
 
     fn prompt(question: &'static str, default: &'static str) -> String {
-        println!("|\n| {} (default: {})", question, default);
+        if !Self::interactive() {
+            return default.to_string();
+        }
 
-        print!(">>> ");
+        outln!("|\n| {} (default: {})", question, default);
+
+        out!(">>> ");
         Self::flush();
 
         let mut input = String::new();
@@ -113,25 +423,50 @@ impl UI {
         }
     }
 
-    pub fn flush() {
-        stdout().flush().ok().expect("Failed to flush stdout.");
+    // Asks the question again until the answer parses the same way it would
+    // in a config file.
+    fn ask<T>(question: &'static str, default: &'static str, parse: impl Fn(&str) -> Result<T, String>) -> T {
+        loop {
+            match parse(Self::prompt(question, default).trim()) {
+                Ok(value) => return value,
+                Err(error) => outln!("| Please try again: {}.", error),
+            }
+        }
     }
 
-    fn parse_integer(input: &str) -> usize {
-        input.trim().parse().expect("Failed to parse integer.")
+    /// Sends everything the UI prints to stderr, so that stdout can be kept
+    /// for the events stream.
+    pub fn use_stderr() {
+        TO_STDERR.store(true, Ordering::Relaxed);
     }
 
-    fn parse_float(input: &str) -> f64 {
-        input.trim().parse().expect("Failed to parse float.")
+    /// Whether what the UI prints ends up on a terminal.
+    pub fn terminal() -> bool {
+        match TO_STDERR.load(Ordering::Relaxed) {
+            true => stderr().is_terminal(),
+            false => stdout().is_terminal(),
+        }
     }
 
-    fn parse_boolean(input: &str) -> bool {
-        match input.to_lowercase().trim() {
-            "y" => true,
-            "n" => false,
-            "yes" => true,
-            "no" => false,
-            _ => panic!("Failed to parse boolean."),
+    pub fn write(arguments: Arguments) {
+        let result = match TO_STDERR.load(Ordering::Relaxed) {
+            true => stderr().write_fmt(arguments),
+            false => stdout().write_fmt(arguments),
+        };
+
+        result.expect("Failed to write output.");
+    }
+
+    pub fn clear_line() {
+        if Self::terminal() {
+            out!("\r\x1b[2K");
+        }
+    }
+
+    pub fn flush() {
+        match TO_STDERR.load(Ordering::Relaxed) {
+            true => stderr().flush().expect("Failed to flush stderr."),
+            false => stdout().flush().expect("Failed to flush stdout."),
         }
     }
 }
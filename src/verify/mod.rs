@@ -0,0 +1,119 @@
+use super::bounds::{Bounds, Entry};
+
+use std::collections::HashSet;
+use std::fs::read_to_string;
+
+/// What a string over n symbols contains, worked out directly from the string.
+///
+/// This deliberately shares no code with the search. Every symbol after the
+/// first n - 1 either completes a permutation that hasn't been seen before or
+/// is wasted, so the length is always `n - 1 + permutations + waste`. This is
+/// the rule `Candidate::expand_one` applies, except that the search also
+/// charges early for wasted symbols it knows are coming, so a candidate never
+/// reports less waste than its string has.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub n: usize,
+    /// The distinct permutations in the order they first appear.
+    pub permutations: Vec<Vec<u8>>,
+    /// The zero-based positions of the wasted symbols.
+    pub wasted: Vec<usize>,
+}
+
+/// The result of checking one line of a bounds file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub line: usize,
+    pub entry: Entry,
+    /// The report for the witness string, or none if the line doesn't have one.
+    pub report: Option<Report>,
+}
+
+pub fn verify(string: &[u8], n: usize) -> Result<Report, String> {
+    if n < 2 {
+        return Err(format!("n must be at least 2 (got {})", n));
+    }
+
+    if let Some(&symbol) = string.iter().find(|&&s| s as usize >= n) {
+        return Err(format!("the string contains symbol {} which is not one of the {} symbols", symbol + 1, n));
+    }
+
+    let mut seen = HashSet::new();
+    let mut permutations = vec![];
+    let mut wasted = vec![];
+
+    for end in n - 1..string.len() {
+        let window = &string[end + 1 - n..=end];
+
+        match is_permutation(window) && seen.insert(window) {
+            true => permutations.push(window.to_vec()),
+            false => wasted.push(end),
+        }
+    }
+
+    Ok(Report { n, permutations, wasted })
+}
+
+pub fn verify_bounds(path: &str, n: usize) -> Result<Vec<Check>, Vec<String>> {
+    let text = read_to_string(path)
+        .map_err(|e| vec![format!("{}: {}", path, e)])?;
+
+    let mut checks = vec![];
+    let mut errors = vec![];
+
+    for (line, entry) in Bounds::entries(&text) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => { errors.push(format!("{}: line {}: {}", path, line, error)); continue },
+        };
+
+        let report = match &entry.witness {
+            Some(witness) => match verify(witness, n) {
+                Ok(report) => Some(report),
+                Err(error) => { errors.push(format!("{}: line {}: {}", path, line, error)); continue },
+            },
+            None => None,
+        };
+
+        checks.push(Check { line, entry, report });
+    }
+
+    match errors.is_empty() {
+        true => Ok(checks),
+        false => Err(errors),
+    }
+}
+
+impl Report {
+    pub fn waste(&self) -> usize {
+        self.wasted.len()
+    }
+
+    /// Whether the string fits at least this many permutations into at most
+    /// this many wasted symbols.
+    pub fn achieves(&self, waste: usize, permutations: usize) -> bool {
+        self.waste() <= waste && self.permutations.len() >= permutations
+    }
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        match &self.report {
+            Some(report) => report.achieves(self.entry.waste, self.entry.permutations),
+            None => false,
+        }
+    }
+}
+
+fn is_permutation(window: &[u8]) -> bool {
+    let mut present = vec![false; window.len()];
+
+    window.iter().all(|&s| {
+        let first = !present[s as usize];
+        present[s as usize] = true;
+        first
+    })
+}
+
+#[cfg(test)]
+mod test;
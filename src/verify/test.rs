@@ -0,0 +1,141 @@
+use super::*;
+use super::super::witness::Witnesses;
+use std::fs::{create_dir_all, write};
+
+fn string(text: &str) -> Vec<u8> {
+    Witnesses::parse(text).unwrap()
+}
+
+mod verify {
+    use super::*;
+
+    #[test]
+    fn it_finds_every_permutation_in_a_superpermutation() {
+        let report = verify(&string("123121321"), 3).unwrap();
+
+        assert_eq!(report.permutations, vec![
+            vec![0, 1, 2], vec![1, 2, 0], vec![2, 0, 1],
+            vec![1, 0, 2], vec![0, 2, 1], vec![2, 1, 0],
+        ]);
+
+        assert_eq!(report.wasted, &[5]);
+        assert_eq!(report.waste(), 1);
+    }
+
+    #[test]
+    fn it_wastes_symbols_that_repeat_a_permutation_or_do_not_make_one() {
+        // The example from the introduction: '1234' appears again and '2342'
+        // has a repeated symbol.
+        let report = verify(&string("1234123421"), 4).unwrap();
+
+        assert_eq!(report.permutations.len(), 5);
+        assert_eq!(report.wasted, &[7, 8]);
+    }
+
+    #[test]
+    fn it_does_not_count_the_first_symbols_as_wasted() {
+        let report = verify(&string("12"), 3).unwrap();
+
+        assert_eq!(report.permutations.len(), 0);
+        assert_eq!(report.waste(), 0);
+    }
+
+    #[test]
+    fn it_satisfies_the_length_equation() {
+        let text = "123412314231243121342132413214321";
+        let report = verify(&string(text), 4).unwrap();
+
+        assert_eq!(report.permutations.len(), 24);
+        assert_eq!(report.waste(), 6);
+        assert_eq!(text.len(), 3 + 24 + 6);
+    }
+
+    #[test]
+    fn it_never_finds_more_waste_than_the_candidates_of_the_search() {
+        use super::super::super::candidate::Candidate;
+
+        let mut candidate = Candidate::seed(5);
+
+        for &symbol in &[0, 1, 2, 3, 4, 0, 1, 3, 2, 4, 0, 2] {
+            candidate = candidate.expand(usize::MAX, 5, true).find(|c| *c.tail_of_string.last().unwrap() == symbol).unwrap();
+        }
+
+        let report = verify(&candidate.string(5), 5).unwrap();
+
+        assert_eq!(report.permutations.len(), 8);
+        assert_eq!(report.waste(), 5);
+
+        assert_eq!(candidate.number_of_permutations(), 8);
+        assert_eq!(candidate.wasted_symbols, 8);
+    }
+
+    #[test]
+    fn it_rejects_symbols_that_are_out_of_range() {
+        assert_eq!(verify(&string("1234"), 3), Err("the string contains symbol 4 which is not one of the 3 symbols".to_string()));
+        assert_eq!(verify(&string("1"), 1), Err("n must be at least 2 (got 1)".to_string()));
+    }
+}
+
+mod achieves {
+    use super::*;
+
+    #[test]
+    fn it_checks_the_string_fits_enough_permutations_into_the_waste() {
+        let report = verify(&string("1231213"), 3).unwrap();
+
+        assert_eq!(report.permutations.len(), 4);
+        assert_eq!(report.waste(), 1);
+
+        assert!(report.achieves(1, 4));
+        assert!(report.achieves(2, 4));
+        assert!(!report.achieves(1, 5));
+        assert!(!report.achieves(0, 4));
+    }
+}
+
+mod verify_bounds {
+    use super::*;
+
+    fn file(name: &str, text: &str) -> String {
+        create_dir_all("/tmp/superpermutation-test").unwrap();
+
+        let path = format!("/tmp/superpermutation-test/{}", name);
+        write(&path, text).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn it_checks_the_witness_on_each_line() {
+        let path = file("verify-1.bounds", "0 = 3 proven 12312\n1 = 6 claimed 123121321\n1 = 7 claimed 123121321\n2 = 6 claimed\n");
+        let checks = verify_bounds(&path, 3).unwrap();
+
+        let results: Vec<_> = checks.iter().map(|c| (c.line, c.passed())).collect();
+        assert_eq!(results, vec![(1, true), (2, true), (3, false), (4, false)]);
+
+        assert_eq!(checks[3].report, None);
+    }
+
+    #[test]
+    fn it_checks_a_witnesses_file_that_also_reads_as_bounds() {
+        let text = "0 = 2 claimed 1231\n0 = 3 claimed 12312\n1 = 4 claimed 1231213\n1 = 6 claimed 123121321\n";
+        let path = file("verify-3.bounds", text);
+
+        let checks = verify_bounds(&path, 3).unwrap();
+        assert!(checks.iter().all(|c| c.passed()));
+
+        let bounds = Bounds::read(&path, 3).unwrap();
+        assert_eq!(bounds.lower_bounds, &[3, 6]);
+    }
+
+    #[test]
+    fn it_reports_lines_it_cannot_read() {
+        let path = file("verify-2.bounds", "0 = 3 proven 12312\n1 = six claimed\n2 = 6 claimed 1239\n");
+        let errors = verify_bounds(&path, 3).unwrap_err();
+
+        assert_eq!(errors, vec![
+            format!("{}: line 2: invalid permutations 'six'", path),
+            format!("{}: line 3: the string contains symbol 9 which is not one of the 3 symbols", path),
+        ]);
+    }
+}
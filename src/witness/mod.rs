@@ -0,0 +1,50 @@
+use super::checkpoint;
+use super::observer::{Observer, Snapshot};
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+const SYMBOLS: &[u8] = b"123456789abcdefghijk";
+
+/// Saves a string that achieves each new lower bound, one per line.
+///
+/// Lines look like `waste = permutations claimed string` so that the file can
+/// be verified or used as a bounds file, where a later line for the same waste
+/// replaces an earlier one. The symbols of the string are written as 1-9 and
+/// then a-k for n above nine.
+pub struct Witnesses {
+    writer: Box<dyn Write + Send>,
+}
+
+impl Witnesses {
+    pub fn open(path: &str) -> Self {
+        let file = File::create(path).unwrap_or_else(|_| panic!("Failed to create {}", path));
+        Self::to(Box::new(BufWriter::new(file)))
+    }
+
+    pub fn append(path: &str) -> Self {
+        Self::to(Box::new(BufWriter::new(checkpoint::append(path))))
+    }
+
+    pub fn to(writer: Box<dyn Write + Send>) -> Self {
+        Self { writer }
+    }
+
+    pub fn format(string: &[u8]) -> String {
+        string.iter().map(|&s| SYMBOLS[s as usize] as char).collect()
+    }
+
+    pub fn parse(text: &str) -> Option<Vec<u8>> {
+        text.bytes().map(|c| SYMBOLS.iter().position(|&s| s == c).map(|s| s as u8)).collect()
+    }
+}
+
+impl Observer for Witnesses {
+    fn witness_found(&mut self, waste: usize, permutations: usize, witness: &[u8], _: &Snapshot) {
+        writeln!(self.writer, "{} = {} claimed {}", waste, permutations, Self::format(witness)).expect("Failed to write witness.");
+        self.writer.flush().expect("Failed to flush witnesses.");
+    }
+}
+
+#[cfg(test)]
+mod test;
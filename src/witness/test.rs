@@ -0,0 +1,62 @@
+use super::*;
+use std::sync::{Arc, Mutex};
+
+type Subject = Witnesses;
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> ::std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+mod format {
+    use super::*;
+
+    #[test]
+    fn it_writes_the_symbols_from_one() {
+        assert_eq!(Subject::format(&[0, 1, 2, 0, 1]), "12312");
+    }
+
+    #[test]
+    fn it_uses_letters_after_nine() {
+        assert_eq!(Subject::format(&[8, 9, 10, 19]), "9abk");
+    }
+}
+
+mod parse {
+    use super::*;
+
+    #[test]
+    fn it_reads_the_symbols_back() {
+        assert_eq!(Subject::parse("123a"), Some(vec![0, 1, 2, 9]));
+    }
+
+    #[test]
+    fn it_returns_none_for_unknown_symbols() {
+        assert_eq!(Subject::parse("120"), None);
+        assert_eq!(Subject::parse("1 2"), None);
+    }
+}
+
+mod witness_found {
+    use super::*;
+
+    #[test]
+    fn it_writes_one_line_per_witness() {
+        let buffer = Buffer::default();
+        let mut subject = Subject::to(Box::new(buffer.clone()));
+
+        subject.witness_found(0, 3, &[0, 1, 2, 0, 1], &Snapshot::default());
+        subject.witness_found(1, 6, &[0, 1, 2, 0, 1, 0, 2, 1, 0], &Snapshot::default());
+
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(text, "0 = 3 claimed 12312\n1 = 6 claimed 123121321\n");
+    }
+}